@@ -0,0 +1,54 @@
+function fibonacci(n) {
+  let [a, b] = [0, 1];
+  for (let i = 0; i < n; i++) {
+    [a, b] = [b, a + b];
+  }
+  return a;
+}
+
+class Point {
+  constructor(x, y) {
+    this.x = x;
+    this.y = y;
+  }
+
+  distance(other) {
+    const dx = this.x - other.x;
+    const dy = this.y - other.y;
+    return Math.sqrt(dx * dx + dy * dy);
+  }
+}
+
+const wordCounts = (text) => {
+  const counts = new Map();
+  for (const word of text.split(/\s+/)) {
+    counts.set(word, (counts.get(word) ?? 0) + 1);
+  }
+  return counts;
+};
+
+async function fetchJson(url) {
+  const response = await fetch(url);
+  if (!response.ok) {
+    throw new Error(`Request failed: ${response.status}`);
+  }
+  return response.json();
+}
+
+function debounce(fn, delay) {
+  let timer = null;
+  return (...args) => {
+    clearTimeout(timer);
+    timer = setTimeout(() => fn(...args), delay);
+  };
+}
+
+const total = items
+  .filter((item) => item.active)
+  .map((item) => item.price * item.quantity)
+  .reduce((sum, value) => sum + value, 0);
+
+document.querySelector("#save").addEventListener("click", (event) => {
+  event.preventDefault();
+  localStorage.setItem("draft", JSON.stringify(state));
+});
@@ -0,0 +1,48 @@
+def fibonacci(n):
+    a, b = 0, 1
+    for _ in range(n):
+        a, b = b, a + b
+    return a
+
+class Point:
+    def __init__(self, x, y):
+        self.x = x
+        self.y = y
+
+    def distance(self, other):
+        dx = self.x - other.x
+        dy = self.y - other.y
+        return (dx ** 2 + dy ** 2) ** 0.5
+
+def word_counts(text):
+    counts = {}
+    for word in text.split():
+        counts[word] = counts.get(word, 0) + 1
+    return counts
+
+def parse_port(value):
+    try:
+        port = int(value.strip())
+    except ValueError as exc:
+        raise ValueError(f"invalid port {value!r}") from exc
+    if not 0 < port < 65536:
+        raise ValueError("port out of range")
+    return port
+
+def binary_search(items, target):
+    low, high = 0, len(items)
+    while low < high:
+        mid = (low + high) // 2
+        if items[mid] < target:
+            low = mid + 1
+        elif items[mid] > target:
+            high = mid
+        else:
+            return mid
+    return None
+
+with open("notes.txt", encoding="utf-8") as handle:
+    lines = [line.rstrip() for line in handle if line.strip()]
+    print(f"{len(lines)} non-empty lines")
+
+squares = {n: n * n for n in range(10) if n % 2 == 0}
@@ -4,6 +4,11 @@
 //! Configuration can be loaded from TOML files or created with sensible defaults.
 
 use crate::core::error::{Result, UtilError};
+use crate::core::types::{
+    CodeLanguage, Difficulty, Language, SchedulerAlgorithm, SessionContent, StorageBackend,
+    TestMode, TIME_LIMITS,
+};
+use crate::utils::fs::{self as safe_fs, FileLock};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -18,6 +23,12 @@ pub struct Config {
     pub defaults: DefaultsConfig,
     /// Learning mode configuration
     pub learning: LearningConfig,
+    /// Code snippet typing configuration
+    #[serde(default)]
+    pub code: CodeConfig,
+    /// Typing goals shown on the results screen
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub goals: Vec<GoalConfig>,
 }
 
 /// Path configuration for data files.
@@ -29,6 +40,20 @@ pub struct PathsConfig {
     pub highscore_file: PathBuf,
     /// Directory for learning sets
     pub learning_sets_dir: PathBuf,
+    /// Where scores and results are stored (json or sqlite)
+    #[serde(default = "default_storage")]
+    pub storage: String,
+    /// Path to the SQLite database (only used with `storage = "sqlite"`)
+    #[serde(default = "default_database_file")]
+    pub database_file: PathBuf,
+}
+
+fn default_storage() -> String {
+    "json".to_string()
+}
+
+fn default_database_file() -> PathBuf {
+    PathBuf::from("data/rut.sqlite3")
 }
 
 /// UI theme configuration.
@@ -57,6 +82,36 @@ pub struct DefaultsConfig {
     pub min_accuracy_for_highscore: f64,
     /// Maximum number of highscores to keep
     pub max_highscores: usize,
+    /// Default test mode (words, time, code or adaptive)
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    /// Time limit in seconds for timed tests (15, 30, 60 or 120)
+    #[serde(default = "default_time_limit")]
+    pub time_limit: u64,
+    /// Custom text source (file, directory or `-` for stdin) instead of the word files
+    #[serde(default)]
+    pub source: Option<PathBuf>,
+    /// How text is taken from a custom source (auto, sentences or random)
+    #[serde(default = "default_source_order")]
+    pub source_order: String,
+    /// Only use words with these parts of speech (empty = all)
+    #[serde(default)]
+    pub parts_of_speech: Vec<String>,
+    /// Only use words from this frequency band (common, medium or rare)
+    #[serde(default)]
+    pub frequency: Option<String>,
+}
+
+fn default_source_order() -> String {
+    "auto".to_string()
+}
+
+fn default_mode() -> String {
+    "words".to_string()
+}
+
+fn default_time_limit() -> u64 {
+    30
 }
 
 /// Learning mode configuration.
@@ -68,6 +123,81 @@ pub struct LearningConfig {
     pub spaced_repetition: bool,
     /// Number of Leitner boxes
     pub leitner_boxes: usize,
+    /// Review interval of each box in days; boxes past the end use the last
+    #[serde(default = "default_box_intervals")]
+    pub box_intervals: Vec<u32>,
+    /// Scheduling algorithm (leitner, sm2 or fsrs); sets can choose their own
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+    /// Share of cards FSRS aims to have remembered when they are due (0.7-0.99)
+    #[serde(default = "default_desired_retention")]
+    pub desired_retention: f64,
+    /// What a session asks (cards, questions or mixed)
+    #[serde(default = "default_session_content")]
+    pub session_content: String,
+}
+
+fn default_session_content() -> String {
+    "mixed".to_string()
+}
+
+fn default_algorithm() -> String {
+    "leitner".to_string()
+}
+
+fn default_desired_retention() -> f64 {
+    0.9
+}
+
+fn default_box_intervals() -> Vec<u32> {
+    vec![1, 2, 4, 8, 16]
+}
+
+/// Code snippet typing configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeConfig {
+    /// Programming language of the snippets (rust, python, javascript)
+    pub language: String,
+    /// Source file or directory to take snippets from (bundled corpus if unset)
+    #[serde(default)]
+    pub snippet_path: Option<PathBuf>,
+    /// Indentation style (spaces or tab)
+    pub indent_style: String,
+    /// Number of spaces per indentation level
+    pub indent_width: usize,
+    /// Insert the indentation of the next line automatically after Enter
+    pub auto_indent: bool,
+}
+
+/// A typing goal, e.g. 70 WPM at 97% accuracy on hard English.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalConfig {
+    /// Target net words per minute
+    pub wpm: f64,
+    /// Minimum accuracy in percent (0.0-100.0)
+    #[serde(default)]
+    pub accuracy: f64,
+    /// Only results in this language (de or en), any if unset
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Only results with this difficulty (easy, medium, hard), any if unset
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    /// Only results of this test mode (words, time, code or adaptive), any if unset
+    #[serde(default)]
+    pub mode: Option<String>,
+}
+
+impl Default for CodeConfig {
+    fn default() -> Self {
+        Self {
+            language: "rust".to_string(),
+            snippet_path: None,
+            indent_style: "spaces".to_string(),
+            indent_width: 4,
+            auto_indent: true,
+        }
+    }
 }
 
 impl Default for Config {
@@ -77,6 +207,8 @@ impl Default for Config {
                 data_dir: PathBuf::from("data"),
                 highscore_file: PathBuf::from("data/highscores.json"),
                 learning_sets_dir: PathBuf::from("data/learning_sets"),
+                storage: default_storage(),
+                database_file: default_database_file(),
             },
             theme: ThemeConfig {
                 correct_color: "green".to_string(),
@@ -90,12 +222,24 @@ impl Default for Config {
                 difficulty: "medium".to_string(),
                 min_accuracy_for_highscore: 80.0,
                 max_highscores: 50,
+                mode: default_mode(),
+                time_limit: default_time_limit(),
+                source: None,
+                source_order: default_source_order(),
+                parts_of_speech: Vec::new(),
+                frequency: None,
             },
             learning: LearningConfig {
                 fuzzy_threshold: 0.85,
                 spaced_repetition: true,
                 leitner_boxes: 5,
+                box_intervals: default_box_intervals(),
+                algorithm: default_algorithm(),
+                desired_retention: default_desired_retention(),
+                session_content: default_session_content(),
             },
+            code: CodeConfig::default(),
+            goals: Vec::new(),
         }
     }
 }
@@ -124,19 +268,27 @@ impl Config {
 
     /// Save configuration to a TOML file.
     ///
+    /// The file is replaced atomically and the previous version is kept as
+    /// `<file>.bak`.
+    ///
     /// # Arguments
     ///
     /// * `path` - Path where the configuration should be saved
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be written.
+    /// Returns an error if the file cannot be written, or if an existing
+    /// file cannot be parsed (it is not overwritten so it can be repaired).
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
         let content = toml::to_string_pretty(self)
             .map_err(|e| UtilError::ConfigError(format!("Failed to serialize config: {}", e)))?;
 
-        std::fs::write(path, content)?;
-        Ok(())
+        let _lock = FileLock::acquire(path)?;
+        if path.exists() {
+            Self::load_from_file(path)?;
+        }
+        safe_fs::write_atomic_with_backup(path, content.as_bytes())
     }
 
     /// Load configuration from the default location or create default config.
@@ -169,6 +321,78 @@ impl Config {
             ));
         }
 
+        // Validate time limit
+        if !TIME_LIMITS.contains(&self.defaults.time_limit) {
+            return Err(UtilError::ConfigError(format!(
+                "time_limit must be one of {:?}",
+                TIME_LIMITS
+            )));
+        }
+
+        // Validate custom source order
+        if !matches!(self.defaults.source_order.as_str(), "auto" | "sentences" | "random") {
+            return Err(UtilError::ConfigError(
+                "source_order must be \"auto\", \"sentences\" or \"random\"".to_string(),
+            ));
+        }
+
+        // Validate frequency band
+        if let Some(frequency) = &self.defaults.frequency {
+            if !matches!(frequency.as_str(), "common" | "medium" | "rare") {
+                return Err(UtilError::ConfigError(
+                    "frequency must be \"common\", \"medium\" or \"rare\"".to_string(),
+                ));
+            }
+        }
+
+        // Validate storage backend
+        if self.paths.storage.parse::<StorageBackend>().is_err() {
+            return Err(UtilError::ConfigError(
+                "storage must be \"json\" or \"sqlite\"".to_string(),
+            ));
+        }
+
+        // Validate code snippet settings
+        if self.code.language.parse::<CodeLanguage>().is_err() {
+            return Err(UtilError::ConfigError(format!(
+                "Unknown code language: {}",
+                self.code.language
+            )));
+        }
+
+        if !matches!(self.code.indent_style.as_str(), "spaces" | "tab") {
+            return Err(UtilError::ConfigError(
+                "indent_style must be \"spaces\" or \"tab\"".to_string(),
+            ));
+        }
+
+        if self.code.indent_width == 0 || self.code.indent_width > 8 {
+            return Err(UtilError::ConfigError(
+                "indent_width must be between 1 and 8".to_string(),
+            ));
+        }
+
+        // Validate goals
+        for goal in &self.goals {
+            if goal.wpm.is_nan() || goal.wpm <= 0.0 {
+                return Err(UtilError::ConfigError("goal wpm must be positive".to_string()));
+            }
+            if !(0.0..=100.0).contains(&goal.accuracy) {
+                return Err(UtilError::ConfigError(
+                    "goal accuracy must be between 0.0 and 100.0".to_string(),
+                ));
+            }
+            if let Some(language) = &goal.language {
+                language.parse::<Language>().map_err(UtilError::ConfigError)?;
+            }
+            if let Some(difficulty) = &goal.difficulty {
+                difficulty.parse::<Difficulty>().map_err(UtilError::ConfigError)?;
+            }
+            if let Some(mode) = &goal.mode {
+                mode.parse::<TestMode>().map_err(UtilError::ConfigError)?;
+            }
+        }
+
         // Validate Leitner boxes count
         if self.learning.leitner_boxes < 2 || self.learning.leitner_boxes > 10 {
             return Err(UtilError::ConfigError(
@@ -176,6 +400,29 @@ impl Config {
             ));
         }
 
+        if self.learning.box_intervals.is_empty() || self.learning.box_intervals.contains(&0) {
+            return Err(UtilError::ConfigError(
+                "box_intervals must be at least one day each".to_string(),
+            ));
+        }
+
+        if self.learning.algorithm.parse::<SchedulerAlgorithm>().is_err() {
+            return Err(UtilError::ConfigError(
+                "algorithm must be \"leitner\", \"sm2\" or \"fsrs\"".to_string(),
+            ));
+        }
+
+        if !(0.7..=0.99).contains(&self.learning.desired_retention) {
+            return Err(UtilError::ConfigError(
+                "desired_retention must be between 0.7 and 0.99".to_string(),
+            ));
+        }
+
+        self.learning
+            .session_content
+            .parse::<SessionContent>()
+            .map_err(UtilError::ConfigError)?;
+
         Ok(())
     }
 
@@ -207,5 +454,69 @@ mod tests {
         config.learning.fuzzy_threshold = 0.85;
         config.learning.leitner_boxes = 20;
         assert!(config.validate().is_err());
+
+        config.learning.leitner_boxes = 5;
+        config.learning.box_intervals = vec![1, 0, 4];
+        assert!(config.validate().is_err());
+
+        config.learning.box_intervals = vec![1, 3];
+        config.learning.algorithm = "anki".to_string();
+        assert!(config.validate().is_err());
+
+        config.learning.algorithm = "fsrs".to_string();
+        config.learning.desired_retention = 1.0;
+        assert!(config.validate().is_err());
+
+        config.learning.desired_retention = 0.9;
+        config.learning.session_content = "flashcard".to_string();
+        assert!(config.validate().is_err());
+
+        config.learning.session_content = "quiz".to_string();
+        config.defaults.time_limit = 45;
+        assert!(config.validate().is_err());
+
+        config.defaults.time_limit = 30;
+        config.code.indent_style = "both".to_string();
+        assert!(config.validate().is_err());
+
+        config.code.indent_style = "spaces".to_string();
+        config.paths.storage = "postgres".to_string();
+        assert!(config.validate().is_err());
+
+        config.paths.storage = "json".to_string();
+        let goal = GoalConfig {
+            wpm: 70.0,
+            accuracy: 97.0,
+            language: Some("en".to_string()),
+            difficulty: Some("hard".to_string()),
+            mode: None,
+        };
+        config.goals = vec![goal.clone()];
+        assert!(config.validate().is_ok());
+
+        config.goals[0].difficulty = Some("extreme".to_string());
+        assert!(config.validate().is_err());
+
+        config.goals[0] = GoalConfig { accuracy: 120.0, ..goal };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_save_keeps_backup_and_damaged_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let mut config = Config::default();
+        config.save_to_file(&path).unwrap();
+        config.defaults.language = "de".to_string();
+        config.save_to_file(&path).unwrap();
+
+        assert_eq!(Config::load_from_file(&path).unwrap().defaults.language, "de");
+        let backup = Config::load_from_file(safe_fs::backup_path(&path)).unwrap();
+        assert_eq!(backup.defaults.language, "en");
+
+        std::fs::write(&path, "[paths").unwrap();
+        assert!(config.save_to_file(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[paths");
     }
 }
@@ -27,6 +27,18 @@ pub enum UtilError {
         difficulty: String,
     },
 
+    /// Error when loading code snippet files
+    #[error("Failed to load code snippets from {path}: {source}")]
+    SnippetLoadError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Error when no code snippets are available
+    #[error("No code snippets found for {language}")]
+    NoMatchingSnippets { language: String },
+
     /// Configuration-related errors
     #[error("Configuration error: {0}")]
     ConfigError(String),
@@ -55,6 +67,23 @@ pub enum UtilError {
     #[error("Highscore operation failed: {0}")]
     HighscoreError(String),
 
+    /// Error when a score fails the plausibility checks and is only kept in
+    /// the history
+    #[error("Not added to the highscores: {0}")]
+    ScoreRejected(String),
+
+    /// Error when creating, renaming or selecting a profile
+    #[error("Profile error: {0}")]
+    ProfileError(String),
+
+    /// Error when reading or writing the learning progress
+    #[error("Learning progress error: {0}")]
+    ProgressError(String),
+
+    /// Error when reading or importing a data bundle
+    #[error("Import failed: {0}")]
+    ImportError(String),
+
     /// Generic I/O errors
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -63,6 +92,10 @@ pub enum UtilError {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// SQLite database errors
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
     /// TOML parsing errors
     #[error("TOML error: {0}")]
     Toml(#[from] toml::de::Error),
@@ -5,9 +5,14 @@
 
 pub mod config;
 pub mod error;
+pub mod profile;
 pub mod types;
 
 // Re-export commonly used items
-pub use config::Config;
+pub use config::{Config, GoalConfig};
 pub use error::{Result, UtilError};
-pub use types::{Difficulty, Language};
+pub use profile::{Profile, ProfileStore};
+pub use types::{
+    CefrLevel, CodeLanguage, Difficulty, Language, SchedulerAlgorithm, SessionContent,
+    StorageBackend, TestMode,
+};
@@ -0,0 +1,413 @@
+//! Player profiles.
+//!
+//! Several people can share one installation: every profile has its own
+//! typing history and highscores, default language and difficulty, theme
+//! and goals. Profiles are listed in `profiles.json` in the data
+//! directory, and each profile keeps its data in `profiles/<dir>/` next to
+//! it.
+//!
+//! The first profile takes over the data files from before profiles existed,
+//! so upgrading keeps existing scores.
+
+use crate::core::config::{Config, GoalConfig, PathsConfig, ThemeConfig};
+use crate::core::error::{Result, UtilError};
+use crate::utils::fs::{self as safe_fs, FileLock};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the player when no profile has been created.
+pub const DEFAULT_PROFILE_NAME: &str = "Player";
+
+/// Maximum length of a profile name in characters.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// A named player profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// Display name, also recorded with every score
+    pub name: String,
+    /// Data directory below `profiles/`; `None` for the shared data files
+    /// from before profiles existed
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Default language (de or en)
+    pub language: String,
+    /// Default difficulty (easy, medium, hard)
+    pub difficulty: String,
+    /// UI theme
+    pub theme: ThemeConfig,
+    /// Typing goals of this player; the configured goals if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub goals: Vec<GoalConfig>,
+}
+
+impl Profile {
+    /// Create a profile with the settings of a configuration.
+    fn new(name: &str, dir: Option<String>, config: &Config) -> Self {
+        Self {
+            name: name.to_string(),
+            dir,
+            language: config.defaults.language.clone(),
+            difficulty: config.defaults.difficulty.clone(),
+            theme: config.theme.clone(),
+            goals: Vec::new(),
+        }
+    }
+
+    /// Take over the settings of the profile from a configuration.
+    pub fn update_settings(&mut self, config: &Config) {
+        self.language = config.defaults.language.clone();
+        self.difficulty = config.defaults.difficulty.clone();
+        self.theme = config.theme.clone();
+    }
+}
+
+/// Contents of `profiles.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProfileFile {
+    /// Name of the most recently selected profile
+    #[serde(default)]
+    last_used: Option<String>,
+    #[serde(default)]
+    profiles: Vec<Profile>,
+}
+
+/// The list of profiles and the one last selected.
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    file_path: PathBuf,
+    /// Paths of the configuration without a profile applied
+    base_paths: PathsConfig,
+    data: ProfileFile,
+}
+
+impl ProfileStore {
+    /// Load the profiles of the data directory of a configuration.
+    ///
+    /// The configuration must not have a profile applied yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the profile file exists but cannot be read.
+    pub fn for_config(config: &Config) -> Result<Self> {
+        let file_path = config.paths.data_dir.join("profiles.json");
+        let data = Self::read(&file_path)?;
+        Ok(Self {
+            file_path,
+            base_paths: config.paths.clone(),
+            data,
+        })
+    }
+
+    fn read(path: &Path) -> Result<ProfileFile> {
+        if !path.exists() {
+            return Ok(ProfileFile::default());
+        }
+        let content = std::fs::read(path)?;
+        serde_json::from_slice(&content).map_err(|e| {
+            UtilError::ProfileError(format!("Failed to parse {}: {}", path.display(), e))
+        })
+    }
+
+    /// Re-read the file under a lock, apply a change and write it back, so
+    /// changes of other processes are kept.
+    fn modify<T>(&mut self, change: impl FnOnce(&mut ProfileFile) -> Result<T>) -> Result<T> {
+        let _lock = FileLock::acquire(&self.file_path)?;
+        let mut data = Self::read(&self.file_path)?;
+        let result = change(&mut data)?;
+        safe_fs::write_atomic_with_backup(&self.file_path, &serde_json::to_vec_pretty(&data)?)?;
+        self.data = data;
+        Ok(result)
+    }
+
+    /// All profiles in the order they were created.
+    pub fn profiles(&self) -> &[Profile] {
+        &self.data.profiles
+    }
+
+    /// Find a profile by name (case-insensitive).
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        find(&self.data, name).map(|i| &self.data.profiles[i])
+    }
+
+    /// The most recently selected profile, or the first one.
+    pub fn current(&self) -> Option<&Profile> {
+        self.data
+            .last_used
+            .as_deref()
+            .and_then(|name| self.get(name))
+            .or_else(|| self.data.profiles.first())
+    }
+
+    /// Name to record with scores: the current profile, or
+    /// [`DEFAULT_PROFILE_NAME`] if there is none.
+    pub fn player_name(&self) -> String {
+        self.current()
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string())
+    }
+
+    /// Create a profile with the settings of `config` and select it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or already taken, or if the
+    /// profile file cannot be written.
+    pub fn create(&mut self, name: &str, config: &Config) -> Result<Profile> {
+        let name = check_name(name)?;
+        self.modify(|data| {
+            if find(data, &name).is_some() {
+                return Err(UtilError::ProfileError(format!("Profile {} already exists", name)));
+            }
+            // The first profile keeps the data from before profiles existed
+            let dir = if data.profiles.is_empty() {
+                None
+            } else {
+                Some(unique_dir(data, &name))
+            };
+            let profile = Profile::new(&name, dir, config);
+            data.profiles.push(profile.clone());
+            data.last_used = Some(name);
+            Ok(profile)
+        })
+    }
+
+    /// Rename a profile. Its data directory stays the same.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the profile does not exist, the new name is
+    /// invalid or taken, or the profile file cannot be written.
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<Profile> {
+        let new_name = check_name(new_name)?;
+        self.modify(|data| {
+            let index = find(data, name).ok_or_else(|| not_found(name))?;
+            if find(data, &new_name).is_some_and(|other| other != index) {
+                return Err(UtilError::ProfileError(format!("Profile {} already exists", new_name)));
+            }
+            if data.last_used.as_deref() == Some(data.profiles[index].name.as_str()) {
+                data.last_used = Some(new_name.clone());
+            }
+            data.profiles[index].name = new_name;
+            Ok(data.profiles[index].clone())
+        })
+    }
+
+    /// Select a profile as the current one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the profile does not exist or the profile file
+    /// cannot be written.
+    pub fn select(&mut self, name: &str) -> Result<Profile> {
+        self.modify(|data| {
+            let index = find(data, name).ok_or_else(|| not_found(name))?;
+            data.last_used = Some(data.profiles[index].name.clone());
+            Ok(data.profiles[index].clone())
+        })
+    }
+
+    /// Select a profile, creating it with the settings of `config` if it
+    /// does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the profile cannot be created or selected.
+    pub fn select_or_create(&mut self, name: &str, config: &Config) -> Result<Profile> {
+        if self.get(name).is_some() {
+            self.select(name)
+        } else {
+            self.create(name, config)
+        }
+    }
+
+    /// Save the settings of a profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the profile does not exist or the profile file
+    /// cannot be written.
+    pub fn update(&mut self, profile: &Profile) -> Result<()> {
+        self.modify(|data| {
+            let index = find(data, &profile.name).ok_or_else(|| not_found(&profile.name))?;
+            let dir = data.profiles[index].dir.clone();
+            data.profiles[index] = Profile {
+                dir,
+                ..profile.clone()
+            };
+            Ok(())
+        })
+    }
+
+    /// Directory with the data of a profile.
+    pub fn data_dir(&self, profile: &Profile) -> PathBuf {
+        match &profile.dir {
+            Some(dir) => self.base_paths.data_dir.join("profiles").join(dir),
+            None => self
+                .base_paths
+                .highscore_file
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| self.base_paths.data_dir.clone()),
+        }
+    }
+
+    /// The configuration with the data paths and settings of a profile.
+    ///
+    /// Word files and learning sets stay shared between profiles.
+    pub fn apply(&self, profile: &Profile, config: &Config) -> Config {
+        let mut config = config.clone();
+        config.paths.highscore_file = self.base_paths.highscore_file.clone();
+        config.paths.database_file = self.base_paths.database_file.clone();
+        if profile.dir.is_some() {
+            let dir = self.data_dir(profile);
+            for path in [&mut config.paths.highscore_file, &mut config.paths.database_file] {
+                if let Some(file_name) = path.file_name() {
+                    *path = dir.join(file_name);
+                }
+            }
+        }
+        config.defaults.language = profile.language.clone();
+        config.defaults.difficulty = profile.difficulty.clone();
+        config.theme = profile.theme.clone();
+        if !profile.goals.is_empty() {
+            config.goals = profile.goals.clone();
+        }
+        config
+    }
+}
+
+fn find(data: &ProfileFile, name: &str) -> Option<usize> {
+    let name = name.trim().to_lowercase();
+    data.profiles.iter().position(|p| p.name.to_lowercase() == name)
+}
+
+fn not_found(name: &str) -> UtilError {
+    UtilError::ProfileError(format!("No profile named {}", name))
+}
+
+/// Trim a profile name and check that it can be used.
+fn check_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(UtilError::ProfileError("Profile name must not be empty".to_string()));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(UtilError::ProfileError(format!(
+            "Profile name must be at most {} characters",
+            MAX_NAME_LENGTH
+        )));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(UtilError::ProfileError(
+            "Profile name must not contain control characters".to_string(),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// A directory name for a new profile that no other profile uses.
+fn unique_dir(data: &ProfileFile, name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-');
+    let slug = if slug.is_empty() { "profile" } else { slug };
+
+    let taken = |dir: &str| data.profiles.iter().any(|p| p.dir.as_deref() == Some(dir));
+    if !taken(slug) {
+        return slug.to_string();
+    }
+    (2..)
+        .map(|i| format!("{}-{}", slug, i))
+        .find(|dir| !taken(dir))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(dir: &TempDir) -> Config {
+        let mut config = Config::default();
+        config.paths.data_dir = dir.path().to_path_buf();
+        config.paths.highscore_file = dir.path().join("highscores.json");
+        config.paths.database_file = dir.path().join("rut.sqlite3");
+        config
+    }
+
+    #[test]
+    fn test_create_and_apply() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        let mut store = ProfileStore::for_config(&config).unwrap();
+        assert!(store.current().is_none());
+        assert_eq!(store.player_name(), DEFAULT_PROFILE_NAME);
+
+        // The first profile keeps the existing data files
+        let first = store.create("Tim", &config).unwrap();
+        assert_eq!(first.dir, None);
+        assert_eq!(store.apply(&first, &config).paths.highscore_file, config.paths.highscore_file);
+
+        let mut settings = config.clone();
+        settings.defaults.language = "de".to_string();
+        let second = store.create("Anna Lena", &settings).unwrap();
+        assert_eq!(second.dir.as_deref(), Some("anna-lena"));
+        assert_eq!(store.player_name(), "Anna Lena");
+
+        let applied = store.apply(&second, &config);
+        assert_eq!(applied.paths.highscore_file, dir.path().join("profiles/anna-lena/highscores.json"));
+        assert_eq!(applied.paths.database_file, dir.path().join("profiles/anna-lena/rut.sqlite3"));
+        assert_eq!(applied.paths.data_dir, config.paths.data_dir);
+        assert_eq!(applied.defaults.language, "de");
+
+        // Switching back restores the shared paths
+        let back = store.apply(&first, &applied);
+        assert_eq!(back.paths.highscore_file, config.paths.highscore_file);
+        assert_eq!(back.defaults.language, config.defaults.language);
+
+        assert!(store.create("tim", &config).is_err());
+        assert!(store.create("  ", &config).is_err());
+    }
+
+    #[test]
+    fn test_select_rename_and_persist() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        let mut store = ProfileStore::for_config(&config).unwrap();
+        store.create("Tim", &config).unwrap();
+        store.create("Anna", &config).unwrap();
+
+        store.select("tim").unwrap();
+        assert_eq!(store.player_name(), "Tim");
+
+        let renamed = store.rename("Tim", "Timothy").unwrap();
+        assert_eq!(renamed.dir, None);
+        assert!(store.rename("Anna", "timothy").is_err());
+        assert!(store.rename("Nobody", "Someone").is_err());
+
+        let mut anna = store.select_or_create("Anna", &config).unwrap();
+        anna.difficulty = "hard".to_string();
+        store.update(&anna).unwrap();
+
+        let reloaded = ProfileStore::for_config(&config).unwrap();
+        assert_eq!(reloaded.profiles().len(), 2);
+        assert_eq!(reloaded.player_name(), "Anna");
+        assert_eq!(reloaded.get("anna").unwrap().difficulty, "hard");
+        assert!(reloaded.get("Timothy").is_some());
+    }
+
+    #[test]
+    fn test_unique_dir() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        let mut store = ProfileStore::for_config(&config).unwrap();
+        store.create("First", &config).unwrap();
+        store.create("A B", &config).unwrap();
+        assert_eq!(store.create("a-b", &config).unwrap().dir.as_deref(), Some("a-b-2"));
+        assert_eq!(store.create("!!!", &config).unwrap().dir.as_deref(), Some("profile"));
+    }
+}
@@ -8,7 +8,7 @@ use std::fmt;
 use std::str::FromStr;
 
 /// Supported languages for typing tests and learning content.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Language {
     /// German language
@@ -62,7 +62,7 @@ impl FromStr for Language {
 }
 
 /// Difficulty levels for typing tests and learning content.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Difficulty {
     /// Easy difficulty (A1-A2 CEFR level, shorter words)
@@ -74,6 +74,15 @@ pub enum Difficulty {
 }
 
 impl Difficulty {
+    /// Returns the lowercase identifier used in config and data files.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+
     /// Returns the number of words for this difficulty level.
     pub fn word_count(&self) -> usize {
         match self {
@@ -84,11 +93,11 @@ impl Difficulty {
     }
 
     /// Returns the allowed CEFR levels for this difficulty.
-    pub fn allowed_cefr_levels(&self) -> &'static [&'static str] {
+    pub fn allowed_cefr_levels(&self) -> &'static [CefrLevel] {
         match self {
-            Difficulty::Easy => &["A1", "A2"],
-            Difficulty::Medium => &["A2", "B1", "B2"],
-            Difficulty::Hard => &["B2", "C1", "C2"],
+            Difficulty::Easy => &[CefrLevel::A1, CefrLevel::A2],
+            Difficulty::Medium => &[CefrLevel::A2, CefrLevel::B1, CefrLevel::B2],
+            Difficulty::Hard => &[CefrLevel::B2, CefrLevel::C1, CefrLevel::C2],
         }
     }
 
@@ -101,6 +110,15 @@ impl Difficulty {
         }
     }
 
+    /// Returns the maximum number of lines of a code snippet for this difficulty.
+    pub fn max_snippet_lines(&self) -> usize {
+        match self {
+            Difficulty::Easy => 6,
+            Difficulty::Medium => 12,
+            Difficulty::Hard => 24,
+        }
+    }
+
     /// Returns a human-readable description of this difficulty.
     pub fn description(&self) -> &'static str {
         match self {
@@ -135,8 +153,277 @@ impl FromStr for Difficulty {
     }
 }
 
+/// How a typing test is ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestMode {
+    /// Fixed number of words, ends when the whole text has been typed
+    #[default]
+    Words,
+    /// Countdown, ends when the time limit is reached
+    Time,
+    /// Source code snippet, ends when the whole snippet has been typed
+    Code,
+    /// Fixed number of words chosen to practise the weakest keys
+    Adaptive,
+}
+
+/// Time limits (in seconds) available for timed typing tests.
+pub const TIME_LIMITS: [u64; 4] = [15, 30, 60, 120];
+
+impl TestMode {
+    /// Returns the lowercase identifier used in config files and on the CLI.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TestMode::Words => "words",
+            TestMode::Time => "time",
+            TestMode::Code => "code",
+            TestMode::Adaptive => "adaptive",
+        }
+    }
+}
+
+impl fmt::Display for TestMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TestMode::Words => "Wörter/Words",
+            TestMode::Time => "Zeit/Time",
+            TestMode::Code => "Code",
+            TestMode::Adaptive => "Schwächen/Weaknesses",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TestMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "words" | "wörter" | "word" => Ok(TestMode::Words),
+            "time" | "zeit" | "timed" => Ok(TestMode::Time),
+            "code" | "snippet" | "snippets" => Ok(TestMode::Code),
+            "adaptive" | "practice" | "weaknesses" | "schwächen" => Ok(TestMode::Adaptive),
+            _ => Err(format!("Unknown test mode: {}", s)),
+        }
+    }
+}
+
+/// Programming languages available for code snippet tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeLanguage {
+    /// Rust source code
+    Rust,
+    /// Python source code
+    Python,
+    /// JavaScript source code
+    JavaScript,
+}
+
+impl CodeLanguage {
+    /// Returns the lowercase identifier used in config files and on the CLI.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CodeLanguage::Rust => "rust",
+            CodeLanguage::Python => "python",
+            CodeLanguage::JavaScript => "javascript",
+        }
+    }
+
+    /// Returns the file extensions of source files in this language.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            CodeLanguage::Rust => &["rs"],
+            CodeLanguage::Python => &["py"],
+            CodeLanguage::JavaScript => &["js", "mjs", "cjs"],
+        }
+    }
+
+    /// Detect the language from a file extension.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.to_lowercase();
+        [CodeLanguage::Rust, CodeLanguage::Python, CodeLanguage::JavaScript]
+            .into_iter()
+            .find(|lang| lang.extensions().contains(&extension.as_str()))
+    }
+}
+
+impl fmt::Display for CodeLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CodeLanguage::Rust => "Rust",
+            CodeLanguage::Python => "Python",
+            CodeLanguage::JavaScript => "JavaScript",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for CodeLanguage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rust" | "rs" => Ok(CodeLanguage::Rust),
+            "python" | "py" => Ok(CodeLanguage::Python),
+            "javascript" | "js" => Ok(CodeLanguage::JavaScript),
+            _ => Err(format!("Unknown code language: {}", s)),
+        }
+    }
+}
+
+/// Backends for storing scores and results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// JSON files next to each other in the data directory
+    #[default]
+    Json,
+    /// Embedded SQLite database
+    Sqlite,
+}
+
+impl StorageBackend {
+    /// Returns the lowercase identifier used in config files and on the CLI.
+    pub fn code(&self) -> &'static str {
+        match self {
+            StorageBackend::Json => "json",
+            StorageBackend::Sqlite => "sqlite",
+        }
+    }
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StorageBackend::Json => "JSON",
+            StorageBackend::Sqlite => "SQLite",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(StorageBackend::Json),
+            "sqlite" | "sqlite3" | "db" => Ok(StorageBackend::Sqlite),
+            _ => Err(format!("Unknown storage backend: {}", s)),
+        }
+    }
+}
+
+/// Algorithms for scheduling spaced-repetition reviews.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerAlgorithm {
+    /// Leitner boxes with a fixed interval per box
+    #[default]
+    Leitner,
+    /// SuperMemo 2: an ease factor per card that scales its interval
+    Sm2,
+    /// Free Spaced Repetition Scheduler: a stability/difficulty memory model
+    Fsrs,
+}
+
+impl SchedulerAlgorithm {
+    /// Returns the lowercase identifier used in config files and learning sets.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SchedulerAlgorithm::Leitner => "leitner",
+            SchedulerAlgorithm::Sm2 => "sm2",
+            SchedulerAlgorithm::Fsrs => "fsrs",
+        }
+    }
+}
+
+impl fmt::Display for SchedulerAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SchedulerAlgorithm::Leitner => "Leitner",
+            SchedulerAlgorithm::Sm2 => "SM-2",
+            SchedulerAlgorithm::Fsrs => "FSRS",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SchedulerAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "leitner" => Ok(SchedulerAlgorithm::Leitner),
+            "sm2" | "sm-2" => Ok(SchedulerAlgorithm::Sm2),
+            "fsrs" => Ok(SchedulerAlgorithm::Fsrs),
+            _ => Err(format!("Unknown scheduling algorithm: {} (expected leitner, sm2 or fsrs)", s)),
+        }
+    }
+}
+
+/// What a learning session asks: flashcards, quiz questions or both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionContent {
+    /// Only flashcards
+    Cards,
+    /// Only quiz questions
+    Questions,
+    /// Flashcards with the quiz questions spread between them
+    #[default]
+    Mixed,
+}
+
+impl SessionContent {
+    /// Returns the lowercase identifier used in config files.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SessionContent::Cards => "cards",
+            SessionContent::Questions => "questions",
+            SessionContent::Mixed => "mixed",
+        }
+    }
+
+    /// Whether the session asks flashcards.
+    pub fn includes_cards(&self) -> bool {
+        *self != SessionContent::Questions
+    }
+
+    /// Whether the session asks quiz questions.
+    pub fn includes_questions(&self) -> bool {
+        *self != SessionContent::Cards
+    }
+}
+
+impl fmt::Display for SessionContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SessionContent::Cards => "Cards",
+            SessionContent::Questions => "Questions",
+            SessionContent::Mixed => "Mixed",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SessionContent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cards" | "flashcards" => Ok(SessionContent::Cards),
+            "questions" | "quiz" => Ok(SessionContent::Questions),
+            "mixed" | "all" => Ok(SessionContent::Mixed),
+            _ => Err(format!("Unknown session content: {} (expected cards, questions or mixed)", s)),
+        }
+    }
+}
+
 /// CEFR (Common European Framework of Reference) levels.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum CefrLevel {
     A1,
     A2,
@@ -193,4 +480,45 @@ mod tests {
         assert_eq!(Difficulty::Medium.word_count(), 30);
         assert_eq!(Difficulty::Hard.word_count(), 50);
     }
+
+    #[test]
+    fn test_allowed_cefr_levels() {
+        assert_eq!(
+            Difficulty::Easy.allowed_cefr_levels(),
+            &[CefrLevel::A1, CefrLevel::A2]
+        );
+        assert!(Difficulty::Hard.allowed_cefr_levels().contains(&CefrLevel::C2));
+        assert_eq!("b1".parse::<CefrLevel>().unwrap(), CefrLevel::B1);
+    }
+
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("words".parse::<TestMode>().unwrap(), TestMode::Words);
+        assert_eq!("Time".parse::<TestMode>().unwrap(), TestMode::Time);
+        assert_eq!("zeit".parse::<TestMode>().unwrap(), TestMode::Time);
+        assert_eq!("adaptive".parse::<TestMode>().unwrap(), TestMode::Adaptive);
+        assert!("marathon".parse::<TestMode>().is_err());
+    }
+
+    #[test]
+    fn test_storage_backend_from_str() {
+        assert_eq!("json".parse::<StorageBackend>().unwrap(), StorageBackend::Json);
+        assert_eq!("SQLite".parse::<StorageBackend>().unwrap(), StorageBackend::Sqlite);
+        assert_eq!(StorageBackend::Sqlite.code(), "sqlite");
+        assert!("csv".parse::<StorageBackend>().is_err());
+        assert_eq!("SM-2".parse::<SchedulerAlgorithm>().unwrap(), SchedulerAlgorithm::Sm2);
+        assert_eq!(SchedulerAlgorithm::Fsrs.code(), "fsrs");
+        assert!("anki".parse::<SchedulerAlgorithm>().is_err());
+        assert_eq!("quiz".parse::<SessionContent>().unwrap(), SessionContent::Questions);
+        assert!(!SessionContent::Questions.includes_cards());
+        assert!(SessionContent::Mixed.includes_questions());
+    }
+
+    #[test]
+    fn test_code_language() {
+        assert_eq!("py".parse::<CodeLanguage>().unwrap(), CodeLanguage::Python);
+        assert_eq!(CodeLanguage::from_extension("RS"), Some(CodeLanguage::Rust));
+        assert_eq!(CodeLanguage::from_extension("mjs"), Some(CodeLanguage::JavaScript));
+        assert_eq!(CodeLanguage::from_extension("txt"), None);
+    }
 }
@@ -7,6 +7,7 @@
 //!
 //! - `core`: Core types, errors, and configuration
 //! - `modules`: Main application logic (typing, learning)
+//! - `storage`: Persistence backends (JSON files, SQLite)
 //! - `ui`: Terminal user interface components
 //! - `utils`: Helper utilities
 //!
@@ -24,6 +25,7 @@
 
 pub mod core;
 pub mod modules;
+pub mod storage;
 pub mod ui;
 pub mod utils;
 
@@ -3,9 +3,18 @@
 //! This is the CLI application entry point using clap for argument parsing.
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use rust_util_tools::core::Config;
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use rust_util_tools::core::{
+    CodeLanguage, Config, Difficulty, Language, ProfileStore, SessionContent, TestMode,
+};
+use chrono::NaiveDate;
+use rust_util_tools::modules::learning;
+use rust_util_tools::modules::typing::{
+    FrequencyBand, HighScoreManager, OutputFormat, SourceOrder, StatsGrouping, StatsReport,
+};
+use rust_util_tools::storage::ResultFilter;
+use rust_util_tools::utils::{Bundle, ImportMode};
+use std::path::{Path, PathBuf};
 
 /// Rust Util Tools - All-in-One Learning & Utility CLI Suite
 #[derive(Parser)]
@@ -22,6 +31,10 @@ struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Player profile to use (created if it does not exist)
+    #[arg(short, long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -29,36 +42,40 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Start a typing speed test
-    Typing {
-        /// Language (de/en)
-        #[arg(short, long, value_name = "LANG")]
-        language: Option<String>,
-
-        /// Difficulty (easy/medium/hard)
-        #[arg(short, long, value_name = "DIFF")]
-        difficulty: Option<String>,
-    },
+    Typing(Box<TypingArgs>),
 
     /// Start learning mode with flashcards or quizzes
-    Learn {
-        /// Path to learning set file
-        #[arg(value_name = "FILE")]
-        set: PathBuf,
+    Learn(LearnArgs),
 
-        /// Enable spaced repetition
-        #[arg(short, long)]
-        spaced: bool,
+    /// Show statistics and highscores
+    Stats(StatsArgs),
+
+    /// Manage player profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
     },
 
-    /// Show statistics and highscores
-    Stats {
-        /// Filter by language
-        #[arg(short, long)]
-        language: Option<String>,
-
-        /// Filter by difficulty
-        #[arg(short, long)]
-        difficulty: Option<String>,
+    /// Export highscores, history and settings of the profile to a file
+    Export {
+        /// Bundle file to write
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
+
+    /// Import a file written by `rut export` into the profile
+    Import {
+        /// Bundle file to read
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Replace the history and settings instead of adding new results
+        #[arg(long)]
+        replace: bool,
+
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Configuration management
@@ -71,6 +88,187 @@ enum Commands {
     Demo,
 }
 
+/// Options of the typing test, overriding the configured defaults.
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct TypingArgs {
+    #[command(subcommand)]
+    action: Option<TypingAction>,
+
+    /// Language (de/en)
+    #[arg(short, long, value_name = "LANG")]
+    language: Option<String>,
+
+    /// Difficulty (easy/medium/hard)
+    #[arg(short, long, value_name = "DIFF")]
+    difficulty: Option<String>,
+
+    /// Test mode (words/time/code/adaptive)
+    #[arg(short, long, value_name = "MODE")]
+    mode: Option<String>,
+
+    /// Practise the weakest keys of previous tests (same as `--mode adaptive`)
+    #[arg(long, conflicts_with_all = ["mode", "time"])]
+    adaptive: bool,
+
+    /// Time limit in seconds for timed tests (15/30/60/120), implies `--mode time`
+    #[arg(short, long, value_name = "SECS")]
+    time: Option<u64>,
+
+    /// Only use words with these parts of speech (e.g. noun,verb)
+    #[arg(long, value_name = "POS", value_delimiter = ',')]
+    pos: Vec<String>,
+
+    /// Only use words from a frequency band (common/medium/rare)
+    #[arg(long, value_name = "BAND")]
+    frequency: Option<String>,
+
+    /// Custom text source: plain-text file, JSON word list, directory or `-` for stdin
+    #[arg(short, long, value_name = "FILE|DIR|-")]
+    source: Option<PathBuf>,
+
+    /// How text is taken from the source (auto/sentences/random)
+    #[arg(short, long, value_name = "ORDER")]
+    order: Option<String>,
+
+    /// Programming language for code snippets (rust/python/javascript), implies `--mode code`
+    #[arg(long, value_name = "LANG")]
+    code_lang: Option<String>,
+
+    /// Source file or directory to take code snippets from, implies `--mode code`
+    #[arg(long, value_name = "PATH")]
+    snippets: Option<PathBuf>,
+
+    /// Indentation for code snippets (spaces/tab)
+    #[arg(long, value_name = "STYLE")]
+    indent: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum TypingAction {
+    /// Replay a recorded test (lists recorded tests if no ID is given)
+    Replay {
+        /// Result ID of the test, or "last" for the most recent one
+        #[arg(value_name = "ID")]
+        id: Option<String>,
+
+        /// Playback speed multiplier (1 = real time)
+        #[arg(short, long, default_value_t = 1.0)]
+        speed: f64,
+    },
+}
+
+/// Options of a learning session.
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct LearnArgs {
+    #[command(subcommand)]
+    action: Option<LearnAction>,
+
+    /// Path to learning set file
+    #[arg(value_name = "FILE", required = true)]
+    set: Option<PathBuf>,
+
+    /// Use spaced repetition and continue with the saved progress
+    /// (always on if `learning.spaced_repetition` is set)
+    #[arg(short, long)]
+    spaced: bool,
+
+    /// What to ask: cards, questions or mixed (default: `learning.session_content`)
+    #[arg(short, long, value_name = "CONTENT")]
+    content: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum LearnAction {
+    /// List how many cards of each learning set are due today
+    Due {
+        /// Learning set files or directories (default: the learning sets directory)
+        #[arg(value_name = "PATH")]
+        paths: Vec<PathBuf>,
+    },
+    /// Show the earlier learning sessions and the progress per set
+    History {
+        /// Only sessions with this learning set (by name)
+        #[arg(value_name = "SET")]
+        set: Option<String>,
+
+        /// Number of most recent sessions to list
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+/// Options of the statistics report.
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct StatsArgs {
+    #[command(subcommand)]
+    action: Option<StatsAction>,
+
+    /// Open the statistics screen instead of printing a report
+    #[arg(long, conflicts_with_all = ["format", "top", "group_by"])]
+    tui: bool,
+
+    /// Output format (table/json/csv)
+    #[arg(short, long, default_value = "table")]
+    format: String,
+
+    /// Filter by language
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// Filter by difficulty
+    #[arg(short, long)]
+    difficulty: Option<String>,
+
+    /// Filter by test mode (words/time/code/adaptive)
+    #[arg(short, long)]
+    mode: Option<String>,
+
+    /// Only results from this day on (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    from: Option<NaiveDate>,
+
+    /// Only results up to and including this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    to: Option<NaiveDate>,
+
+    /// Only the N fastest results (or groups with the highest average WPM)
+    #[arg(short, long, value_name = "N")]
+    top: Option<usize>,
+
+    /// Group the results (language/difficulty/week)
+    #[arg(short, long, value_name = "GROUP")]
+    group_by: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum StatsAction {
+    /// Report results that are not real runs (too fast, too short, pasted or automated)
+    Prune {
+        /// Remove the reported results from the history and rebuild the highscores
+        #[arg(long)]
+        remove: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// List all profiles
+    List,
+    /// Rename a profile
+    Rename {
+        /// Current name of the profile
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// New name
+        #[arg(value_name = "NEW_NAME")]
+        new_name: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum ConfigAction {
     /// Show current configuration
@@ -85,7 +283,8 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
 
     // Load configuration
-    let config = if let Some(config_path) = cli.config {
+    let config_path = cli.config.clone().unwrap_or_else(|| PathBuf::from("config/default.toml"));
+    let mut config = if let Some(config_path) = cli.config {
         Config::load_from_file(config_path)?
     } else {
         Config::load_or_default()
@@ -98,31 +297,93 @@ fn main() -> Result<()> {
     // Validate configuration
     config.validate()?;
 
+    // Use the data and settings of the selected (or last used) profile
+    let base_config = config.clone();
+    let mut profiles = ProfileStore::for_config(&config)?;
+    let pick_profile = cli.profile.is_none() && profiles.profiles().len() > 1;
+    let profile = match &cli.profile {
+        Some(name) => Some(profiles.select_or_create(name, &config)?),
+        None => profiles.current().cloned(),
+    };
+    if let Some(profile) = &profile {
+        config = profiles.apply(profile, &config);
+    }
+
     // Execute command
     match cli.command {
-        Commands::Typing {
-            language: _language,
-            difficulty: _difficulty,
-        } => {
-            // TODO: Pass language/difficulty to TUI
-            rust_util_tools::ui::run(config, rust_util_tools::ui::app::CurrentScreen::TypingTest)?;
-        }
-        Commands::Learn { set: _set, spaced: _spaced } => {
-            // TODO: Pass set/spaced to TUI
-            rust_util_tools::ui::run(config, rust_util_tools::ui::app::CurrentScreen::LearningMode)?;
+        Commands::Typing(mut args) => match args.action.take() {
+            Some(TypingAction::Replay { id, speed }) => replay(config, profiles, id, speed)?,
+            None => {
+                apply_typing_args(&mut config, *args)?;
+                rust_util_tools::ui::run(config, profiles, rust_util_tools::ui::app::CurrentScreen::TypingTest)?;
+            }
+        },
+        Commands::Learn(args) => match (args.action, args.set) {
+            (Some(LearnAction::Due { paths }), _) => print_due(&config, paths)?,
+            (Some(LearnAction::History { set, limit }), _) => print_learning_history(&config, set, limit)?,
+            (None, Some(set)) => {
+                let set = learning::load_auto(&set)?;
+                if let Some(content) = args.content {
+                    let content: SessionContent = content.parse().map_err(anyhow::Error::msg)?;
+                    config.learning.session_content = content.code().to_string();
+                }
+                let spaced = args.spaced || config.learning.spaced_repetition;
+                rust_util_tools::ui::learn(config, profiles, set, spaced)?;
+            }
+            (None, None) => unreachable!("clap requires a learning set"),
+        },
+        Commands::Stats(args) => match args.action {
+            Some(StatsAction::Prune { remove }) => prune_highscores(&config, remove)?,
+            None if args.tui => {
+                rust_util_tools::ui::run(config, profiles, rust_util_tools::ui::app::CurrentScreen::Statistics)?;
+            }
+            None => print_stats(&config, args)?,
+        },
+        Commands::Profile { action } => match action {
+            ProfileAction::List => {
+                let current = profiles.current().map(|p| p.name.clone());
+                if profiles.profiles().is_empty() {
+                    println!("No profiles yet, create one with --profile <NAME>.");
+                }
+                for profile in profiles.profiles() {
+                    let marker = if Some(&profile.name) == current.as_ref() { "*" } else { " " };
+                    println!(
+                        "{} {} ({}, {}) - {}",
+                        marker,
+                        profile.name,
+                        profile.language,
+                        profile.difficulty,
+                        profiles.data_dir(profile).display()
+                    );
+                }
+            }
+            ProfileAction::Rename { name, new_name } => {
+                let profile = profiles.rename(&name, &new_name)?;
+                println!("✓ Renamed profile {} to {}", name, profile.name);
+            }
+        },
+        Commands::Export { file } => {
+            let manager = HighScoreManager::from_config(&config)?;
+            let name = profile.as_ref().map(|p| p.name.as_str());
+            let bundle = Bundle::export(&manager, &config, name)?;
+            bundle.write(&file)?;
+            println!(
+                "✓ Exported {} result(s), the progress of {} learning card(s) and the settings to {}",
+                bundle.history.len(),
+                bundle.learning.card_count(),
+                file.display()
+            );
         }
-        Commands::Stats {
-            language: _language,
-            difficulty: _difficulty,
-        } => {
-            rust_util_tools::ui::run(config, rust_util_tools::ui::app::CurrentScreen::Statistics)?;
+        Commands::Import { file, replace, dry_run } => {
+            let mode = if replace { ImportMode::Replace } else { ImportMode::Merge };
+            import_bundle(&config, &config_path, &mut profiles, &file, mode, dry_run)?;
         }
         Commands::Config { action } => match action {
             ConfigAction::Show => {
                 println!("{:#?}", config);
             }
             ConfigAction::Init => {
-                config.save_to_file("config/default.toml")?;
+                base_config.save_to_file("config/default.toml")?;
                 println!("✓ Configuration file created at config/default.toml");
             }
             ConfigAction::Validate => {
@@ -131,12 +392,352 @@ fn main() -> Result<()> {
             }
         },
         Commands::Demo => {
-            // Run TUI Main Menu
-            rust_util_tools::ui::run(config, rust_util_tools::ui::app::CurrentScreen::Menu)?;
+            // Run TUI Main Menu, asking who is typing if several people share it
+            let screen = if pick_profile {
+                rust_util_tools::ui::app::CurrentScreen::ProfileSelect
+            } else {
+                rust_util_tools::ui::app::CurrentScreen::Menu
+            };
+            rust_util_tools::ui::run(config, profiles, screen)?;
         }
     }
 
     Ok(())
 }
 
+/// Apply the typing test options to the configuration.
+fn apply_typing_args(config: &mut Config, args: TypingArgs) -> Result<()> {
+    // Command line options override the configured defaults
+    if let Some(language) = args.language {
+        let language: Language = language.parse().map_err(anyhow::Error::msg)?;
+        config.defaults.language = language.code().to_string();
+    }
+    if let Some(difficulty) = args.difficulty {
+        let difficulty: Difficulty = difficulty.parse().map_err(anyhow::Error::msg)?;
+        config.defaults.difficulty = format!("{:?}", difficulty);
+    }
+    if let Some(mode) = args.mode {
+        let mode: TestMode = mode.parse().map_err(anyhow::Error::msg)?;
+        config.defaults.mode = mode.code().to_string();
+    }
+    if args.adaptive {
+        config.defaults.mode = TestMode::Adaptive.code().to_string();
+    }
+    if let Some(time) = args.time {
+        config.defaults.mode = TestMode::Time.code().to_string();
+        config.defaults.time_limit = time;
+    }
+    if !args.pos.is_empty() {
+        config.defaults.parts_of_speech = args.pos;
+    }
+    if let Some(frequency) = args.frequency {
+        let frequency: FrequencyBand = frequency.parse().map_err(anyhow::Error::msg)?;
+        config.defaults.frequency = Some(frequency.to_string());
+    }
+    if let Some(source) = args.source {
+        config.defaults.source = Some(source);
+    }
+    if let Some(order) = args.order {
+        let order: SourceOrder = order.parse().map_err(anyhow::Error::msg)?;
+        config.defaults.source_order = order.to_string();
+    }
+    if let Some(code_lang) = args.code_lang {
+        let code_lang: CodeLanguage = code_lang.parse().map_err(anyhow::Error::msg)?;
+        config.defaults.mode = TestMode::Code.code().to_string();
+        config.code.language = code_lang.code().to_string();
+    }
+    if let Some(snippets) = args.snippets {
+        config.defaults.mode = TestMode::Code.code().to_string();
+        config.code.snippet_path = Some(snippets);
+    }
+    if let Some(indent) = args.indent {
+        config.code.indent_style = indent;
+    }
+    config.validate()?;
+
+    Ok(())
+}
+
+/// Print a statistics report to stdout.
+fn print_stats(config: &Config, args: StatsArgs) -> Result<()> {
+    let format: OutputFormat = args.format.parse().map_err(anyhow::Error::msg)?;
+    let language = args
+        .language
+        .map(|l| l.parse::<Language>())
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+    let difficulty = args
+        .difficulty
+        .map(|d| d.parse::<Difficulty>())
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+    let mode = args
+        .mode
+        .map(|m| m.parse::<TestMode>())
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+    let grouping = args
+        .group_by
+        .map(|g| g.parse::<StatsGrouping>())
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
 
+    let filter = ResultFilter::new(language, difficulty)
+        .with_mode(mode)
+        .between(args.from, args.to);
+    let manager = HighScoreManager::from_config(config)?;
+    let report = StatsReport::build(&manager, &filter, grouping, args.top)?;
+    print!("{}", report.render(format)?);
+
+    Ok(())
+}
+
+/// Import a bundle into the current profile, or show what would change.
+fn import_bundle(
+    config: &Config,
+    config_path: &Path,
+    profiles: &mut ProfileStore,
+    file: &Path,
+    mode: ImportMode,
+    dry_run: bool,
+) -> Result<()> {
+    let bundle = Bundle::read(file)?;
+    let manager = HighScoreManager::from_config(config)?;
+    let plan = bundle.plan(manager.load_history()?, manager.storage().load_progress()?, config, mode);
+
+    let target = profiles
+        .current()
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| profiles.player_name());
+    println!("Importing {} into profile {} ({})", file.display(), target, mode);
+    println!("{}", plan);
+    if dry_run {
+        println!("Dry run, nothing was changed");
+        return Ok(());
+    }
+
+    plan.apply(&manager)?;
+    if let Some(settings) = &plan.settings {
+        settings.validate()?;
+        let mut file_config = if config_path.exists() {
+            Config::load_from_file(config_path)?
+        } else {
+            Config::default()
+        };
+        // Language, difficulty and theme belong to the profile if there is one
+        let (language, difficulty, theme) = match profiles.current().cloned() {
+            Some(mut profile) => {
+                profile.update_settings(settings);
+                profiles.update(&profile)?;
+                (
+                    file_config.defaults.language.clone(),
+                    file_config.defaults.difficulty.clone(),
+                    file_config.theme.clone(),
+                )
+            }
+            None => (
+                settings.defaults.language.clone(),
+                settings.defaults.difficulty.clone(),
+                settings.theme.clone(),
+            ),
+        };
+        file_config = Config {
+            paths: file_config.paths,
+            theme,
+            ..settings.clone()
+        };
+        file_config.defaults.language = language;
+        file_config.defaults.difficulty = difficulty;
+        file_config.save_to_file(config_path)?;
+    }
+    println!("✓ Import finished");
+
+    Ok(())
+}
+
+/// Report implausible results and optionally remove them from the history.
+///
+/// Runs with low accuracy are not reported; they stay in the history and
+/// only stay out of the highscore table.
+fn prune_highscores(config: &Config, remove: bool) -> Result<()> {
+    let manager = HighScoreManager::from_config(config)?;
+
+    let implausible = if remove {
+        manager.prune()?
+    } else {
+        manager.find_implausible()?
+    };
+
+    if implausible.is_empty() {
+        println!("✓ All results are plausible");
+        return Ok(());
+    }
+
+    for (score, issues) in &implausible {
+        let reasons: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        println!(
+            "{} {} - {:.1} WPM, {:.1}%: {}",
+            score.local_time(),
+            score.name,
+            score.wpm,
+            score.accuracy,
+            reasons.join(", ")
+        );
+    }
+
+    if remove {
+        println!("✓ Removed {} implausible result(s)", implausible.len());
+    } else {
+        println!(
+            "{} implausible result(s) found, run with --remove to delete them",
+            implausible.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// List how many cards of each learning set are due today.
+///
+/// Directories are searched for set files; sets that cannot be loaded are
+/// reported and skipped.
+fn print_due(config: &Config, paths: Vec<PathBuf>) -> Result<()> {
+    let paths = if paths.is_empty() {
+        vec![config.paths.learning_sets_dir.clone()]
+    } else {
+        paths
+    };
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(learning::find_learning_sets(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+
+    let storage = rust_util_tools::storage::open(config)?;
+    let progress = storage.load_progress()?;
+    let until = learning::end_of_day(chrono::Local::now().date_naive());
+
+    let mut summaries = Vec::new();
+    for file in &files {
+        match learning::load_auto(file) {
+            Ok(set) => {
+                let (set_progress, _) = progress.for_set(&set, config.learning.leitner_boxes);
+                summaries.push(set_progress.due_summary(&set, until));
+            }
+            Err(e) => eprintln!("Skipping {}: {}", file.display(), e),
+        }
+    }
+    if summaries.is_empty() {
+        println!("No learning sets found");
+        return Ok(());
+    }
+
+    let width = summaries.iter().map(|s| s.name.chars().count()).max().unwrap_or(0).max(3);
+    println!("{:<width$}  {:>5}  {:>3}  {:>3}  Next due", "Set", "Cards", "Due", "New");
+    for summary in &summaries {
+        let next = match (summary.to_review(), summary.next_due) {
+            (0, Some(next)) => next.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string(),
+            (0, None) => "-".to_string(),
+            _ => "today".to_string(),
+        };
+        println!(
+            "{:<width$}  {:>5}  {:>3}  {:>3}  {}",
+            summary.name, summary.total, summary.due, summary.new, next
+        );
+    }
+    let total: usize = summaries.iter().map(|s| s.to_review()).sum();
+    println!("{} card(s) to review today", total);
+
+    Ok(())
+}
+
+/// List the most recent learning sessions and the progress of each set.
+fn print_learning_history(config: &Config, set: Option<String>, limit: usize) -> Result<()> {
+    let mut sessions = rust_util_tools::storage::open(config)?.load_sessions()?;
+    if let Some(set) = &set {
+        sessions.retain(|session| &session.set_name == set);
+    }
+    if sessions.is_empty() {
+        println!("No learning sessions yet");
+        return Ok(());
+    }
+
+    let width = sessions.iter().map(|s| s.set_name.chars().count()).max().unwrap_or(0).max(3);
+    println!(
+        "{:<16}  {:<width$}  {:>7}  {:>8}  {:>7}  {:>8}",
+        "Date", "Set", "Answers", "Accuracy", "Time", "Mastered"
+    );
+    for session in sessions.iter().skip(sessions.len().saturating_sub(limit)) {
+        let mastery = match session.mastery {
+            Some(mastery) => format!("{:.0}%", mastery),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<16}  {:<width$}  {:>7}  {:>7.1}%  {:>7}  {:>8}",
+            session.local_time(),
+            session.set_name,
+            session.stats.total_reviewed,
+            session.accuracy(),
+            learning::format_duration(session.duration_secs),
+            mastery
+        );
+    }
+
+    println!();
+    println!(
+        "{:<width$}  {:>8}  {:>7}  {:>15}  {:>8}  {:>8}",
+        "Set", "Sessions", "Answers", "Accuracy", "Mastered", "Time"
+    );
+    for trend in learning::set_trends(&sessions) {
+        let mastery = match trend.mastery {
+            Some(mastery) => format!("{:.0}%", mastery),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<width$}  {:>8}  {:>7}  {:>15}  {:>8}  {:>8}",
+            trend.name,
+            trend.sessions,
+            trend.reviewed,
+            format!("{:.0}% → {:.0}%", trend.first_accuracy, trend.last_accuracy),
+            mastery,
+            learning::format_duration(trend.duration_secs)
+        );
+    }
+
+    Ok(())
+}
+
+/// Replay a recorded typing test, or list the recorded tests.
+fn replay(config: Config, profiles: ProfileStore, id: Option<String>, speed: f64) -> Result<()> {
+    let storage = rust_util_tools::storage::open(&config)?;
+    let ids = storage.keystroke_log_ids()?;
+
+    let id = match id.as_deref() {
+        None => {
+            if ids.is_empty() {
+                println!("No recorded tests yet.");
+            }
+            for id in ids {
+                println!("{}", id);
+            }
+            return Ok(());
+        }
+        Some("last") => ids
+            .last()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No recorded tests yet"))?,
+        Some(id) => id.to_string(),
+    };
+
+    if !speed.is_finite() || speed <= 0.0 {
+        anyhow::bail!("Playback speed must be greater than 0");
+    }
+
+    let log = storage
+        .load_keystroke_log(&id)?
+        .ok_or_else(|| anyhow::anyhow!("No keystroke log for result {}", id))?;
+    rust_util_tools::ui::replay(config, profiles, log, speed)
+}
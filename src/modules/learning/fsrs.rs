@@ -0,0 +1,199 @@
+//! FSRS scheduling (Free Spaced Repetition Scheduler, version 4.5).
+//!
+//! Every item has a stability, the number of days until the chance to
+//! recall it drops to 90%, and a difficulty from 1 to 10. Each answer
+//! updates both from the grade and the recall chance at the time of the
+//! review. The next review is scheduled when the recall chance drops to the
+//! desired retention.
+
+use crate::core::SchedulerAlgorithm;
+use crate::modules::learning::spaced_rep::{Grade, ItemState, Scheduler};
+use chrono::{DateTime, Utc};
+
+/// Default FSRS-4.5 model weights.
+pub const DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19.0 / 81.0;
+/// Longest interval in days (100 years).
+const MAX_INTERVAL_DAYS: f64 = 36_500.0;
+
+/// FSRS rating (1-4) of a grade.
+fn rating(grade: Grade) -> f64 {
+    match grade {
+        Grade::Again => 1.0,
+        Grade::Hard => 2.0,
+        Grade::Good => 3.0,
+        Grade::Easy => 4.0,
+    }
+}
+
+/// Chance to recall an item `elapsed_days` after its last review.
+pub fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+    (1.0 + FACTOR * elapsed_days / stability).powf(DECAY)
+}
+
+/// FSRS scheduler.
+pub struct FsrsScheduler {
+    states: Vec<ItemState>,
+    desired_retention: f64,
+    weights: [f64; 17],
+}
+
+impl FsrsScheduler {
+    /// Create a scheduler from stored item states.
+    ///
+    /// # Arguments
+    ///
+    /// * `states` - State of each item
+    /// * `desired_retention` - Recall chance at which items become due (0-1)
+    pub fn new(states: Vec<ItemState>, desired_retention: f64) -> Self {
+        Self {
+            states,
+            desired_retention,
+            weights: DEFAULT_WEIGHTS,
+        }
+    }
+
+    /// Days until the recall chance of an item with the given stability
+    /// drops to the desired retention.
+    pub fn interval_days(&self, stability: f64) -> u32 {
+        let days = stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        days.round().clamp(1.0, MAX_INTERVAL_DAYS) as u32
+    }
+
+    fn initial_stability(&self, grade: Grade) -> f64 {
+        self.weights[rating(grade) as usize - 1]
+    }
+
+    fn initial_difficulty(&self, grade: Grade) -> f64 {
+        (self.weights[4] - (rating(grade) - 3.0) * self.weights[5]).clamp(1.0, 10.0)
+    }
+
+    fn next_difficulty(&self, difficulty: f64, grade: Grade) -> f64 {
+        let w = &self.weights;
+        let next = difficulty - w[6] * (rating(grade) - 3.0);
+        // Mean reversion toward the difficulty of an easy first answer
+        (w[7] * self.initial_difficulty(Grade::Easy) + (1.0 - w[7]) * next).clamp(1.0, 10.0)
+    }
+
+    fn next_stability(&self, stability: f64, difficulty: f64, recall: f64, grade: Grade) -> f64 {
+        let w = &self.weights;
+        if grade == Grade::Again {
+            return w[11]
+                * difficulty.powf(-w[12])
+                * ((stability + 1.0).powf(w[13]) - 1.0)
+                * (w[14] * (1.0 - recall)).exp();
+        }
+
+        let hard_penalty = if grade == Grade::Hard { w[15] } else { 1.0 };
+        let easy_bonus = if grade == Grade::Easy { w[16] } else { 1.0 };
+        stability
+            * (1.0
+                + w[8].exp()
+                    * (11.0 - difficulty)
+                    * stability.powf(-w[9])
+                    * ((w[10] * (1.0 - recall)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus)
+    }
+}
+
+impl Scheduler for FsrsScheduler {
+    fn algorithm(&self) -> SchedulerAlgorithm {
+        SchedulerAlgorithm::Fsrs
+    }
+
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    fn state(&self, item_id: usize) -> Option<&ItemState> {
+        self.states.get(item_id)
+    }
+
+    fn review(&mut self, item_id: usize, grade: Grade, reviewed: DateTime<Utc>) {
+        let Some(state) = self.states.get(item_id) else {
+            return;
+        };
+
+        let (stability, difficulty) = match (state.stability, state.difficulty) {
+            (Some(stability), Some(difficulty)) if !state.is_new() => {
+                let recall = retrievability(state.elapsed_days(reviewed), stability);
+                (
+                    self.next_stability(stability, difficulty, recall, grade),
+                    self.next_difficulty(difficulty, grade),
+                )
+            }
+            // First answer, or the item was scheduled by another algorithm
+            _ => (self.initial_stability(grade), self.initial_difficulty(grade)),
+        };
+        let stability = stability.max(0.1);
+        let interval_days = self.interval_days(stability);
+
+        let state = &mut self.states[item_id];
+        state.stability = Some(stability);
+        state.difficulty = Some(difficulty);
+        state.record(grade, reviewed, interval_days);
+    }
+
+    fn describe(&self, item_id: usize) -> String {
+        match self.state(item_id) {
+            Some(ItemState {
+                stability: Some(stability),
+                difficulty: Some(difficulty),
+                ..
+            }) => format!("Stability {:.1}d, difficulty {:.1}", stability, difficulty),
+            _ => "New".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_retrievability() {
+        assert!((retrievability(0.0, 5.0) - 1.0).abs() < 1e-9);
+        // By definition recall drops to 90% after `stability` days
+        assert!((retrievability(5.0, 5.0) - 0.9).abs() < 1e-9);
+
+        let fsrs = FsrsScheduler::new(Vec::new(), 0.9);
+        assert_eq!(fsrs.interval_days(12.0), 12);
+        assert!(FsrsScheduler::new(Vec::new(), 0.8).interval_days(12.0) > 12);
+    }
+
+    #[test]
+    fn test_fsrs_review() {
+        let now: DateTime<Utc> = "2024-03-01T12:00:00Z".parse().unwrap();
+        let mut fsrs = FsrsScheduler::new(vec![ItemState::default(); 2], 0.9);
+
+        fsrs.review(0, Grade::Good, now);
+        fsrs.review(1, Grade::Again, now);
+        let good = fsrs.state(0).unwrap().clone();
+        let again = fsrs.state(1).unwrap().clone();
+        assert_eq!(good.stability, Some(DEFAULT_WEIGHTS[2]));
+        assert_eq!(good.interval_days, 4);
+        assert_eq!(again.interval_days, 1);
+        assert!(again.difficulty > good.difficulty);
+
+        // Remembering after the interval grows the stability
+        let due = good.due.unwrap();
+        fsrs.review(0, Grade::Good, due);
+        let state = fsrs.state(0).unwrap();
+        assert!(state.stability.unwrap() > good.stability.unwrap());
+        assert!(state.interval_days > good.interval_days);
+
+        // Forgetting shrinks it
+        fsrs.review(0, Grade::Again, due + Duration::days(1));
+        let state = fsrs.state(0).unwrap();
+        assert!(state.stability.unwrap() < good.stability.unwrap());
+        assert_eq!(state.lapses, 1);
+        assert!(fsrs.describe(0).starts_with("Stability"));
+    }
+}
@@ -0,0 +1,307 @@
+//! History of finished learning sessions.
+//!
+//! Every finished session is kept in the storage backend (see
+//! [`Storage`](crate::storage::Storage)); with JSON storage in
+//! `highscores.sessions.jsonl` next to the highscore file, one session per
+//! line. [`set_trends`] condenses the history into the progress of each set
+//! over time.
+
+use crate::core::{Result, SchedulerAlgorithm, UtilError};
+use crate::modules::learning::models::SessionStats;
+use crate::modules::learning::session::Movement;
+use crate::utils::fs as safe_fs;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// A finished learning session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearningSession {
+    /// Name of the learning set
+    pub set_name: String,
+    /// When the session started
+    pub started: DateTime<Utc>,
+    /// Time spent in seconds
+    pub duration_secs: f64,
+    /// Answers to cards and questions
+    #[serde(flatten)]
+    pub stats: SessionStats,
+    /// Quiz questions asked
+    #[serde(default)]
+    pub quiz_total: usize,
+    /// Quiz questions answered correctly
+    #[serde(default)]
+    pub quiz_correct: usize,
+    /// Scheduling algorithm, `None` without spaced repetition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<SchedulerAlgorithm>,
+    /// How the answers moved the cards in the schedule
+    #[serde(flatten)]
+    pub movement: Movement,
+    /// Share of the set's cards mastered after the session, in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mastery: Option<f64>,
+    /// Prompts of the hardest cards and questions, hardest first
+    #[serde(default)]
+    pub hardest: Vec<String>,
+}
+
+impl LearningSession {
+    /// Accuracy of the session in percent.
+    pub fn accuracy(&self) -> f64 {
+        self.stats.accuracy()
+    }
+
+    /// Start of the session in local time, e.g. "2024-03-01 13:00".
+    pub fn local_time(&self) -> String {
+        self.started.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    /// Average time per answer in seconds.
+    pub fn secs_per_item(&self) -> f64 {
+        if self.stats.total_reviewed == 0 {
+            return 0.0;
+        }
+        self.duration_secs / self.stats.total_reviewed as f64
+    }
+}
+
+/// Format a time span as e.g. "45s", "3m 12s" or "1h 05m".
+pub fn format_duration(secs: f64) -> String {
+    let secs = secs.max(0.0).round() as u64;
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Progress of one learning set over its sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetTrend {
+    /// Name of the learning set
+    pub name: String,
+    /// Number of sessions
+    pub sessions: usize,
+    /// Answers over all sessions
+    pub reviewed: usize,
+    /// Total time spent in seconds
+    pub duration_secs: f64,
+    /// Accuracy of the first session in percent
+    pub first_accuracy: f64,
+    /// Accuracy of the latest session in percent
+    pub last_accuracy: f64,
+    /// Mastery after the latest session that recorded one
+    pub mastery: Option<f64>,
+    /// When the latest session started
+    pub last_session: DateTime<Utc>,
+}
+
+impl SetTrend {
+    /// Change in accuracy from the first to the latest session, in
+    /// percentage points.
+    pub fn accuracy_change(&self) -> f64 {
+        self.last_accuracy - self.first_accuracy
+    }
+}
+
+/// Summarize the history by learning set, sorted by set name.
+///
+/// # Arguments
+///
+/// * `sessions` - Sessions of any sets, oldest first
+pub fn set_trends(sessions: &[LearningSession]) -> Vec<SetTrend> {
+    let mut by_set: BTreeMap<&str, Vec<&LearningSession>> = BTreeMap::new();
+    for session in sessions {
+        by_set.entry(&session.set_name).or_default().push(session);
+    }
+
+    by_set
+        .into_iter()
+        .filter_map(|(name, sessions)| {
+            let first = sessions.first()?;
+            let last = sessions.last()?;
+            Some(SetTrend {
+                name: name.to_string(),
+                sessions: sessions.len(),
+                reviewed: sessions.iter().map(|s| s.stats.total_reviewed).sum(),
+                duration_secs: sessions.iter().map(|s| s.duration_secs).sum(),
+                first_accuracy: first.accuracy(),
+                last_accuracy: last.accuracy(),
+                mastery: sessions.iter().rev().find_map(|s| s.mastery),
+                last_session: last.started,
+            })
+        })
+        .collect()
+}
+
+/// Append-only store of learning sessions in JSON Lines format, used by the
+/// JSON storage.
+pub struct SessionHistory {
+    file_path: PathBuf,
+}
+
+impl SessionHistory {
+    /// Create a history stored in the given file.
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        Self {
+            file_path: file_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Create the history that belongs to a highscore file
+    /// (`highscores.json` → `highscores.sessions.jsonl`).
+    pub fn for_highscore_file<P: AsRef<Path>>(highscore_file: P) -> Self {
+        Self::new(highscore_file.as_ref().with_extension("sessions.jsonl"))
+    }
+
+    /// Path of the history file.
+    pub fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Append a session to the history.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn append(&self, session: &LearningSession) -> Result<()> {
+        if let Some(parent) = self.file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file_path)
+            .map_err(|e| {
+                UtilError::ProgressError(format!("Failed to open session history: {}", e))
+            })?;
+        // A new line first, in case an earlier append was interrupted; empty
+        // lines are skipped when loading
+        let mut line = String::from("\n");
+        line.push_str(&serde_json::to_string(session)?);
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load all sessions, oldest first.
+    ///
+    /// A missing file is an empty history. Lines that cannot be parsed
+    /// (left behind by a crash while appending) are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn load(&self) -> Result<Vec<LearningSession>> {
+        if !self.file_path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = File::open(&self.file_path).map_err(|e| {
+            UtilError::ProgressError(format!("Failed to open session history: {}", e))
+        })?;
+
+        let mut sessions = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(session) = serde_json::from_str(&line) {
+                sessions.push(session);
+            }
+        }
+        Ok(sessions)
+    }
+
+    /// Replace the whole history.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn rewrite(&self, sessions: &[LearningSession]) -> Result<()> {
+        let mut contents = Vec::new();
+        for session in sessions {
+            serde_json::to_writer(&mut contents, session)?;
+            contents.push(b'\n');
+        }
+        safe_fs::write_atomic_with_backup(&self.file_path, &contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn session(set_name: &str, day: u32, correct: usize, incorrect: usize) -> LearningSession {
+        LearningSession {
+            set_name: set_name.to_string(),
+            started: format!("2024-03-{:02}T12:00:00Z", day).parse().unwrap(),
+            duration_secs: 60.0,
+            stats: SessionStats {
+                total_reviewed: correct + incorrect,
+                correct,
+                incorrect,
+                user_overrides: 0,
+            },
+            quiz_total: 0,
+            quiz_correct: 0,
+            algorithm: Some(SchedulerAlgorithm::Leitner),
+            movement: Movement::default(),
+            mastery: Some(day as f64 * 10.0),
+            hardest: vec!["France".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = TempDir::new().unwrap();
+        let history = SessionHistory::for_highscore_file(dir.path().join("highscores.json"));
+        assert_eq!(history.path(), dir.path().join("highscores.sessions.jsonl"));
+        assert!(history.load().unwrap().is_empty());
+
+        history.append(&session("Capitals", 1, 3, 1)).unwrap();
+        // An interrupted append leaves half a line behind
+        let mut file = OpenOptions::new().append(true).open(history.path()).unwrap();
+        file.write_all(b"\n{\"set_name\": \"Cap").unwrap();
+        history.append(&session("Capitals", 2, 4, 0)).unwrap();
+
+        let sessions = history.load().unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1], session("Capitals", 2, 4, 0));
+
+        history.rewrite(&sessions[..1]).unwrap();
+        assert_eq!(history.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_set_trends() {
+        let sessions = vec![
+            session("Rivers", 1, 1, 1),
+            session("Capitals", 2, 2, 2),
+            session("Capitals", 3, 3, 1),
+            session("Capitals", 5, 4, 0),
+        ];
+        let trends = set_trends(&sessions);
+        assert_eq!(trends.len(), 2);
+
+        let capitals = &trends[0];
+        assert_eq!((capitals.name.as_str(), capitals.sessions, capitals.reviewed), ("Capitals", 3, 12));
+        assert_eq!((capitals.first_accuracy, capitals.last_accuracy), (50.0, 100.0));
+        assert_eq!(capitals.accuracy_change(), 50.0);
+        assert_eq!(capitals.mastery, Some(50.0));
+        assert_eq!(capitals.duration_secs, 180.0);
+        assert_eq!(sessions[0].secs_per_item(), 30.0);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(44.6), "45s");
+        assert_eq!(format_duration(192.0), "3m 12s");
+        assert_eq!(format_duration(3900.0), "1h 05m");
+    }
+}
@@ -1,15 +1,28 @@
 //! Learning module.
 //!
 //! This module contains all logic for the learning system, including
-//! flashcards, quizzes, fuzzy matching, and spaced repetition.
+//! flashcards, quizzes, fuzzy matching, and spaced repetition (Leitner boxes,
+//! SM-2 and FSRS).
 
+pub mod fsrs;
 pub mod fuzzy;
+pub mod history;
 pub mod models;
 pub mod parsers;
+pub mod progress;
+pub mod session;
+pub mod sm2;
 pub mod spaced_rep;
 
 // Re-export commonly used items
 pub use fuzzy::{FuzzyMatcher, MatchResult};
+pub use history::{format_duration, set_trends, LearningSession, SessionHistory, SetTrend};
 pub use models::{Card, LearningSet, QuizQuestion, SessionStats};
-pub use parsers::{load_auto, load_from_json};
-pub use spaced_rep::{LeitnerBox, LeitnerSummary};
+pub use parsers::{find_learning_sets, is_learning_set, load_auto, load_from_json};
+pub use progress::{
+    card_keys, end_of_day, DueSummary, LearningProgress, ProgressStore, Reconciliation, SetProgress,
+};
+pub use fsrs::FsrsScheduler;
+pub use session::{hardest_items, session_order, HardItem, Movement, SessionItem};
+pub use sm2::Sm2Scheduler;
+pub use spaced_rep::{create_scheduler, Grade, ItemState, LeitnerBox, Scheduler, SchedulerSummary};
@@ -3,11 +3,15 @@
 //! This module defines the core data structures for flashcards,
 //! quiz questions, and learning sets.
 
+use crate::core::SchedulerAlgorithm;
 use serde::{Deserialize, Serialize};
 
 /// A flashcard with front and back sides.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Card {
+    /// Stable identifier for the learning progress; the front is used if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Front side of the card (question/prompt)
     pub front: String,
     /// Back side of the card (answer)
@@ -44,6 +48,11 @@ impl QuizQuestion {
         !self.alternatives.is_empty()
     }
 
+    /// Check whether a chosen option is the correct answer.
+    pub fn is_correct_option(&self, option: &str) -> bool {
+        option.trim() == self.correct_answer.trim()
+    }
+
     /// Get all answer options (correct + alternatives) shuffled.
     pub fn get_shuffled_options(&self) -> Vec<String> {
         use rand::seq::SliceRandom;
@@ -71,6 +80,10 @@ pub struct LearningSet {
     /// Metadata tags
     #[serde(default)]
     pub tags: Vec<String>,
+    /// Scheduling algorithm for this set (leitner, sm2 or fsrs); the
+    /// configured one if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
 }
 
 impl LearningSet {
@@ -83,18 +96,32 @@ impl LearningSet {
     pub fn is_empty(&self) -> bool {
         self.cards.is_empty() && self.questions.is_empty()
     }
+
+    /// Get the scheduling algorithm of the set.
+    ///
+    /// Uses `default` if the set does not choose a known algorithm.
+    pub fn scheduler_algorithm(&self, default: SchedulerAlgorithm) -> SchedulerAlgorithm {
+        self.algorithm
+            .as_deref()
+            .and_then(|name| name.parse().ok())
+            .unwrap_or(default)
+    }
 }
 
 /// Statistics for a learning session.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SessionStats {
     /// Total items reviewed
+    #[serde(default)]
     pub total_reviewed: usize,
     /// Items answered correctly
+    #[serde(default)]
     pub correct: usize,
     /// Items answered incorrectly
+    #[serde(default)]
     pub incorrect: usize,
     /// Items where user override was used
+    #[serde(default)]
     pub user_overrides: usize,
 }
 
@@ -172,6 +199,7 @@ mod tests {
             cards: vec![],
             questions: vec![],
             tags: vec![],
+            algorithm: None,
         };
 
         assert!(set.is_empty());
@@ -2,11 +2,11 @@
 //!
 //! This module provides parsers for JSON, CSV, and Markdown formats.
 
-use crate::core::{Result, UtilError};
+use crate::core::{Result, SchedulerAlgorithm, UtilError};
 use crate::modules::learning::models::{Card, LearningSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Load a learning set from a JSON file.
 ///
@@ -61,6 +61,15 @@ pub fn load_from_json<P: AsRef<Path>>(path: P) -> Result<LearningSet> {
         });
     }
 
+    if let Some(algorithm) = &set.algorithm {
+        if let Err(reason) = algorithm.parse::<SchedulerAlgorithm>() {
+            return Err(UtilError::InvalidLearningSetFormat {
+                path: path.to_path_buf(),
+                reason,
+            });
+        }
+    }
+
     Ok(set)
 }
 
@@ -124,6 +133,7 @@ pub fn load_cards_from_csv<P: AsRef<Path>>(path: P, name: String) -> Result<Lear
             };
 
             cards.push(Card {
+                id: None,
                 front,
                 back,
                 tags,
@@ -145,6 +155,7 @@ pub fn load_cards_from_csv<P: AsRef<Path>>(path: P, name: String) -> Result<Lear
         cards,
         questions: Vec::new(),
         tags: Vec::new(),
+        algorithm: None,
     })
 }
 
@@ -192,8 +203,8 @@ pub fn load_from_markdown<P: AsRef<Path>>(path: P) -> Result<LearningSet> {
         let trimmed = line.trim();
 
         // Parse title
-        if trimmed.starts_with("# ") {
-            name = trimmed[2..].to_string();
+        if let Some(title) = trimmed.strip_prefix("# ") {
+            name = title.to_string();
         }
         // Parse front (Question)
         else if trimmed.starts_with("**Front:**") || trimmed.starts_with("Front:") 
@@ -222,6 +233,7 @@ pub fn load_from_markdown<P: AsRef<Path>>(path: P) -> Result<LearningSet> {
 
             if let Some(front) = current_front.take() {
                 cards.push(Card {
+                    id: None,
                     front,
                     back: back_text,
                     tags: Vec::new(),
@@ -244,6 +256,7 @@ pub fn load_from_markdown<P: AsRef<Path>>(path: P) -> Result<LearningSet> {
         cards,
         questions: Vec::new(),
         tags: Vec::new(),
+        algorithm: None,
     })
 }
 
@@ -275,6 +288,38 @@ pub fn load_auto<P: AsRef<Path>>(path: P) -> Result<LearningSet> {
     }
 }
 
+/// Check whether a file has the extension of a supported set format.
+pub fn is_learning_set<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| matches!(e.to_lowercase().as_str(), "json" | "csv" | "md" | "markdown"))
+        .unwrap_or(false)
+}
+
+/// Find the learning set files in a directory and its subdirectories,
+/// sorted by path.
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read.
+pub fn find_learning_sets<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_learning_set(&path) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -304,6 +349,13 @@ mod tests {
         assert_eq!(set.name, "Test Set");
         assert_eq!(set.cards.len(), 1);
         assert_eq!(set.cards[0].front, "Question 1");
+        assert_eq!(set.scheduler_algorithm(SchedulerAlgorithm::Sm2), SchedulerAlgorithm::Sm2);
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let json_content = r#"{"name": "Test", "algorithm": "anki", "cards": [{"front": "Q", "back": "A"}]}"#;
+        temp_file.write_all(json_content.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+        assert!(load_from_json(temp_file.path()).is_err());
     }
 
     #[test]
@@ -318,4 +370,17 @@ mod tests {
         assert_eq!(set.cards.len(), 2);
         assert_eq!(set.cards[0].tags.len(), 2);
     }
+
+    #[test]
+    fn test_find_learning_sets() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        for name in ["b.csv", "a.json", "notes.txt", "sub/c.md"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let found = find_learning_sets(dir.path()).unwrap();
+        let names: Vec<_> = found.iter().map(|p| p.strip_prefix(dir.path()).unwrap()).collect();
+        assert_eq!(names, [Path::new("a.json"), Path::new("b.csv"), Path::new("sub/c.md")]);
+    }
 }
@@ -0,0 +1,484 @@
+//! Persistent spaced-repetition progress.
+//!
+//! The scheduling state of every card (see [`ItemState`]) is kept in the storage backend (see
+//! [`Storage`](crate::storage::Storage)); with JSON storage in
+//! `highscores.progress.json` next to the highscore file, so each profile
+//! has its own progress. Sets are identified by their name, cards by their
+//! `id` or, if they have none, by a hash of their front. Positions in the
+//! file are never used, so cards can be reordered freely. When a set is
+//! opened the stored progress is reconciled with the cards: new cards start in box 0, removed cards are
+//! dropped and cards whose answer changed start over.
+//!
+//! Every card also records when it is due again, as scheduled by the
+//! set's algorithm; cards that were never answered are due right away.
+
+use crate::core::{Result, UtilError};
+use crate::modules::learning::models::{Card, LearningSet};
+use crate::core::config::LearningConfig;
+use crate::core::SchedulerAlgorithm;
+use crate::modules::learning::spaced_rep::{create_scheduler, ItemState, Scheduler};
+use crate::utils::fs::{self as safe_fs, FileLock};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Progress of one card.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CardProgress {
+    /// Hash of the answer the progress was made with
+    pub fingerprint: String,
+    /// Scheduling state of the card
+    #[serde(flatten)]
+    pub state: ItemState,
+}
+
+/// Progress of all cards of a set, by card key.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SetProgress {
+    #[serde(default)]
+    pub cards: BTreeMap<String, CardProgress>,
+}
+
+/// What reconciling the stored progress with a set changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Cards without stored progress
+    pub added: usize,
+    /// Stored cards that are no longer in the set
+    pub removed: usize,
+    /// Cards whose answer changed; they start over as new cards
+    pub edited: usize,
+}
+
+impl Reconciliation {
+    /// Whether the stored progress had to be changed.
+    pub fn is_changed(&self) -> bool {
+        self.added > 0 || self.removed > 0 || self.edited > 0
+    }
+}
+
+impl SetProgress {
+    /// Bring the stored progress in line with the cards of a set.
+    ///
+    /// # Arguments
+    ///
+    /// * `set` - The set as it is now
+    /// * `num_boxes` - Number of Leitner boxes; higher boxes are clamped
+    pub fn reconcile(&mut self, set: &LearningSet, num_boxes: usize) -> Reconciliation {
+        let last_box = num_boxes.saturating_sub(1);
+        let keys = card_keys(set);
+        let mut result = Reconciliation::default();
+
+        for (key, card) in keys.iter().zip(&set.cards) {
+            let fingerprint = fingerprint(card);
+            match self.cards.get_mut(key) {
+                Some(progress) if progress.fingerprint != fingerprint => {
+                    progress.state = ItemState::default();
+                    progress.fingerprint = fingerprint;
+                    result.edited += 1;
+                }
+                Some(progress) => progress.state.box_index = progress.state.box_index.min(last_box),
+                None => {
+                    self.cards.insert(
+                        key.clone(),
+                        CardProgress {
+                            fingerprint,
+                            state: ItemState::default(),
+                        },
+                    );
+                    result.added += 1;
+                }
+            }
+        }
+
+        let before = self.cards.len();
+        self.cards.retain(|key, _| keys.contains(key));
+        result.removed = before - self.cards.len();
+        result
+    }
+
+    /// Build the scheduler for a set from the stored progress.
+    ///
+    /// Items are the card indices of `set`; cards without progress are new
+    /// and due now.
+    ///
+    /// # Arguments
+    ///
+    /// * `set` - The set the progress belongs to
+    /// * `algorithm` - The scheduling algorithm
+    /// * `config` - Learning settings (boxes, intervals, desired retention)
+    pub fn scheduler(
+        &self,
+        set: &LearningSet,
+        algorithm: SchedulerAlgorithm,
+        config: &LearningConfig,
+    ) -> Box<dyn Scheduler> {
+        let states = card_keys(set)
+            .iter()
+            .map(|key| self.cards.get(key).map(|p| p.state.clone()).unwrap_or_default())
+            .collect();
+        create_scheduler(algorithm, config, states)
+    }
+
+    /// Store the scheduling state of a card after it was answered.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key of the card (see [`card_keys`])
+    /// * `card` - The card
+    /// * `state` - The state of the card after the answer
+    pub fn record(&mut self, key: &str, card: &Card, state: ItemState) {
+        self.cards.insert(
+            key.to_string(),
+            CardProgress {
+                fingerprint: fingerprint(card),
+                state,
+            },
+        );
+    }
+
+    /// Count the cards of a set that are due at `at`.
+    ///
+    /// The progress should be reconciled with the set first; cards without
+    /// progress count as new.
+    pub fn due_summary(&self, set: &LearningSet, at: DateTime<Utc>) -> DueSummary {
+        let mut summary = DueSummary {
+            name: set.name.clone(),
+            total: set.cards.len(),
+            ..Default::default()
+        };
+        for key in card_keys(set) {
+            match self.cards.get(&key) {
+                Some(card) if !card.state.is_new() => match card.state.due {
+                    Some(due) if due > at => {
+                        summary.next_due = Some(summary.next_due.map_or(due, |next| next.min(due)));
+                    }
+                    _ => summary.due += 1,
+                },
+                _ => summary.new += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// How many cards of a set are due.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DueSummary {
+    /// Name of the set
+    pub name: String,
+    /// Number of cards in the set
+    pub total: usize,
+    /// Answered cards that are due
+    pub due: usize,
+    /// Cards that were never answered
+    pub new: usize,
+    /// When the next card that is not due yet will be due
+    pub next_due: Option<DateTime<Utc>>,
+}
+
+impl DueSummary {
+    /// Cards to review: due and new ones.
+    pub fn to_review(&self) -> usize {
+        self.due + self.new
+    }
+}
+
+/// End of a local day; cards due before it are due "today".
+pub fn end_of_day(date: NaiveDate) -> DateTime<Utc> {
+    crate::storage::local_midnight(date.succ_opt().unwrap_or(date))
+}
+
+/// Progress of all sets, by set name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LearningProgress {
+    #[serde(default)]
+    pub sets: BTreeMap<String, SetProgress>,
+}
+
+impl LearningProgress {
+    /// Add the sets and cards of `other`; cards that exist in both keep the
+    /// more recently reviewed progress.
+    pub fn merge(&mut self, other: &LearningProgress) {
+        for (name, incoming) in &other.sets {
+            let set = self.sets.entry(name.clone()).or_default();
+            for (key, card) in &incoming.cards {
+                let newer = match set.cards.get(key) {
+                    Some(existing) => card.state.last_reviewed > existing.state.last_reviewed,
+                    None => true,
+                };
+                if newer {
+                    set.cards.insert(key.clone(), card.clone());
+                }
+            }
+        }
+    }
+
+    /// The stored progress of a set, reconciled with its current cards.
+    pub fn for_set(&self, set: &LearningSet, num_boxes: usize) -> (SetProgress, Reconciliation) {
+        let mut progress = self.sets.get(&set.name).cloned().unwrap_or_default();
+        let changes = progress.reconcile(set, num_boxes);
+        (progress, changes)
+    }
+
+    /// Total number of cards with stored progress.
+    pub fn card_count(&self) -> usize {
+        self.sets.values().map(|s| s.cards.len()).sum()
+    }
+}
+
+/// The stable keys of the cards of a set, in card order.
+///
+/// A card with an `id` uses it; otherwise the key is a hash of the
+/// normalized front. Cards with the same front get `-2`, `-3`, ... appended
+/// in the order they appear.
+pub fn card_keys(set: &LearningSet) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    set.cards
+        .iter()
+        .map(|card| {
+            let key = match &card.id {
+                Some(id) if !id.trim().is_empty() => id.trim().to_string(),
+                _ => format!("{:016x}", fnv1a(&normalize(&card.front))),
+            };
+            let count = seen.entry(key.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                key
+            } else {
+                format!("{}-{}", key, count)
+            }
+        })
+        .collect()
+}
+
+/// Hash of the normalized answer of a card.
+fn fingerprint(card: &Card) -> String {
+    format!("{:016x}", fnv1a(&normalize(&card.back)))
+}
+
+/// Lowercase and collapse whitespace, so reformatting a file keeps the keys.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` it is the same in every build.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The progress file of a profile, used by the JSON storage.
+pub struct ProgressStore {
+    file_path: PathBuf,
+}
+
+impl ProgressStore {
+    /// Create a store for the given progress file.
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        Self {
+            file_path: file_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Create the store that belongs to a highscore file
+    /// (`highscores.json` → `highscores.progress.json`).
+    pub fn for_highscore_file<P: AsRef<Path>>(highscore_file: P) -> Self {
+        Self::new(highscore_file.as_ref().with_extension("progress.json"))
+    }
+
+    /// Path of the progress file.
+    pub fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Load the progress of all sets.
+    ///
+    /// A missing file contains no progress yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(&self) -> Result<LearningProgress> {
+        if !self.file_path.exists() {
+            return Ok(LearningProgress::default());
+        }
+        let contents = std::fs::read(&self.file_path)?;
+        serde_json::from_slice(&contents).map_err(|e| {
+            UtilError::ProgressError(format!(
+                "Failed to parse {}: {}",
+                self.file_path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Store the progress of one set, keeping the other sets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or written.
+    pub fn save_set(&self, name: &str, progress: &SetProgress) -> Result<()> {
+        let _lock = FileLock::acquire(&self.file_path)?;
+        let mut all = self.load()?;
+        all.sets.insert(name.to_string(), progress.clone());
+        self.write(&all)
+    }
+
+    /// Replace the progress of all sets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn replace(&self, progress: &LearningProgress) -> Result<()> {
+        let _lock = FileLock::acquire(&self.file_path)?;
+        self.write(progress)
+    }
+
+    fn write(&self, progress: &LearningProgress) -> Result<()> {
+        safe_fs::write_atomic_with_backup(&self.file_path, &serde_json::to_vec_pretty(progress)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Config;
+    use crate::modules::learning::spaced_rep::Grade;
+    use tempfile::TempDir;
+
+    fn card(front: &str, back: &str) -> Card {
+        Card {
+            id: None,
+            front: front.to_string(),
+            back: back.to_string(),
+            tags: Vec::new(),
+            explanation: None,
+        }
+    }
+
+    fn set(cards: Vec<Card>) -> LearningSet {
+        LearningSet {
+            name: "Capitals".to_string(),
+            description: String::new(),
+            cards,
+            questions: Vec::new(),
+            tags: Vec::new(),
+            algorithm: None,
+        }
+    }
+
+    fn state(box_index: usize) -> ItemState {
+        ItemState {
+            box_index,
+            last_reviewed: Some(Utc::now()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_card_keys() {
+        let mut with_id = card("Spain", "Madrid");
+        with_id.id = Some("es".to_string());
+        let keys = card_keys(&set(vec![
+            card("France", "Paris"),
+            card("  france ", "Paris"),
+            with_id,
+        ]));
+        assert_eq!(keys[1], format!("{}-2", keys[0]));
+        assert_eq!(keys[2], "es");
+
+        // Keys do not depend on the position
+        let moved = card_keys(&set(vec![card("Italy", "Rome"), card("France", "Paris")]));
+        assert_eq!(moved[1], keys[0]);
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let original = set(vec![card("France", "Paris"), card("Italy", "Rome")]);
+        let keys = card_keys(&original);
+        let mut progress = SetProgress::default();
+        assert_eq!(progress.reconcile(&original, 5).added, 2);
+
+        progress.record(&keys[0], &original.cards[0], state(3));
+        progress.record(&keys[1], &original.cards[1], state(4));
+
+        // Reordered, one edited, one removed, one added
+        let edited = set(vec![
+            card("Germany", "Berlin"),
+            card("Italy", "Roma"),
+            card("France", "Paris"),
+        ]);
+        let changes = progress.reconcile(&edited, 5);
+        assert_eq!(changes, Reconciliation { added: 1, removed: 0, edited: 1 });
+
+        let config = Config::default().learning;
+        let leitner = progress.scheduler(&edited, SchedulerAlgorithm::Leitner, &config);
+        let boxes: Vec<usize> = (0..3).map(|i| leitner.state(i).unwrap().box_index).collect();
+        assert_eq!(boxes, [0, 0, 3]);
+        assert!(leitner.state(1).unwrap().is_new());
+
+        let changes = progress.reconcile(&set(vec![card("France", "Paris")]), 3);
+        assert_eq!(changes.removed, 2);
+        assert_eq!(progress.cards[&keys[0]].state.box_index, 2);
+    }
+
+    #[test]
+    fn test_due_summary() {
+        let capitals = set(vec![
+            card("France", "Paris"),
+            card("Italy", "Rome"),
+            card("Spain", "Madrid"),
+        ]);
+        let keys = card_keys(&capitals);
+        let now: DateTime<Utc> = "2024-03-01T12:00:00Z".parse().unwrap();
+        let mut config = Config::default().learning;
+        config.box_intervals = vec![1, 2, 4];
+        let mut progress = SetProgress::default();
+        progress.reconcile(&capitals, 5);
+        let mut leitner = progress.scheduler(&capitals, SchedulerAlgorithm::Leitner, &config);
+        for (index, grade) in [(0, Grade::Good), (1, Grade::Again)] {
+            leitner.review(index, grade, now);
+            let state = leitner.state(index).unwrap().clone();
+            progress.record(&keys[index], &capitals.cards[index], state);
+        }
+
+        let summary = progress.due_summary(&capitals, now);
+        assert_eq!((summary.total, summary.due, summary.new), (3, 0, 1));
+        assert_eq!(summary.next_due, Some(now + chrono::Duration::days(1)));
+
+        let summary = progress.due_summary(&capitals, now + chrono::Duration::days(1));
+        assert_eq!((summary.due, summary.to_review()), (1, 2));
+
+        // The schedule survives a reload, also with another algorithm
+        let reloaded = progress.scheduler(&capitals, SchedulerAlgorithm::Sm2, &config);
+        assert_eq!(reloaded.state(0).unwrap().due, Some(now + chrono::Duration::days(2)));
+        assert_eq!(reloaded.due_items(now), vec![2]);
+    }
+
+    #[test]
+    fn test_store() {
+        let dir = TempDir::new().unwrap();
+        let store = ProgressStore::for_highscore_file(dir.path().join("highscores.json"));
+        assert_eq!(store.path(), dir.path().join("highscores.progress.json"));
+
+        let capitals = set(vec![card("France", "Paris")]);
+        let (mut progress, changes) = store.load().unwrap().for_set(&capitals, 5);
+        assert_eq!(changes.added, 1);
+
+        let key = &card_keys(&capitals)[0];
+        progress.record(key, &capitals.cards[0], state(2));
+        store.save_set(&capitals.name, &progress).unwrap();
+        store.save_set("Other", &SetProgress::default()).unwrap();
+
+        let (loaded, changes) = store.load().unwrap().for_set(&capitals, 5);
+        assert!(!changes.is_changed());
+        assert_eq!(loaded.cards[key].state.box_index, 2);
+        assert_eq!(store.load().unwrap().sets.len(), 2);
+    }
+}
@@ -0,0 +1,183 @@
+//! Composition of a learning session.
+//!
+//! A session asks flashcards, quiz questions or both (see
+//! [`SessionContent`]). In a mixed session the questions are spread evenly
+//! between the cards, so they do not all come at the end.
+//!
+//! While a session runs, [`Movement`] counts how the answers moved the cards
+//! in the schedule and [`HardItem`] keeps the items that were answered wrong
+//! or with difficulty, for the results screen.
+
+use crate::core::{SchedulerAlgorithm, SessionContent};
+use crate::modules::learning::spaced_rep::{Grade, ItemState};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// One thing asked in a learning session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionItem {
+    /// A flashcard, by its index in the set
+    Card(usize),
+    /// A quiz question, by its index in the set
+    Question(usize),
+}
+
+/// The order in which a session asks its items.
+///
+/// # Arguments
+///
+/// * `cards` - Card indices in the order they should be asked
+/// * `question_count` - Number of quiz questions in the set (asked in file order)
+/// * `content` - What the session asks
+pub fn session_order(cards: Vec<usize>, question_count: usize, content: SessionContent) -> Vec<SessionItem> {
+    let cards = if content.includes_cards() { cards } else { Vec::new() };
+    let questions = if content.includes_questions() { question_count } else { 0 };
+    let total = cards.len() + questions;
+
+    let mut cards = cards.into_iter();
+    let mut asked_questions = 0;
+    let mut order = Vec::with_capacity(total);
+    for position in 1..=total {
+        // Ask a question whenever fewer than their share have been asked
+        if asked_questions < position * questions / total {
+            order.push(SessionItem::Question(asked_questions));
+            asked_questions += 1;
+        } else if let Some(card) = cards.next() {
+            order.push(SessionItem::Card(card));
+        }
+    }
+    order
+}
+
+/// How the answers of a session moved the cards in the schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Movement {
+    /// Cards moved to a higher box or a longer interval
+    #[serde(default)]
+    pub moved_up: usize,
+    /// Cards moved to a lower box or a shorter interval
+    #[serde(default)]
+    pub moved_down: usize,
+    /// Cards that kept their box or interval
+    #[serde(default)]
+    pub unchanged: usize,
+}
+
+impl Movement {
+    /// Count the change of a card's state by one answer.
+    ///
+    /// Leitner cards compare their box, the other algorithms their interval.
+    pub fn record(&mut self, algorithm: SchedulerAlgorithm, before: &ItemState, after: &ItemState) {
+        let ordering = match algorithm {
+            SchedulerAlgorithm::Leitner => after.box_index.cmp(&before.box_index),
+            _ => after.interval_days.cmp(&before.interval_days),
+        };
+        match ordering {
+            Ordering::Greater => self.moved_up += 1,
+            Ordering::Less => self.moved_down += 1,
+            Ordering::Equal => self.unchanged += 1,
+        }
+    }
+
+    /// Number of counted answers.
+    pub fn total(&self) -> usize {
+        self.moved_up + self.moved_down + self.unchanged
+    }
+}
+
+/// A card or question that was answered wrong or graded hard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardItem {
+    /// Front of the card or the question
+    pub prompt: String,
+    /// The correct answer
+    pub answer: String,
+    /// The grade of the answer (again or hard)
+    pub grade: Grade,
+    /// Similarity of a typed answer to the correct one (0.0-1.0)
+    pub score: Option<f64>,
+}
+
+/// The hardest items of a session: wrong answers before hard ones, the
+/// least similar answers first.
+///
+/// # Arguments
+///
+/// * `items` - Items answered wrong or graded hard, in session order
+/// * `limit` - Maximum number of items
+pub fn hardest_items(items: &[HardItem], limit: usize) -> Vec<&HardItem> {
+    let mut hardest: Vec<&HardItem> = items.iter().collect();
+    hardest.sort_by(|a, b| {
+        a.grade
+            .cmp(&b.grade)
+            .then(a.score.unwrap_or(0.0).total_cmp(&b.score.unwrap_or(0.0)))
+    });
+    // The same item may have been missed more than once
+    let mut seen: Vec<&str> = Vec::new();
+    hardest.retain(|item| {
+        let first = !seen.contains(&item.prompt.as_str());
+        seen.push(&item.prompt);
+        first
+    });
+    hardest.truncate(limit);
+    hardest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SessionItem::{Card, Question};
+
+    #[test]
+    fn test_session_order() {
+        assert_eq!(
+            session_order(vec![2, 0, 1], 2, SessionContent::Mixed),
+            vec![Card(2), Card(0), Question(0), Card(1), Question(1)]
+        );
+        assert_eq!(
+            session_order(vec![2, 0, 1], 2, SessionContent::Cards),
+            vec![Card(2), Card(0), Card(1)]
+        );
+        assert_eq!(
+            session_order(vec![2, 0, 1], 2, SessionContent::Questions),
+            vec![Question(0), Question(1)]
+        );
+        assert_eq!(session_order(Vec::new(), 2, SessionContent::Mixed), vec![Question(0), Question(1)]);
+        assert!(session_order(Vec::new(), 0, SessionContent::Mixed).is_empty());
+    }
+
+    #[test]
+    fn test_movement() {
+        let state = |box_index, interval_days| ItemState {
+            box_index,
+            interval_days,
+            ..Default::default()
+        };
+        let mut movement = Movement::default();
+        movement.record(SchedulerAlgorithm::Leitner, &state(1, 2), &state(2, 4));
+        movement.record(SchedulerAlgorithm::Leitner, &state(1, 2), &state(0, 1));
+        movement.record(SchedulerAlgorithm::Sm2, &state(0, 6), &state(0, 6));
+        movement.record(SchedulerAlgorithm::Fsrs, &state(0, 0), &state(0, 3));
+        assert_eq!((movement.moved_up, movement.moved_down, movement.unchanged), (2, 1, 1));
+        assert_eq!(movement.total(), 4);
+    }
+
+    #[test]
+    fn test_hardest_items() {
+        let item = |prompt: &str, grade, score| HardItem {
+            prompt: prompt.to_string(),
+            answer: String::new(),
+            grade,
+            score,
+        };
+        let items = vec![
+            item("hard", Grade::Hard, None),
+            item("close", Grade::Again, Some(0.7)),
+            item("far", Grade::Again, Some(0.1)),
+            item("close", Grade::Again, Some(0.8)),
+        ];
+        let prompts: Vec<&str> = hardest_items(&items, 5).iter().map(|i| i.prompt.as_str()).collect();
+        assert_eq!(prompts, ["far", "close", "hard"]);
+        assert_eq!(hardest_items(&items, 1).len(), 1);
+    }
+}
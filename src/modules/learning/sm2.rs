@@ -0,0 +1,127 @@
+//! SuperMemo 2 scheduling.
+//!
+//! Every item has an ease factor (2.5 for new items). A correct answer
+//! schedules the item after 1 day, then after 6 days, then after the
+//! previous interval times the ease. The grade adjusts the ease; "again"
+//! lowers it and starts the intervals over.
+
+use crate::core::SchedulerAlgorithm;
+use crate::modules::learning::spaced_rep::{Grade, ItemState, Scheduler};
+use chrono::{DateTime, Utc};
+
+/// Ease factor of new items.
+pub const INITIAL_EASE: f64 = 2.5;
+/// Lowest possible ease factor.
+pub const MIN_EASE: f64 = 1.3;
+
+/// SM-2 response quality (0-5) of a grade.
+fn quality(grade: Grade) -> f64 {
+    match grade {
+        Grade::Again => 1.0,
+        Grade::Hard => 3.0,
+        Grade::Good => 4.0,
+        Grade::Easy => 5.0,
+    }
+}
+
+/// Ease factor after an answer of the given grade.
+pub fn next_ease(ease: f64, grade: Grade) -> f64 {
+    let miss = 5.0 - quality(grade);
+    (ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE)
+}
+
+/// SM-2 scheduler.
+pub struct Sm2Scheduler {
+    states: Vec<ItemState>,
+}
+
+impl Sm2Scheduler {
+    /// Create a scheduler from stored item states.
+    pub fn new(states: Vec<ItemState>) -> Self {
+        Self { states }
+    }
+}
+
+impl Scheduler for Sm2Scheduler {
+    fn algorithm(&self) -> SchedulerAlgorithm {
+        SchedulerAlgorithm::Sm2
+    }
+
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    fn state(&self, item_id: usize) -> Option<&ItemState> {
+        self.states.get(item_id)
+    }
+
+    fn review(&mut self, item_id: usize, grade: Grade, reviewed: DateTime<Utc>) {
+        let Some(state) = self.states.get_mut(item_id) else {
+            return;
+        };
+
+        let ease = next_ease(state.ease.unwrap_or(INITIAL_EASE), grade);
+        let interval_days = if !grade.is_correct() {
+            1
+        } else {
+            match state.repetitions + 1 {
+                1 => 1,
+                2 => 6,
+                _ => (state.interval_days.max(1) as f64 * ease).round() as u32,
+            }
+        };
+
+        state.ease = Some(ease);
+        state.record(grade, reviewed, interval_days);
+    }
+
+    fn describe(&self, item_id: usize) -> String {
+        match self.state(item_id) {
+            Some(state) if !state.is_new() => format!(
+                "Ease {:.2}, {}d",
+                state.ease.unwrap_or(INITIAL_EASE),
+                state.interval_days
+            ),
+            _ => "New".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_sm2_intervals() {
+        let now: DateTime<Utc> = "2024-03-01T12:00:00Z".parse().unwrap();
+        let mut sm2 = Sm2Scheduler::new(vec![ItemState::default(); 2]);
+        assert_eq!(sm2.describe(0), "New");
+
+        let mut at = now;
+        for expected in [1, 6, 15] {
+            sm2.review(0, Grade::Good, at);
+            let state = sm2.state(0).unwrap();
+            assert_eq!(state.interval_days, expected);
+            assert_eq!(state.due, Some(at + Duration::days(expected as i64)));
+            at = state.due.unwrap();
+        }
+        assert_eq!(sm2.state(0).unwrap().ease, Some(2.5));
+
+        sm2.review(0, Grade::Again, at);
+        let state = sm2.state(0).unwrap();
+        assert_eq!((state.interval_days, state.repetitions, state.lapses), (1, 0, 1));
+        assert!((state.ease.unwrap() - 1.96).abs() < 1e-9);
+
+        // New items come after the overdue ones
+        assert_eq!(sm2.due_items(at + Duration::days(2)), vec![0, 1]);
+        assert_eq!(sm2.due_items(at), vec![1]);
+    }
+
+    #[test]
+    fn test_ease_bounds() {
+        assert!((next_ease(2.5, Grade::Easy) - 2.6).abs() < 1e-9);
+        assert!((next_ease(2.5, Grade::Hard) - 2.36).abs() < 1e-9);
+        assert_eq!(next_ease(1.4, Grade::Again), MIN_EASE);
+    }
+}
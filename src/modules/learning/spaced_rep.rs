@@ -1,22 +1,317 @@
-//! Spaced repetition system using the Leitner box method.
+//! Spaced repetition scheduling.
 //!
-//! This module implements a simple but effective spaced repetition algorithm
-//! to help users learn more efficiently by reviewing difficult items more frequently.
-
+//! A [`Scheduler`] decides when each item of a set is due again after a
+//! graded answer. Three algorithms implement it:
+//!
+//! - [`LeitnerBox`]: boxes with a fixed review interval each
+//! - [`Sm2Scheduler`](super::sm2::Sm2Scheduler): SuperMemo 2, an ease
+//!   factor per item that scales its interval
+//! - [`FsrsScheduler`](super::fsrs::FsrsScheduler): a memory model with a
+//!   stability and difficulty per item
+//!
+//! All of them keep the state of an item in an [`ItemState`], so the
+//! progress of a set survives switching the algorithm.
+
+use crate::core::config::LearningConfig;
+use crate::core::SchedulerAlgorithm;
+use crate::modules::learning::fsrs::FsrsScheduler;
+use crate::modules::learning::sm2::Sm2Scheduler;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+/// Items with an interval of at least this many days count as mastered
+/// (SM-2 and FSRS; Leitner items are mastered in the last box).
+pub const MASTERED_INTERVAL_DAYS: u32 = 21;
+
+/// How well an item was remembered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Grade {
+    /// Forgotten or answered wrong
+    Again,
+    /// Remembered with serious difficulty
+    Hard,
+    /// Remembered
+    Good,
+    /// Remembered without effort
+    Easy,
+}
+
+impl Grade {
+    /// All grades, from worst to best.
+    pub const ALL: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+    /// Returns the lowercase identifier.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+
+    /// The grade for a key from 1 (again) to 4 (easy).
+    pub fn from_number(number: u32) -> Option<Self> {
+        Self::ALL.get((number as usize).checked_sub(1)?).copied()
+    }
+
+    /// Whether the answer counts as correct.
+    pub fn is_correct(&self) -> bool {
+        *self != Grade::Again
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Grade::Again => "Again",
+            Grade::Hard => "Hard",
+            Grade::Good => "Good",
+            Grade::Easy => "Easy",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Grade {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "again" | "1" => Ok(Grade::Again),
+            "hard" | "2" => Ok(Grade::Hard),
+            "good" | "3" => Ok(Grade::Good),
+            "easy" | "4" => Ok(Grade::Easy),
+            _ => Err(format!("Unknown grade: {} (expected again, hard, good or easy)", s)),
+        }
+    }
+}
+
+/// Scheduling state of one item.
+///
+/// Fields that belong to another algorithm are kept untouched.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ItemState {
+    /// Leitner box (0 = new or last answered wrong)
+    #[serde(default)]
+    pub box_index: usize,
+    /// Answers in a row that were not "again"
+    #[serde(default)]
+    pub repetitions: u32,
+    /// Times a reviewed item was forgotten
+    #[serde(default)]
+    pub lapses: u32,
+    /// Days between the last review and the due date
+    #[serde(default)]
+    pub interval_days: u32,
+    /// SM-2 ease factor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ease: Option<f64>,
+    /// FSRS stability: days until recall drops to 90%
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability: Option<f64>,
+    /// FSRS difficulty (1-10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<f64>,
+    /// When the item was last answered
+    #[serde(default)]
+    pub last_reviewed: Option<DateTime<Utc>>,
+    /// When the item is due again (`None` = now)
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+}
+
+impl ItemState {
+    /// Whether the item was never answered.
+    pub fn is_new(&self) -> bool {
+        self.last_reviewed.is_none()
+    }
+
+    /// Whether the item is due at `at`.
+    pub fn is_due(&self, at: DateTime<Utc>) -> bool {
+        match self.due {
+            Some(due) => due <= at,
+            None => true,
+        }
+    }
+
+    /// Days since the last review at `at` (0 for new items).
+    pub fn elapsed_days(&self, at: DateTime<Utc>) -> f64 {
+        self.last_reviewed
+            .map(|last| (at - last).num_seconds().max(0) as f64 / 86_400.0)
+            .unwrap_or(0.0)
+    }
+
+    /// Update the fields every algorithm shares after an answer.
+    ///
+    /// # Arguments
+    ///
+    /// * `grade` - The grade of the answer
+    /// * `reviewed` - When the answer was given
+    /// * `interval_days` - Days until the item is due again
+    pub fn record(&mut self, grade: Grade, reviewed: DateTime<Utc>, interval_days: u32) {
+        if grade.is_correct() {
+            self.repetitions += 1;
+        } else {
+            if !self.is_new() {
+                self.lapses += 1;
+            }
+            self.repetitions = 0;
+        }
+        self.interval_days = interval_days;
+        self.last_reviewed = Some(reviewed);
+        self.due = Some(reviewed + Duration::days(interval_days as i64));
+    }
+}
+
+/// Summary of the state of all items of a scheduler.
+#[derive(Debug, Clone)]
+pub struct SchedulerSummary {
+    pub total_items: usize,
+    /// Items that were never answered
+    pub new_items: usize,
+    pub mastered_items: usize,
+    pub in_progress_items: usize,
+    /// Items per Leitner box (empty for other algorithms)
+    pub box_counts: Vec<usize>,
+}
+
+impl SchedulerSummary {
+    /// Calculate mastery percentage.
+    pub fn mastery_percentage(&self) -> f64 {
+        if self.total_items == 0 {
+            return 0.0;
+        }
+        (self.mastered_items as f64 / self.total_items as f64) * 100.0
+    }
+}
+
+/// A spaced-repetition algorithm scheduling the items of one set.
+///
+/// Items are identified by their index in the set.
+pub trait Scheduler {
+    /// The algorithm of the scheduler.
+    fn algorithm(&self) -> SchedulerAlgorithm;
+
+    /// Number of items.
+    fn len(&self) -> usize;
+
+    /// Check whether there are no items.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the scheduling state of an item.
+    fn state(&self, item_id: usize) -> Option<&ItemState>;
+
+    /// Record a graded answer given at `reviewed` and schedule the item's
+    /// next review.
+    fn review(&mut self, item_id: usize, grade: Grade, reviewed: DateTime<Utc>);
+
+    /// Get every item that is due at `at`, in the order to ask them.
+    ///
+    /// By default the most overdue items come first and new items last.
+    fn due_items(&self, at: DateTime<Utc>) -> Vec<usize> {
+        let mut due: Vec<usize> = (0..self.len())
+            .filter(|&item_id| self.is_due(item_id, at))
+            .collect();
+        due.sort_by_key(|&item_id| {
+            let state = self.state(item_id).cloned().unwrap_or_default();
+            (state.is_new(), state.due)
+        });
+        due
+    }
+
+    /// Check whether an item is mastered.
+    fn is_mastered(&self, item_id: usize) -> bool {
+        self.state(item_id)
+            .is_some_and(|s| s.interval_days >= MASTERED_INTERVAL_DAYS)
+    }
+
+    /// A short description of an item's state, e.g. "Box 2/5".
+    fn describe(&self, item_id: usize) -> String;
+
+    /// Summarize the state of all items.
+    fn summary(&self) -> SchedulerSummary {
+        let total = self.len();
+        let mastered = (0..total).filter(|&i| self.is_mastered(i)).count();
+        SchedulerSummary {
+            total_items: total,
+            new_items: (0..total)
+                .filter(|&i| self.state(i).is_some_and(ItemState::is_new))
+                .count(),
+            mastered_items: mastered,
+            in_progress_items: total - mastered,
+            box_counts: Vec::new(),
+        }
+    }
+
+    /// Get the next item that is due at `at`.
+    fn next_item(&self, at: DateTime<Utc>) -> Option<usize> {
+        self.due_items(at).first().copied()
+    }
+
+    /// Check whether an item is due at `at`.
+    fn is_due(&self, item_id: usize, at: DateTime<Utc>) -> bool {
+        self.state(item_id).is_some_and(|s| s.is_due(at))
+    }
+
+    /// Get the earliest due date after `at`, if any item is scheduled later.
+    fn next_due_after(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (0..self.len())
+            .filter_map(|item_id| self.state(item_id)?.due)
+            .filter(|&due| due > at)
+            .min()
+    }
+}
+
+/// Create the scheduler for an algorithm.
+///
+/// # Arguments
+///
+/// * `algorithm` - The scheduling algorithm
+/// * `config` - Learning settings (boxes, intervals, desired retention)
+/// * `states` - The stored state of every item
+pub fn create_scheduler(
+    algorithm: SchedulerAlgorithm,
+    config: &LearningConfig,
+    states: Vec<ItemState>,
+) -> Box<dyn Scheduler> {
+    match algorithm {
+        SchedulerAlgorithm::Leitner => Box::new(
+            LeitnerBox::with_states(config.leitner_boxes, states)
+                .with_intervals(config.box_intervals.clone()),
+        ),
+        SchedulerAlgorithm::Sm2 => Box::new(Sm2Scheduler::new(states)),
+        SchedulerAlgorithm::Fsrs => Box::new(FsrsScheduler::new(states, config.desired_retention)),
+    }
+}
+
+/// Review interval of a box in days when none is configured: 1, 2, 4, 8, ...
+pub fn default_interval(box_index: usize) -> u32 {
+    1u32 << box_index.min(16)
+}
 
 /// Leitner box system for spaced repetition.
 ///
-/// Items start in box 0. When answered correctly, they move to the next box.
-/// When answered incorrectly, they move back to box 0.
-/// Items in lower boxes are reviewed more frequently.
+/// Items start in box 0. When answered correctly, they move to the next box
+/// (two boxes if easy, none if hard). When answered incorrectly, they move
+/// back to box 0.
+/// Every box has a review interval; an answered item is due again after the
+/// interval of its new box, so items in lower boxes are reviewed more
+/// frequently. Items that were never answered are always due.
 pub struct LeitnerBox {
     /// Number of boxes in the system
     num_boxes: usize,
     /// Each box contains indices of items
     boxes: Vec<VecDeque<usize>>,
-    /// Track which box each item is in
-    item_locations: Vec<usize>,
+    /// Review interval of each box in days
+    intervals: Vec<u32>,
+    /// State of each item, including its box
+    states: Vec<ItemState>,
 }
 
 impl LeitnerBox {
@@ -35,22 +330,74 @@ impl LeitnerBox {
     /// let leitner = LeitnerBox::new(5, 20);
     /// ```
     pub fn new(num_boxes: usize, num_items: usize) -> Self {
+        Self::with_states(num_boxes, vec![ItemState::default(); num_items])
+    }
+
+    /// Create a Leitner box system with items already placed in boxes.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_boxes` - Number of boxes (typically 3-7)
+    /// * `placements` - Box of each item; boxes past the last are clamped
+    pub fn with_placements(num_boxes: usize, placements: Vec<usize>) -> Self {
+        let states = placements
+            .into_iter()
+            .map(|box_index| ItemState {
+                box_index,
+                ..Default::default()
+            })
+            .collect();
+        Self::with_states(num_boxes, states)
+    }
+
+    /// Create a Leitner box system from stored item states.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_boxes` - Number of boxes (typically 3-7)
+    /// * `states` - State of each item; boxes past the last are clamped
+    pub fn with_states(num_boxes: usize, mut states: Vec<ItemState>) -> Self {
+        let num_boxes = num_boxes.max(1);
         let mut boxes = vec![VecDeque::new(); num_boxes];
-        
-        // All items start in box 0
-        for i in 0..num_items {
-            boxes[0].push_back(i);
-        }
 
-        let item_locations = vec![0; num_items];
+        for (item_id, state) in states.iter_mut().enumerate() {
+            state.box_index = state.box_index.min(num_boxes - 1);
+            boxes[state.box_index].push_back(item_id);
+        }
 
         Self {
             num_boxes,
             boxes,
-            item_locations,
+            intervals: (0..num_boxes).map(default_interval).collect(),
+            states,
         }
     }
 
+    /// Use the given review intervals (in days, by box).
+    ///
+    /// Boxes without an interval use the last one; an empty list keeps the
+    /// default intervals.
+    pub fn with_intervals(mut self, intervals: Vec<u32>) -> Self {
+        if !intervals.is_empty() {
+            self.intervals = intervals;
+        }
+        self
+    }
+
+    /// Review interval of a box in days.
+    pub fn interval_days(&self, box_index: usize) -> u32 {
+        self.intervals
+            .get(box_index)
+            .or(self.intervals.last())
+            .copied()
+            .unwrap_or(1)
+    }
+
+    /// Review interval of a box.
+    pub fn interval(&self, box_index: usize) -> Duration {
+        Duration::days(self.interval_days(box_index) as i64)
+    }
+
     /// Record a correct answer for an item.
     ///
     /// Moves the item to the next box (if not already in the last box).
@@ -59,19 +406,7 @@ impl LeitnerBox {
     ///
     /// * `item_id` - Index of the item
     pub fn answer_correct(&mut self, item_id: usize) {
-        if item_id >= self.item_locations.len() {
-            return;
-        }
-
-        let current_box = self.item_locations[item_id];
-        let next_box = (current_box + 1).min(self.num_boxes - 1);
-
-        // Remove from current box
-        self.boxes[current_box].retain(|&id| id != item_id);
-
-        // Add to next box
-        self.boxes[next_box].push_back(item_id);
-        self.item_locations[item_id] = next_box;
+        self.review(item_id, Grade::Good, Utc::now());
     }
 
     /// Record an incorrect answer for an item.
@@ -82,18 +417,7 @@ impl LeitnerBox {
     ///
     /// * `item_id` - Index of the item
     pub fn answer_incorrect(&mut self, item_id: usize) {
-        if item_id >= self.item_locations.len() {
-            return;
-        }
-
-        let current_box = self.item_locations[item_id];
-
-        // Remove from current box
-        self.boxes[current_box].retain(|&id| id != item_id);
-
-        // Add to box 0
-        self.boxes[0].push_back(item_id);
-        self.item_locations[item_id] = 0;
+        self.review(item_id, Grade::Again, Utc::now());
     }
 
     /// Get the next item to review.
@@ -113,9 +437,19 @@ impl LeitnerBox {
         None
     }
 
+    /// Get every item once, lowest box first.
+    pub fn review_order(&self) -> Vec<usize> {
+        self.boxes.iter().flatten().copied().collect()
+    }
+
     /// Get the box number for a specific item.
     pub fn get_item_box(&self, item_id: usize) -> Option<usize> {
-        self.item_locations.get(item_id).copied()
+        self.states.get(item_id).map(|s| s.box_index)
+    }
+
+    /// Get the box of every item, by item index.
+    pub fn placements(&self) -> Vec<usize> {
+        self.states.iter().map(|s| s.box_index).collect()
     }
 
     /// Get the number of items in each box.
@@ -130,30 +464,89 @@ impl LeitnerBox {
 
     /// Check if all items have been mastered (in the last box).
     pub fn all_mastered(&self) -> bool {
-        self.boxes[self.num_boxes - 1].len() == self.item_locations.len()
+        self.boxes[self.num_boxes - 1].len() == self.states.len()
     }
 
-    /// Reset all items back to box 0.
+    /// Reset all items back to box 0, due now.
     pub fn reset(&mut self) {
         for box_items in &mut self.boxes {
             box_items.clear();
         }
 
-        for (i, location) in self.item_locations.iter_mut().enumerate() {
+        for (i, state) in self.states.iter_mut().enumerate() {
             self.boxes[0].push_back(i);
-            *location = 0;
+            *state = ItemState::default();
         }
     }
+}
+
+impl Scheduler for LeitnerBox {
+    fn algorithm(&self) -> SchedulerAlgorithm {
+        SchedulerAlgorithm::Leitner
+    }
 
-    /// Get a summary of the current state.
-    pub fn summary(&self) -> LeitnerSummary {
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    fn state(&self, item_id: usize) -> Option<&ItemState> {
+        self.states.get(item_id)
+    }
+
+    /// Move the item between the boxes; it is due again after the interval
+    /// of its new box.
+    fn review(&mut self, item_id: usize, grade: Grade, reviewed: DateTime<Utc>) {
+        if item_id >= self.states.len() {
+            return;
+        }
+
+        let current_box = self.states[item_id].box_index;
+        let new_box = match grade {
+            Grade::Again => 0,
+            Grade::Hard => current_box,
+            Grade::Good => current_box + 1,
+            Grade::Easy => current_box + 2,
+        }
+        .min(self.num_boxes - 1);
+
+        // Remove from current box
+        self.boxes[current_box].retain(|&id| id != item_id);
+
+        // Add to new box
+        self.boxes[new_box].push_back(item_id);
+        let interval_days = self.interval_days(new_box);
+        let state = &mut self.states[item_id];
+        state.box_index = new_box;
+        state.record(grade, reviewed, interval_days);
+    }
+
+    /// Items due at `at`, lowest box first.
+    fn due_items(&self, at: DateTime<Utc>) -> Vec<usize> {
+        self.boxes
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&item_id| self.is_due(item_id, at))
+            .collect()
+    }
+
+    fn is_mastered(&self, item_id: usize) -> bool {
+        self.get_item_box(item_id) == Some(self.num_boxes - 1)
+    }
+
+    fn describe(&self, item_id: usize) -> String {
+        format!("Box {}/{}", self.get_item_box(item_id).unwrap_or(0) + 1, self.num_boxes)
+    }
+
+    fn summary(&self) -> SchedulerSummary {
         let counts = self.get_box_counts();
-        let total = self.item_locations.len();
+        let total = self.states.len();
         let mastered = counts.last().copied().unwrap_or(0);
         let in_progress = total - mastered;
 
-        LeitnerSummary {
+        SchedulerSummary {
             total_items: total,
+            new_items: self.states.iter().filter(|s| s.is_new()).count(),
             mastered_items: mastered,
             in_progress_items: in_progress,
             box_counts: counts,
@@ -161,32 +554,13 @@ impl LeitnerBox {
     }
 }
 
-/// Summary of the Leitner box state.
-#[derive(Debug, Clone)]
-pub struct LeitnerSummary {
-    pub total_items: usize,
-    pub mastered_items: usize,
-    pub in_progress_items: usize,
-    pub box_counts: Vec<usize>,
-}
-
-impl LeitnerSummary {
-    /// Calculate mastery percentage.
-    pub fn mastery_percentage(&self) -> f64 {
-        if self.total_items == 0 {
-            return 0.0;
-        }
-        (self.mastered_items as f64 / self.total_items as f64) * 100.0
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_leitner_basic() {
-        let mut leitner = LeitnerBox::new(5, 10);
+        let leitner = LeitnerBox::new(5, 10);
 
         // All items start in box 0
         assert_eq!(leitner.get_item_box(0), Some(0));
@@ -268,4 +642,54 @@ mod tests {
             assert_eq!(leitner.get_item_box(i), Some(0));
         }
     }
+
+    #[test]
+    fn test_with_placements() {
+        let leitner = LeitnerBox::with_placements(3, vec![2, 0, 7, 1]);
+
+        assert_eq!(leitner.placements(), &[2, 0, 2, 1]);
+        assert_eq!(leitner.get_box_counts(), vec![1, 1, 2]);
+        assert_eq!(leitner.review_order(), vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn test_due_dates() {
+        let now: DateTime<Utc> = "2024-03-01T12:00:00Z".parse().unwrap();
+        let mut leitner = LeitnerBox::new(3, 3).with_intervals(vec![1, 3]);
+        assert_eq!(leitner.interval(2), Duration::days(3));
+        assert_eq!(leitner.due_items(now), vec![0, 1, 2]);
+
+        leitner.review(0, Grade::Good, now);
+        leitner.review(1, Grade::Again, now);
+        assert_eq!(leitner.state(0).unwrap().due, Some(now + Duration::days(3)));
+        assert_eq!(leitner.state(1).unwrap().due, Some(now + Duration::days(1)));
+        assert_eq!(leitner.next_item(now), Some(2));
+        assert_eq!(leitner.next_due_after(now), Some(now + Duration::days(1)));
+
+        let later = now + Duration::days(2);
+        assert_eq!(leitner.due_items(later), vec![2, 1]);
+        assert!(!leitner.is_due(0, later));
+    }
+
+    #[test]
+    fn test_graded_answers() {
+        let now: DateTime<Utc> = "2024-03-01T12:00:00Z".parse().unwrap();
+        let mut leitner = LeitnerBox::new(5, 2);
+
+        leitner.review(0, Grade::Easy, now);
+        assert_eq!(leitner.get_item_box(0), Some(2));
+        leitner.review(0, Grade::Hard, now);
+        assert_eq!(leitner.get_item_box(0), Some(2));
+        leitner.review(0, Grade::Again, now);
+        let state = leitner.state(0).unwrap();
+        assert_eq!((state.box_index, state.repetitions, state.lapses), (0, 0, 1));
+
+        let summary = leitner.summary();
+        assert_eq!((summary.new_items, summary.box_counts[0]), (1, 2));
+        assert_eq!(leitner.describe(0), "Box 1/5");
+
+        assert_eq!(Grade::from_number(3), Some(Grade::Good));
+        assert_eq!(Grade::from_number(0), None);
+        assert_eq!("easy".parse::<Grade>().unwrap(), Grade::Easy);
+    }
 }
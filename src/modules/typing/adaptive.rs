@@ -0,0 +1,248 @@
+//! Adaptive practice generator for weak keys.
+//!
+//! Instead of shuffling the word list, the generator weights every word by
+//! how much it exercises the keys and bigrams the user mistypes or hesitates
+//! on most, based on the recorded keystroke history.
+
+use crate::core::{Difficulty, Language, Result};
+use crate::modules::typing::analysis::{KeyStat, KeystrokeAnalysis};
+use crate::modules::typing::word_loader::{WordFilter, WordLoader};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Attempts needed before the statistics of a key are trusted.
+const MIN_KEY_ATTEMPTS: usize = 5;
+
+/// Measurements needed before the statistics of a bigram are trusted.
+const MIN_BIGRAM_SAMPLES: usize = 3;
+
+/// Number of weakest keys and bigrams that are practised.
+const TARGET_COUNT: usize = 6;
+
+/// How much an error counts compared to a slow keystroke.
+const ERROR_WEIGHT: f64 = 10.0;
+
+/// Weight of words that contain none of the weak keys, so the text keeps
+/// some variety.
+const BASE_WEIGHT: f64 = 0.05;
+
+/// The weakest keys and bigrams of a user, with a weight for each.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeaknessProfile {
+    keys: HashMap<char, f64>,
+    bigrams: HashMap<String, f64>,
+}
+
+impl WeaknessProfile {
+    /// Build the profile from a keystroke analysis.
+    ///
+    /// A key's weakness is its error rate (weighted by `ERROR_WEIGHT`) plus
+    /// how much slower than average it is typed. Only the weakest keys and
+    /// bigrams with enough data are kept; letter case is ignored.
+    pub fn from_analysis(analysis: &KeystrokeAnalysis) -> Self {
+        let key_mean = mean_latency(analysis.keys.values());
+
+        let mut keys: Vec<char> = analysis
+            .keys
+            .keys()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(|c| c.to_lowercase())
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        let keys = weakest(
+            keys.into_iter()
+                .map(|c| (c, analysis.key_stat(c)))
+                .filter(|(_, s)| s.attempts >= MIN_KEY_ATTEMPTS)
+                .map(|(c, s)| (c, weakness(&s, key_mean))),
+        );
+
+        let mut bigrams: HashMap<String, KeyStat> = HashMap::new();
+        for (ngram, stat) in &analysis.bigrams {
+            if !ngram.contains(char::is_whitespace) {
+                bigrams.entry(ngram.to_lowercase()).or_default().merge(stat);
+            }
+        }
+        let bigram_mean = mean_latency(bigrams.values());
+        let bigrams = weakest(
+            bigrams
+                .into_iter()
+                .filter(|(_, s)| s.latency_samples >= MIN_BIGRAM_SAMPLES)
+                .map(|(ngram, s)| (ngram, weakness(&s, bigram_mean))),
+        );
+
+        Self { keys, bigrams }
+    }
+
+    /// Whether there is enough history to find weak keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.bigrams.is_empty()
+    }
+
+    /// The targeted keys, weakest first.
+    pub fn weak_keys(&self) -> Vec<char> {
+        let mut keys: Vec<(&char, &f64)> = self.keys.iter().collect();
+        keys.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(b.0)));
+        keys.into_iter().map(|(c, _)| *c).collect()
+    }
+
+    /// How much a word exercises the weak keys and bigrams.
+    pub fn word_score(&self, word: &str) -> f64 {
+        let chars: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+        let keys: f64 = chars.iter().filter_map(|c| self.keys.get(c)).sum();
+        let bigrams: f64 = chars
+            .windows(2)
+            .filter_map(|pair| self.bigrams.get(&pair.iter().collect::<String>()))
+            .sum();
+        keys + bigrams
+    }
+}
+
+/// Word generator that practises the weakest keys.
+pub struct AdaptiveGenerator<'a> {
+    loader: &'a WordLoader,
+    profile: WeaknessProfile,
+}
+
+impl<'a> AdaptiveGenerator<'a> {
+    /// Create a generator on top of a word loader.
+    pub fn new(loader: &'a WordLoader, profile: WeaknessProfile) -> Self {
+        Self { loader, profile }
+    }
+
+    /// The weakness profile the words are chosen by.
+    pub fn profile(&self) -> &WeaknessProfile {
+        &self.profile
+    }
+
+    /// Generate a practice text.
+    ///
+    /// Words matching the filter are sampled without replacement, weighted
+    /// by [`WeaknessProfile::word_score`]. Without any history the words
+    /// are picked at random, like a normal test.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the word file cannot be loaded or no words match.
+    pub fn generate_text(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        filter: &WordFilter,
+    ) -> Result<String> {
+        let candidates = self.loader.load_candidates(language, difficulty, filter)?;
+        let words = self.select_words(candidates, difficulty.word_count(), &mut rand::thread_rng());
+        Ok(words.join(" "))
+    }
+
+    /// Select `count` words, preferring words with weak keys.
+    pub fn select_words<R: Rng>(&self, mut words: Vec<String>, count: usize, rng: &mut R) -> Vec<String> {
+        if self.profile.is_empty() {
+            words.shuffle(rng);
+            words.truncate(count);
+            return words;
+        }
+
+        // Weighted sampling without replacement (Efraimidis-Spirakis):
+        // each word gets the key u^(1/w), the largest keys are taken
+        let mut keyed: Vec<(f64, String)> = words
+            .into_iter()
+            .map(|word| {
+                let weight = BASE_WEIGHT + self.profile.word_score(&word);
+                let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+                (u.powf(1.0 / weight), word)
+            })
+            .collect();
+        keyed.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        keyed.into_iter().take(count).map(|(_, word)| word).collect()
+    }
+}
+
+fn weakness(stat: &KeyStat, mean_latency: f64) -> f64 {
+    let slowness = match stat.avg_latency_ms() {
+        Some(latency) if mean_latency > 0.0 => (latency / mean_latency - 1.0).max(0.0),
+        _ => 0.0,
+    };
+    stat.error_rate() * ERROR_WEIGHT + slowness
+}
+
+fn mean_latency<'a, I: Iterator<Item = &'a KeyStat>>(stats: I) -> f64 {
+    let (total, samples) = stats.fold((0u64, 0usize), |(t, n), s| {
+        (t + s.total_latency_ms, n + s.latency_samples)
+    });
+    if samples == 0 {
+        0.0
+    } else {
+        total as f64 / samples as f64
+    }
+}
+
+/// Keep the `TARGET_COUNT` entries with the highest positive weakness.
+fn weakest<K: std::hash::Hash + Eq + Ord, I: Iterator<Item = (K, f64)>>(entries: I) -> HashMap<K, f64> {
+    let mut entries: Vec<(K, f64)> = entries.filter(|(_, w)| *w > 0.0).collect();
+    entries.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    entries.truncate(TARGET_COUNT);
+    entries.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::typing::keystroke::KeystrokeLog;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    /// A log where every `z` is mistyped and every other key is typed
+    /// correctly at an even pace.
+    fn history() -> KeystrokeLog {
+        let mut log = KeystrokeLog::new();
+        let text = "zap zoo zip haze lazy ".repeat(3);
+        for (i, c) in text.chars().enumerate() {
+            let typed = if c == 'z' { 'x' } else { c };
+            log.record_char(Duration::from_millis(i as u64 * 100), typed, Some(c), i, false);
+        }
+        log
+    }
+
+    #[test]
+    fn test_profile_finds_weak_keys() {
+        let profile = WeaknessProfile::from_analysis(&KeystrokeAnalysis::from_log(&history()));
+
+        assert_eq!(profile.weak_keys().first(), Some(&'z'));
+        assert!(profile.word_score("pizza") > profile.word_score("apple"));
+        assert_eq!(profile.word_score("hop"), 0.0);
+    }
+
+    #[test]
+    fn test_no_history_is_random() {
+        let profile = WeaknessProfile::from_analysis(&KeystrokeAnalysis::default());
+        assert!(profile.is_empty());
+
+        let loader = WordLoader::new("data");
+        let generator = AdaptiveGenerator::new(&loader, profile);
+        let words: Vec<String> = ["a", "b", "c"].iter().map(|w| w.to_string()).collect();
+        let selected = generator.select_words(words, 2, &mut StdRng::seed_from_u64(1));
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn test_weak_words_selected_first() {
+        let profile = WeaknessProfile::from_analysis(&KeystrokeAnalysis::from_log(&history()));
+        let loader = WordLoader::new("data");
+        let generator = AdaptiveGenerator::new(&loader, profile);
+
+        let mut words: Vec<String> = (0..50).map(|i| format!("plain{}", i)).collect();
+        words.extend(["zebra", "pizza", "zone", "quiz", "fuzzy"].iter().map(|w| w.to_string()));
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let selected = generator.select_words(words, 5, &mut rng);
+        let with_z = selected.iter().filter(|w| w.contains('z')).count();
+        assert!(with_z >= 4, "selected {:?}", selected);
+    }
+}
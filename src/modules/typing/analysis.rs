@@ -0,0 +1,308 @@
+//! Per-key and per-n-gram analysis of keystroke logs.
+//!
+//! This module computes accuracy and average latency for every character,
+//! bigram and trigram of a test, and accumulates them across tests to show
+//! which keys cost the most speed.
+
+use crate::modules::typing::keystroke::{KeyAction, Keystroke, KeystrokeLog};
+use std::collections::HashMap;
+
+/// Accuracy and latency statistics for a key or n-gram.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KeyStat {
+    /// Number of times the key (or n-gram) had to be typed
+    pub attempts: usize,
+    /// Number of those attempts that were mistyped
+    pub errors: usize,
+    /// Sum of the latencies that were measured, in milliseconds
+    pub total_latency_ms: u64,
+    /// Number of latency measurements
+    pub latency_samples: usize,
+}
+
+impl KeyStat {
+    /// Accuracy percentage (0.0-100.0), 100.0 if never attempted.
+    pub fn accuracy(&self) -> f64 {
+        if self.attempts == 0 {
+            return 100.0;
+        }
+        (self.attempts.saturating_sub(self.errors) as f64 / self.attempts as f64) * 100.0
+    }
+
+    /// Error rate (0.0-1.0).
+    pub fn error_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.attempts as f64
+    }
+
+    /// Average latency in milliseconds, `None` without measurements.
+    pub fn avg_latency_ms(&self) -> Option<f64> {
+        if self.latency_samples == 0 {
+            return None;
+        }
+        Some(self.total_latency_ms as f64 / self.latency_samples as f64)
+    }
+
+    fn record(&mut self, correct: bool, latency_ms: Option<u64>) {
+        self.attempts += 1;
+        if !correct {
+            self.errors += 1;
+        }
+        if let Some(latency) = latency_ms {
+            self.total_latency_ms += latency;
+            self.latency_samples += 1;
+        }
+    }
+
+    /// Add the counts of another statistic to this one.
+    pub fn merge(&mut self, other: &KeyStat) {
+        self.attempts += other.attempts;
+        self.errors += other.errors;
+        self.total_latency_ms += other.total_latency_ms;
+        self.latency_samples += other.latency_samples;
+    }
+}
+
+/// Per-key, per-bigram and per-trigram statistics of one or more tests.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeystrokeAnalysis {
+    /// Statistics per expected character
+    pub keys: HashMap<char, KeyStat>,
+    /// Statistics per expected bigram
+    pub bigrams: HashMap<String, KeyStat>,
+    /// Statistics per expected trigram
+    pub trigrams: HashMap<String, KeyStat>,
+    /// Number of tests included
+    pub tests: usize,
+}
+
+impl KeystrokeAnalysis {
+    /// Analyse the keystrokes of a single test.
+    ///
+    /// Only typed (not auto-inserted) characters are counted. The latency of
+    /// a keystroke is the time since the previous keystroke; n-grams are
+    /// measured over runs of keystrokes at consecutive positions, so a
+    /// backspace breaks the run. An n-gram counts as an error when its last
+    /// character was mistyped.
+    pub fn from_log(log: &KeystrokeLog) -> Self {
+        let mut analysis = Self {
+            tests: 1,
+            ..Self::default()
+        };
+
+        // Consecutive typed keystrokes at consecutive positions
+        let mut run: Vec<&Keystroke> = Vec::new();
+        let mut previous_time: Option<u64> = None;
+
+        for keystroke in &log.keystrokes {
+            let latency = previous_time.map(|t| keystroke.time_ms.saturating_sub(t));
+            previous_time = Some(keystroke.time_ms);
+
+            let expected = match (keystroke.action, keystroke.expected) {
+                (KeyAction::Char(_), Some(expected)) if !keystroke.synthetic => expected,
+                _ => {
+                    run.clear();
+                    continue;
+                }
+            };
+
+            let correct = keystroke.is_correct();
+            analysis.keys.entry(expected).or_default().record(correct, latency);
+
+            if run.last().map(|k| k.position + 1) != Some(keystroke.position) {
+                run.clear();
+            }
+            run.push(keystroke);
+
+            for (n, map) in [(2, &mut analysis.bigrams), (3, &mut analysis.trigrams)] {
+                if run.len() < n {
+                    continue;
+                }
+                let window = &run[run.len() - n..];
+                let ngram: String = window.iter().filter_map(|k| k.expected).collect();
+                let duration = window[n - 1].time_ms.saturating_sub(window[0].time_ms);
+                map.entry(ngram).or_default().record(correct, Some(duration));
+            }
+
+            // A mistyped key ends the run, the next key starts a fresh n-gram
+            if !correct {
+                run.clear();
+            }
+        }
+
+        analysis
+    }
+
+    /// Accumulate the analysis of many tests.
+    pub fn from_logs<'a, I: IntoIterator<Item = &'a KeystrokeLog>>(logs: I) -> Self {
+        let mut total = Self::default();
+        for log in logs {
+            total.merge(&Self::from_log(log));
+        }
+        total
+    }
+
+    /// Add the statistics of another analysis to this one.
+    pub fn merge(&mut self, other: &KeystrokeAnalysis) {
+        for (key, stat) in &other.keys {
+            self.keys.entry(*key).or_default().merge(stat);
+        }
+        for (ngram, stat) in &other.bigrams {
+            self.bigrams.entry(ngram.clone()).or_default().merge(stat);
+        }
+        for (ngram, stat) in &other.trigrams {
+            self.trigrams.entry(ngram.clone()).or_default().merge(stat);
+        }
+        self.tests += other.tests;
+    }
+
+    /// Statistics for a keyboard key, combining upper and lower case.
+    pub fn key_stat(&self, key: char) -> KeyStat {
+        let mut variants: Vec<char> = key.to_lowercase().chain(key.to_uppercase()).collect();
+        variants.sort_unstable();
+        variants.dedup();
+
+        let mut stat = KeyStat::default();
+        for c in variants {
+            if let Some(s) = self.keys.get(&c) {
+                stat.merge(s);
+            }
+        }
+        stat
+    }
+
+    /// The `n` slowest bigrams with at least `min_samples` measurements.
+    pub fn slowest_bigrams(&self, n: usize, min_samples: usize) -> Vec<(String, KeyStat)> {
+        slowest(&self.bigrams, n, min_samples)
+    }
+
+    /// The `n` slowest trigrams with at least `min_samples` measurements.
+    pub fn slowest_trigrams(&self, n: usize, min_samples: usize) -> Vec<(String, KeyStat)> {
+        slowest(&self.trigrams, n, min_samples)
+    }
+
+    /// The `n` keys with the highest error rate, slowest first on ties.
+    pub fn weakest_keys(&self, n: usize) -> Vec<(char, KeyStat)> {
+        let mut keys: Vec<(char, KeyStat)> = self
+            .keys
+            .iter()
+            .filter(|(c, _)| !c.is_whitespace())
+            .map(|(c, s)| (*c, *s))
+            .collect();
+        keys.sort_by(|a, b| {
+            b.1.error_rate()
+                .partial_cmp(&a.1.error_rate())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| {
+                    let la = a.1.avg_latency_ms().unwrap_or(0.0);
+                    let lb = b.1.avg_latency_ms().unwrap_or(0.0);
+                    lb.partial_cmp(&la).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        keys.truncate(n);
+        keys
+    }
+}
+
+fn slowest(map: &HashMap<String, KeyStat>, n: usize, min_samples: usize) -> Vec<(String, KeyStat)> {
+    let mut ngrams: Vec<(String, KeyStat)> = map
+        .iter()
+        .filter(|(ngram, s)| s.latency_samples >= min_samples && !ngram.contains(char::is_whitespace))
+        .map(|(ngram, s)| (ngram.clone(), *s))
+        .collect();
+    ngrams.sort_by(|a, b| {
+        let la = a.1.avg_latency_ms().unwrap_or(0.0);
+        let lb = b.1.avg_latency_ms().unwrap_or(0.0);
+        lb.partial_cmp(&la)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    ngrams.truncate(n);
+    ngrams
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn log_for(target: &str, typed: &[(u64, char)]) -> KeystrokeLog {
+        let mut log = KeystrokeLog::new();
+        log.target_text = target.to_string();
+        let target: Vec<char> = target.chars().collect();
+        for (position, (ms, c)) in typed.iter().enumerate() {
+            log.record_char(
+                Duration::from_millis(*ms),
+                *c,
+                target.get(position).copied(),
+                position,
+                false,
+            );
+        }
+        log
+    }
+
+    #[test]
+    fn test_key_accuracy_and_latency() {
+        // "the": t at 0, h after 100ms, e mistyped after 300ms
+        let log = log_for("the", &[(0, 't'), (100, 'h'), (400, 'x')]);
+        let analysis = KeystrokeAnalysis::from_log(&log);
+
+        let t = analysis.keys[&'t'];
+        assert_eq!(t.attempts, 1);
+        assert_eq!(t.avg_latency_ms(), None);
+
+        let h = analysis.keys[&'h'];
+        assert_eq!(h.accuracy(), 100.0);
+        assert_eq!(h.avg_latency_ms(), Some(100.0));
+
+        let e = analysis.keys[&'e'];
+        assert_eq!(e.errors, 1);
+        assert_eq!(e.accuracy(), 0.0);
+
+        assert_eq!(analysis.bigrams["th"].avg_latency_ms(), Some(100.0));
+        assert_eq!(analysis.bigrams["he"].errors, 1);
+        assert_eq!(analysis.trigrams["the"].avg_latency_ms(), Some(400.0));
+    }
+
+    #[test]
+    fn test_backspace_breaks_ngrams() {
+        let mut log = log_for("ab", &[(0, 'a')]);
+        log.record_backspace(Duration::from_millis(100), Some('b'), 1);
+        log.record_char(Duration::from_millis(200), 'a', Some('a'), 0, false);
+        log.record_char(Duration::from_millis(250), 'b', Some('b'), 1, false);
+
+        let analysis = KeystrokeAnalysis::from_log(&log);
+        assert_eq!(analysis.keys[&'a'].attempts, 2);
+        assert_eq!(analysis.bigrams["ab"].latency_samples, 1);
+        assert_eq!(analysis.bigrams["ab"].avg_latency_ms(), Some(50.0));
+    }
+
+    #[test]
+    fn test_accumulate_and_rank() {
+        let fast = log_for("abab", &[(0, 'a'), (50, 'b'), (100, 'a'), (150, 'b')]);
+        let slow = log_for("ba", &[(0, 'b'), (500, 'a')]);
+        let analysis = KeystrokeAnalysis::from_logs([&fast, &slow]);
+
+        assert_eq!(analysis.tests, 2);
+        assert_eq!(analysis.keys[&'a'].attempts, 3);
+        let slowest = analysis.slowest_bigrams(1, 1);
+        // "ba": 50ms in the fast run, 500ms in the slow one
+        assert_eq!(slowest[0].0, "ba");
+        assert_eq!(slowest[0].1.avg_latency_ms(), Some(275.0));
+    }
+
+    #[test]
+    fn test_key_stat_combines_case() {
+        let log = log_for("Aa", &[(0, 'A'), (100, 'x')]);
+        let analysis = KeystrokeAnalysis::from_log(&log);
+
+        let stat = analysis.key_stat('a');
+        assert_eq!(stat.attempts, 2);
+        assert_eq!(stat.errors, 1);
+        assert_eq!(analysis.weakest_keys(1)[0].0, 'a');
+    }
+}
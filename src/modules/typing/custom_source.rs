@@ -0,0 +1,365 @@
+//! Custom word lists and free-text sources for typing tests.
+//!
+//! Instead of the bundled word files, a typing test can take its text from
+//! a plain-text file, a JSON word list, a directory of such files or stdin.
+//! Prose can be typed sentence by sentence, word lists in random order.
+
+use crate::core::{Difficulty, Result, UtilError};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+/// File extensions picked up when a directory is used as source.
+const TEXT_EXTENSIONS: [&str; 4] = ["txt", "md", "text", "json"];
+
+/// How the target text is generated from a custom source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceOrder {
+    /// Word lists are shuffled, prose keeps its sentences
+    Auto,
+    /// Consecutive sentences, as written
+    Sentences,
+    /// Randomly selected words
+    Random,
+}
+
+impl fmt::Display for SourceOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SourceOrder::Auto => "auto",
+            SourceOrder::Sentences => "sentences",
+            SourceOrder::Random => "random",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SourceOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(SourceOrder::Auto),
+            "sentences" | "sentence" | "prose" => Ok(SourceOrder::Sentences),
+            "random" | "words" | "list" => Ok(SourceOrder::Random),
+            _ => Err(format!("Unknown source order: {}", s)),
+        }
+    }
+}
+
+/// Entry of a JSON word list: either a plain string or an object with a
+/// `word` field (the format of the bundled word files).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WordEntry {
+    Plain(String),
+    Record { word: String },
+}
+
+/// Text loaded from a custom source.
+#[derive(Debug, Clone, Default)]
+pub struct CustomSource {
+    /// Name of the source for messages (file path or `<stdin>`)
+    name: String,
+    /// Sentences from prose sources
+    sentences: Vec<String>,
+    /// All words (from word lists and prose)
+    words: Vec<String>,
+    /// Whether any part of the source was a word list
+    is_word_list: bool,
+}
+
+impl CustomSource {
+    /// Load a custom source from a path.
+    ///
+    /// `-` reads from stdin, a directory loads all `.txt`, `.md`, `.text`
+    /// and `.json` files in it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or a JSON file is malformed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        if path == Path::new("-") {
+            let mut content = String::new();
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| UtilError::WordLoadError {
+                    path: "<stdin>".to_string(),
+                    source: e,
+                })?;
+            let mut source = Self::new("<stdin>");
+            source.add_content(&content, content.trim_start().starts_with('['))?;
+            return Ok(source);
+        }
+
+        let mut source = Self::new(&path.display().to_string());
+        if path.is_dir() {
+            let mut files: Vec<_> = std::fs::read_dir(path)
+                .map_err(|e| UtilError::WordLoadError {
+                    path: path.display().to_string(),
+                    source: e,
+                })?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|p| {
+                    p.extension()
+                        .and_then(|e| e.to_str())
+                        .map(|e| TEXT_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                        .unwrap_or(false)
+                })
+                .collect();
+            files.sort();
+
+            for file in files {
+                source.add_file(&file)?;
+            }
+        } else {
+            source.add_file(path)?;
+        }
+
+        Ok(source)
+    }
+
+    /// Create a source directly from text content.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the source for messages
+    /// * `content` - Prose, or a JSON word list if `is_json` is set
+    /// * `is_json` - Whether the content is a JSON word list
+    pub fn from_text(name: &str, content: &str, is_json: bool) -> Result<Self> {
+        let mut source = Self::new(name);
+        source.add_content(content, is_json)?;
+        Ok(source)
+    }
+
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn add_file(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path).map_err(|e| UtilError::WordLoadError {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        let is_json = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("json"))
+            .unwrap_or(false);
+
+        self.add_content(&content, is_json)
+            .map_err(|e| match e {
+                UtilError::Json(e) => UtilError::WordLoadError {
+                    path: path.display().to_string(),
+                    source: std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+                },
+                other => other,
+            })
+    }
+
+    fn add_content(&mut self, content: &str, is_json: bool) -> Result<()> {
+        if is_json {
+            let entries: Vec<WordEntry> = serde_json::from_str(content)?;
+            self.words.extend(entries.into_iter().map(|entry| match entry {
+                WordEntry::Plain(word) | WordEntry::Record { word } => word.trim().to_string(),
+            }).filter(|w| !w.is_empty()));
+            self.is_word_list = true;
+        } else {
+            let sentences = split_sentences(content);
+            self.words
+                .extend(sentences.iter().flat_map(|s| s.split_whitespace().map(str::to_string)));
+            self.sentences.extend(sentences);
+        }
+        Ok(())
+    }
+
+    /// Name of the source (file path or `<stdin>`).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Resolve [`SourceOrder::Auto`] for this source.
+    pub fn resolve_order(&self, order: SourceOrder) -> SourceOrder {
+        match order {
+            SourceOrder::Auto if self.is_word_list || self.sentences.is_empty() => {
+                SourceOrder::Random
+            }
+            SourceOrder::Auto => SourceOrder::Sentences,
+            other => other,
+        }
+    }
+
+    /// Generate a text for a typing test.
+    ///
+    /// Random order takes `difficulty.word_count()` words no longer than
+    /// `difficulty.max_word_length()`. Sentence order starts at a random
+    /// sentence and keeps consecutive sentences until the word count is
+    /// reached, skipping sentences with words that are too long (unless
+    /// no sentence would be left).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no words match the difficulty.
+    pub fn generate_text(&self, order: SourceOrder, difficulty: Difficulty) -> Result<String> {
+        let max_length = difficulty.max_word_length();
+        let fits = |word: &str| {
+            let len = word
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .chars()
+                .count();
+            len <= max_length
+        };
+
+        let text = match self.resolve_order(order) {
+            SourceOrder::Random | SourceOrder::Auto => {
+                let mut words: Vec<&String> = self.words.iter().filter(|w| fits(w)).collect();
+                words.shuffle(&mut rand::thread_rng());
+                words
+                    .into_iter()
+                    .take(difficulty.word_count())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            SourceOrder::Sentences => {
+                let fitting: Vec<&String> = self
+                    .sentences
+                    .iter()
+                    .filter(|s| s.split_whitespace().all(fits))
+                    .collect();
+                let sentences = if fitting.is_empty() {
+                    self.sentences.iter().collect()
+                } else {
+                    fitting
+                };
+
+                let mut selected = Vec::new();
+                let mut word_count = 0;
+                if !sentences.is_empty() {
+                    let start = rand::thread_rng().gen_range(0..sentences.len());
+                    for sentence in sentences.iter().cycle().skip(start).take(sentences.len()) {
+                        selected.push(sentence.as_str());
+                        word_count += sentence.split_whitespace().count();
+                        if word_count >= difficulty.word_count() {
+                            break;
+                        }
+                    }
+                }
+                selected.join(" ")
+            }
+        };
+
+        if text.is_empty() {
+            return Err(UtilError::NoMatchingWords {
+                language: self.name.clone(),
+                difficulty: difficulty.to_string(),
+            });
+        }
+
+        Ok(text)
+    }
+}
+
+/// Split prose into sentences.
+///
+/// Line breaks and whitespace runs are collapsed into single spaces; a
+/// sentence ends after `.`, `!` or `?` followed by whitespace, or at a
+/// blank line (so headings and list items do not run together).
+pub fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+
+    for paragraph in text.split("\n\n") {
+        let mut current = String::new();
+        for word in paragraph.split_whitespace() {
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+
+            if word.ends_with(['.', '!', '?']) {
+                sentences.push(std::mem::take(&mut current));
+            }
+        }
+        if !current.is_empty() {
+            sentences.push(current);
+        }
+    }
+
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_split_sentences() {
+        let text = "First line\ncontinues here. Second one!\n\nHeading\n\nThird?";
+        assert_eq!(
+            split_sentences(text),
+            vec!["First line continues here.", "Second one!", "Heading", "Third?"]
+        );
+    }
+
+    #[test]
+    fn test_json_word_list() {
+        let json = r#"["alpha", {"word": "beta", "cefr_level": "A1"}, "  "]"#;
+        let source = CustomSource::from_text("test", json, true).unwrap();
+
+        assert_eq!(source.resolve_order(SourceOrder::Auto), SourceOrder::Random);
+        let text = source.generate_text(SourceOrder::Auto, Difficulty::Easy).unwrap();
+        let mut words: Vec<&str> = text.split(' ').collect();
+        words.sort();
+        assert_eq!(words, vec!["alpha", "beta"]);
+    }
+
+    #[test]
+    fn test_random_order_filters_length() {
+        let source = CustomSource::from_text("test", "cat elephant dog.", false).unwrap();
+        let text = source.generate_text(SourceOrder::Random, Difficulty::Easy).unwrap();
+
+        assert!(!text.contains("elephant"));
+        assert!(text.contains("cat"));
+    }
+
+    #[test]
+    fn test_sentence_order_keeps_sentences() {
+        let source = CustomSource::from_text(
+            "test",
+            "The cat sat. A dog ran off. Encyclopaedically verbose.",
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(source.resolve_order(SourceOrder::Auto), SourceOrder::Sentences);
+        let text = source.generate_text(SourceOrder::Auto, Difficulty::Easy).unwrap();
+
+        // Sentences stay intact and the one with a long word is skipped
+        assert!(text.contains("The cat sat."));
+        assert!(text.contains("A dog ran off."));
+        assert!(!text.contains("verbose"));
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello world. Bye now.").unwrap();
+        temp_file.flush().unwrap();
+
+        let source = CustomSource::load(temp_file.path()).unwrap();
+        let text = source.generate_text(SourceOrder::Sentences, Difficulty::Hard).unwrap();
+        assert!(text == "Hello world. Bye now." || text == "Bye now. Hello world.");
+    }
+}
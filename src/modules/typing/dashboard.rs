@@ -0,0 +1,352 @@
+//! Data for the statistics dashboard.
+//!
+//! [`Dashboard`] condenses the result history into what the charts of the
+//! statistics screen show: WPM, its 7-day average and accuracy over time, a
+//! WPM histogram, breakdowns by language and difficulty, personal bests, and
+//! practice streaks by day. Days are local calendar days.
+
+use crate::core::TestMode;
+use crate::modules::typing::highscore::HighScore;
+use chrono::{Duration, Local, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Width of a WPM histogram bucket.
+pub const HISTOGRAM_BUCKET: f64 = 10.0;
+
+/// Days averaged for the rolling WPM line of the progress chart.
+pub const ROLLING_DAYS: i64 = 7;
+
+/// Date range shown on the dashboard, counted back from today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatsPeriod {
+    #[default]
+    All,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl StatsPeriod {
+    /// Number of days covered, `None` for all results.
+    pub fn days(&self) -> Option<i64> {
+        match self {
+            StatsPeriod::All => None,
+            StatsPeriod::Week => Some(7),
+            StatsPeriod::Month => Some(30),
+            StatsPeriod::Quarter => Some(90),
+            StatsPeriod::Year => Some(365),
+        }
+    }
+
+    /// First day of the range ending `today`, `None` for all results.
+    pub fn start(&self, today: NaiveDate) -> Option<NaiveDate> {
+        self.days().map(|days| today - Duration::days(days - 1))
+    }
+
+    /// The next longer range (wrapping around to all results).
+    pub fn next(&self) -> Self {
+        match self {
+            StatsPeriod::All => StatsPeriod::Week,
+            StatsPeriod::Week => StatsPeriod::Month,
+            StatsPeriod::Month => StatsPeriod::Quarter,
+            StatsPeriod::Quarter => StatsPeriod::Year,
+            StatsPeriod::Year => StatsPeriod::All,
+        }
+    }
+}
+
+impl fmt::Display for StatsPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.days() {
+            None => write!(f, "all time"),
+            Some(days) => write!(f, "last {} days", days),
+        }
+    }
+}
+
+/// The next mode filter in the cycle all → words → time → code → adaptive.
+pub fn next_mode_filter(mode: Option<TestMode>) -> Option<TestMode> {
+    match mode {
+        None => Some(TestMode::Words),
+        Some(TestMode::Words) => Some(TestMode::Time),
+        Some(TestMode::Time) => Some(TestMode::Code),
+        Some(TestMode::Code) => Some(TestMode::Adaptive),
+        Some(TestMode::Adaptive) => None,
+    }
+}
+
+/// Summary of the results in one category (a language or difficulty).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryStats {
+    pub label: String,
+    pub tests: usize,
+    pub avg_wpm: f64,
+    pub best_wpm: f64,
+    pub avg_accuracy: f64,
+}
+
+impl CategoryStats {
+    fn from_scores(label: String, scores: &[&HighScore]) -> Self {
+        let tests = scores.len();
+        Self {
+            label,
+            tests,
+            avg_wpm: scores.iter().map(|s| s.wpm).sum::<f64>() / tests as f64,
+            best_wpm: scores.iter().map(|s| s.wpm).fold(0.0, f64::max),
+            avg_accuracy: scores.iter().map(|s| s.accuracy).sum::<f64>() / tests as f64,
+        }
+    }
+}
+
+/// Everything the statistics dashboard shows.
+#[derive(Debug, Clone, Default)]
+pub struct Dashboard {
+    /// Number of results
+    pub tests: usize,
+    /// (test number, WPM) in chronological order
+    pub wpm_points: Vec<(f64, f64)>,
+    /// (test number, accuracy in %) in chronological order
+    pub accuracy_points: Vec<(f64, f64)>,
+    /// (test number, average WPM of the 7 days up to that test)
+    pub rolling_points: Vec<(f64, f64)>,
+    /// Day of the first and the last result
+    pub first_day: Option<NaiveDate>,
+    pub last_day: Option<NaiveDate>,
+    /// (lower bound of the bucket, number of results), without gaps
+    pub wpm_histogram: Vec<(u32, u64)>,
+    pub by_language: Vec<CategoryStats>,
+    pub by_difficulty: Vec<CategoryStats>,
+    /// Fastest result of each ranking category, fastest first
+    pub personal_bests: Vec<HighScore>,
+    /// Number of results per day
+    pub daily_tests: BTreeMap<NaiveDate, u64>,
+    /// Days in a row with practice up to today (or yesterday)
+    pub current_streak: u32,
+    /// Most days in a row with practice
+    pub longest_streak: u32,
+}
+
+impl Dashboard {
+    /// Build the dashboard from results in chronological order.
+    ///
+    /// # Arguments
+    ///
+    /// * `scores` - The results, oldest first
+    /// * `today` - The current local day (for the streak)
+    pub fn from_history(scores: &[HighScore], today: NaiveDate) -> Self {
+        if scores.is_empty() {
+            return Self::default();
+        }
+
+        let wpm_points = scores
+            .iter()
+            .enumerate()
+            .map(|(i, s)| ((i + 1) as f64, s.wpm))
+            .collect();
+        let accuracy_points = scores
+            .iter()
+            .enumerate()
+            .map(|(i, s)| ((i + 1) as f64, s.accuracy))
+            .collect();
+
+        let rolling_points = rolling_points(scores);
+
+        let mut daily_tests = BTreeMap::new();
+        for score in scores {
+            *daily_tests.entry(local_day(score)).or_insert(0) += 1;
+        }
+        let (current_streak, longest_streak) = streaks(&daily_tests, today);
+
+        Self {
+            tests: scores.len(),
+            wpm_points,
+            accuracy_points,
+            rolling_points,
+            first_day: daily_tests.keys().next().copied(),
+            last_day: daily_tests.keys().next_back().copied(),
+            wpm_histogram: histogram(scores),
+            by_language: breakdown(scores, |s| s.language.name().to_string()),
+            by_difficulty: breakdown(scores, |s| s.difficulty.code().to_string()),
+            personal_bests: personal_bests(scores),
+            daily_tests,
+            current_streak,
+            longest_streak,
+        }
+    }
+
+    /// Number of results on each of the `days` days up to `today`, oldest
+    /// first.
+    pub fn daily_counts(&self, today: NaiveDate, days: i64) -> Vec<u64> {
+        (0..days)
+            .rev()
+            .map(|back| {
+                let day = today - Duration::days(back);
+                self.daily_tests.get(&day).copied().unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+/// Average WPM of the [`ROLLING_DAYS`] days up to each result, as a sliding
+/// window over the chronological results.
+fn rolling_points(scores: &[HighScore]) -> Vec<(f64, f64)> {
+    let mut points = Vec::with_capacity(scores.len());
+    let mut start = 0;
+    let mut sum = 0.0;
+    for (i, score) in scores.iter().enumerate() {
+        sum += score.wpm;
+        let window_start = score.timestamp - Duration::days(ROLLING_DAYS);
+        while scores[start].timestamp <= window_start {
+            sum -= scores[start].wpm;
+            start += 1;
+        }
+        points.push(((i + 1) as f64, sum / (i + 1 - start) as f64));
+    }
+    points
+}
+
+/// Local calendar day of a result.
+pub fn local_day(score: &HighScore) -> NaiveDate {
+    score.timestamp.with_timezone(&Local).date_naive()
+}
+
+fn histogram(scores: &[HighScore]) -> Vec<(u32, u64)> {
+    let bucket = |wpm: f64| (wpm.max(0.0) / HISTOGRAM_BUCKET) as u32;
+    let mut counts = BTreeMap::new();
+    for score in scores {
+        *counts.entry(bucket(score.wpm)).or_insert(0) += 1;
+    }
+
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return Vec::new();
+    };
+    (first..=last)
+        .map(|b| {
+            (
+                b * HISTOGRAM_BUCKET as u32,
+                counts.get(&b).copied().unwrap_or(0),
+            )
+        })
+        .collect()
+}
+
+/// Group results by a label, in order of first appearance.
+fn breakdown(scores: &[HighScore], label: impl Fn(&HighScore) -> String) -> Vec<CategoryStats> {
+    let mut groups: Vec<(String, Vec<&HighScore>)> = Vec::new();
+    for score in scores {
+        let key = label(score);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(score),
+            None => groups.push((key, vec![score])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(label, group)| CategoryStats::from_scores(label, &group))
+        .collect()
+}
+
+fn personal_bests(scores: &[HighScore]) -> Vec<HighScore> {
+    let mut bests: Vec<HighScore> = Vec::new();
+    for score in scores {
+        match bests.iter_mut().find(|b| b.same_category(score)) {
+            Some(best) if score.wpm > best.wpm => *best = score.clone(),
+            Some(_) => {}
+            None => bests.push(score.clone()),
+        }
+    }
+    bests.sort_by(|a, b| b.wpm.total_cmp(&a.wpm));
+    bests
+}
+
+/// Current and longest run of consecutive days with results. A streak that
+/// reached yesterday is still current until today is over.
+fn streaks(daily_tests: &BTreeMap<NaiveDate, u64>, today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in daily_tests.keys() {
+        run = match previous {
+            Some(prev) if day - prev == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let current = match previous {
+        Some(last) if last == today || last == today - Duration::days(1) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Difficulty;
+    use chrono::{TimeZone, Utc};
+
+    fn score(day: u32, wpm: f64, difficulty: Difficulty) -> HighScore {
+        let timestamp = Local
+            .with_ymd_and_hms(2024, 3, day, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        HighScore {
+            difficulty,
+            timestamp,
+            ..HighScore::sample("Player", wpm)
+        }
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_dashboard() {
+        let scores = vec![
+            score(1, 42.0, Difficulty::Easy),
+            score(2, 38.0, Difficulty::Medium),
+            score(2, 61.0, Difficulty::Medium),
+            score(3, 55.0, Difficulty::Easy),
+            score(6, 47.0, Difficulty::Medium),
+            score(7, 50.0, Difficulty::Medium),
+        ];
+        let dashboard = Dashboard::from_history(&scores, day(8));
+
+        assert_eq!(dashboard.tests, 6);
+        assert_eq!(dashboard.wpm_points[2], (3.0, 61.0));
+        assert_eq!(dashboard.rolling_points[2], (3.0, 47.0));
+        assert_eq!(dashboard.first_day, Some(day(1)));
+        assert_eq!(dashboard.last_day, Some(day(7)));
+        assert_eq!(
+            dashboard.wpm_histogram,
+            vec![(30, 1), (40, 2), (50, 2), (60, 1)]
+        );
+
+        assert_eq!(dashboard.by_language.len(), 1);
+        let medium = &dashboard.by_difficulty[1];
+        assert_eq!(medium.tests, 4);
+        assert_eq!(medium.best_wpm, 61.0);
+        assert_eq!(medium.avg_wpm, 49.0);
+
+        let bests: Vec<f64> = dashboard.personal_bests.iter().map(|s| s.wpm).collect();
+        assert_eq!(bests, vec![61.0, 55.0]);
+
+        assert_eq!(dashboard.longest_streak, 3);
+        assert_eq!(dashboard.current_streak, 2);
+        assert_eq!(dashboard.daily_counts(day(8), 3), vec![1, 1, 0]);
+        assert_eq!(Dashboard::from_history(&scores, day(10)).current_streak, 0);
+    }
+
+    #[test]
+    fn test_period() {
+        assert_eq!(StatsPeriod::All.start(day(8)), None);
+        assert_eq!(StatsPeriod::Week.start(day(8)), Some(day(2)));
+        assert_eq!(StatsPeriod::Year.next(), StatsPeriod::All);
+        assert_eq!(next_mode_filter(Some(TestMode::Adaptive)), None);
+    }
+}
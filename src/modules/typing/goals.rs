@@ -0,0 +1,243 @@
+//! Feedback on a result relative to the player's own history.
+//!
+//! After a test the results screen shows whether the run is a personal best
+//! in its ranking category (see [`HighScore::same_category`]), its rank
+//! among all earlier results of that category, and the progress toward the
+//! configured goals.
+
+use crate::core::{Difficulty, GoalConfig, Language, Result, TestMode, UtilError};
+use crate::modules::typing::highscore::HighScore;
+use std::fmt;
+use std::str::FromStr;
+
+/// A typing goal, e.g. 70 WPM at 97% accuracy on hard English.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Goal {
+    /// Target net words per minute
+    pub wpm: f64,
+    /// Minimum accuracy in percent
+    pub accuracy: f64,
+    /// Only results in this language, any if `None`
+    pub language: Option<Language>,
+    /// Only results with this difficulty, any if `None`
+    pub difficulty: Option<Difficulty>,
+    /// Only results of this test mode, any if `None`
+    pub mode: Option<TestMode>,
+}
+
+impl Goal {
+    /// Parse a goal from the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the language, difficulty or mode is unknown.
+    pub fn from_config(goal: &GoalConfig) -> Result<Self> {
+        Ok(Self {
+            wpm: goal.wpm,
+            accuracy: goal.accuracy,
+            language: parse_optional(&goal.language)?,
+            difficulty: parse_optional(&goal.difficulty)?,
+            mode: parse_optional(&goal.mode)?,
+        })
+    }
+
+    /// Whether a result counts toward the goal.
+    pub fn applies_to(&self, score: &HighScore) -> bool {
+        matches_filter(self.language, score.language)
+            && matches_filter(self.difficulty, score.difficulty)
+            && matches_filter(self.mode, score.mode)
+    }
+
+    /// Whether a result reaches the goal.
+    pub fn is_met_by(&self, score: &HighScore) -> bool {
+        self.applies_to(score) && score.wpm >= self.wpm && score.accuracy >= self.accuracy
+    }
+}
+
+/// An unset filter matches every value.
+fn matches_filter<T: PartialEq>(filter: Option<T>, value: T) -> bool {
+    filter.is_none() || filter == Some(value)
+}
+
+fn parse_optional<T: FromStr<Err = String>>(value: &Option<String>) -> Result<Option<T>> {
+    value
+        .as_deref()
+        .map(str::parse)
+        .transpose()
+        .map_err(UtilError::ConfigError)
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0} WPM", self.wpm)?;
+        if self.accuracy > 0.0 {
+            write!(f, " at {:.0}%", self.accuracy)?;
+        }
+        match (self.difficulty, self.language) {
+            (Some(difficulty), Some(language)) => {
+                write!(f, " on {} {}", difficulty.code(), language.name())?
+            }
+            (Some(difficulty), None) => write!(f, " on {}", difficulty.code())?,
+            (None, Some(language)) => write!(f, " in {}", language.name())?,
+            (None, None) => {}
+        }
+        if let Some(mode) = self.mode {
+            write!(f, " ({})", mode.code())?;
+        }
+        Ok(())
+    }
+}
+
+/// Progress of one result toward a goal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    /// Share of the target WPM reached (0.0-1.0)
+    pub wpm_progress: f64,
+    /// Whether the accuracy is high enough
+    pub accuracy_met: bool,
+    /// Whether this result reaches the goal
+    pub reached: bool,
+    /// Whether an earlier result already reached the goal
+    pub reached_before: bool,
+}
+
+/// How a result compares to the player's history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunFeedback {
+    /// WPM of the result
+    pub wpm: f64,
+    /// WPM of the best earlier result in the same category
+    pub previous_best: Option<f64>,
+    /// Rank among the results of the category, including this one (1 = fastest)
+    pub rank: usize,
+    /// Number of results in the category, including this one
+    pub total: usize,
+    /// Progress toward every goal that applies to the result
+    pub goals: Vec<GoalProgress>,
+}
+
+impl RunFeedback {
+    /// Compare a result with the earlier results.
+    ///
+    /// # Arguments
+    ///
+    /// * `score` - The new result
+    /// * `history` - Earlier results (of any category, without `score`)
+    /// * `goals` - The player's goals
+    pub fn evaluate(score: &HighScore, history: &[HighScore], goals: &[Goal]) -> Self {
+        let category: Vec<&HighScore> = history.iter().filter(|s| s.same_category(score)).collect();
+        let previous_best = category.iter().map(|s| s.wpm).reduce(f64::max);
+        let faster = category.iter().filter(|s| s.wpm > score.wpm).count();
+
+        let goals = goals
+            .iter()
+            .filter(|goal| goal.applies_to(score))
+            .map(|goal| GoalProgress {
+                goal: goal.clone(),
+                wpm_progress: (score.wpm / goal.wpm).clamp(0.0, 1.0),
+                accuracy_met: score.accuracy >= goal.accuracy,
+                reached: goal.is_met_by(score),
+                reached_before: history.iter().any(|s| goal.is_met_by(s)),
+            })
+            .collect();
+
+        Self {
+            wpm: score.wpm,
+            previous_best,
+            rank: faster + 1,
+            total: category.len() + 1,
+            goals,
+        }
+    }
+
+    /// Whether the result is faster than every earlier result of its
+    /// category (also true for the first result).
+    pub fn is_personal_best(&self) -> bool {
+        match self.previous_best {
+            Some(best) => self.wpm > best,
+            None => true,
+        }
+    }
+
+    /// One line comparing the result with the previous best, e.g.
+    /// "New personal best! +2.5 WPM over 61.0".
+    pub fn summary(&self) -> String {
+        match self.previous_best {
+            None => "First result in this category - your personal best!".to_string(),
+            Some(best) if self.wpm > best => format!(
+                "New personal best! +{:.1} WPM over {:.1}",
+                self.wpm - best,
+                best
+            ),
+            Some(best) if self.wpm == best => format!("Tied your personal best of {:.1} WPM", best),
+            Some(best) => format!("{:.1} WPM below your personal best of {:.1}", best - self.wpm, best),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wpm: f64, accuracy: f64, difficulty: Difficulty) -> HighScore {
+        HighScore {
+            accuracy,
+            difficulty,
+            ..HighScore::sample("Player", wpm)
+        }
+    }
+
+    fn goal() -> Goal {
+        Goal::from_config(&GoalConfig {
+            wpm: 70.0,
+            accuracy: 97.0,
+            language: Some("en".to_string()),
+            difficulty: Some("hard".to_string()),
+            mode: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rank_and_personal_best() {
+        let history = vec![
+            score(55.0, 96.0, Difficulty::Hard),
+            score(61.0, 95.0, Difficulty::Hard),
+            score(90.0, 99.0, Difficulty::Easy),
+        ];
+
+        let feedback = RunFeedback::evaluate(&score(58.0, 97.0, Difficulty::Hard), &history, &[]);
+        assert_eq!((feedback.rank, feedback.total), (2, 3));
+        assert!(!feedback.is_personal_best());
+        assert_eq!(feedback.summary(), "3.0 WPM below your personal best of 61.0");
+
+        let feedback = RunFeedback::evaluate(&score(63.5, 97.0, Difficulty::Hard), &history, &[]);
+        assert_eq!(feedback.rank, 1);
+        assert!(feedback.is_personal_best());
+        assert_eq!(feedback.summary(), "New personal best! +2.5 WPM over 61.0");
+
+        let first = RunFeedback::evaluate(&score(40.0, 97.0, Difficulty::Medium), &history, &[]);
+        assert!(first.is_personal_best());
+        assert_eq!((first.rank, first.total), (1, 1));
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        let goal = goal();
+        assert_eq!(goal.to_string(), "70 WPM at 97% on hard English");
+
+        let goals = vec![goal];
+        let history = vec![score(65.0, 98.0, Difficulty::Hard)];
+        let feedback = RunFeedback::evaluate(&score(56.0, 98.0, Difficulty::Hard), &history, &goals);
+        let progress = &feedback.goals[0];
+        assert_eq!(progress.wpm_progress, 0.8);
+        assert!(progress.accuracy_met && !progress.reached && !progress.reached_before);
+
+        let feedback = RunFeedback::evaluate(&score(72.0, 97.5, Difficulty::Hard), &history, &goals);
+        assert!(feedback.goals[0].reached);
+
+        let feedback = RunFeedback::evaluate(&score(72.0, 97.5, Difficulty::Easy), &history, &goals);
+        assert!(feedback.goals.is_empty());
+    }
+}
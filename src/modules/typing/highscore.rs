@@ -1,29 +1,63 @@
 //! Highscore management and persistence.
 //!
 //! This module handles loading, saving, and managing typing test highscores.
+//! Every real run is appended to the result history; the highscore table is
+//! the top of each category of the results in that history that pass
+//! validation. Both are kept in the configured [`Storage`] backend.
 
-use crate::core::{Difficulty, Language, Result, UtilError};
+use crate::core::{Config, Difficulty, Language, Result, TestMode, UtilError};
+use crate::modules::typing::goals::{Goal, RunFeedback};
+use crate::modules::typing::keystroke::{new_result_id, KeystrokeLog};
 use crate::modules::typing::scorer::TestResult;
+use crate::modules::typing::validation::{ScoreValidator, ValidationIssue};
+use crate::storage::{self, JsonStorage, ResultFilter, Storage};
+use chrono::{DateTime, Local, SubsecRound, Utc};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::BufReader;
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Number of most recent tests averaged for [`HighScoreStatistics::recent_avg_wpm`].
+pub const RECENT_TESTS: usize = 10;
 
 /// A highscore entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HighScore {
+    /// Result ID, also used to find the keystroke log (missing in old entries)
+    #[serde(default)]
+    pub id: Option<String>,
     /// Player name
     pub name: String,
-    /// Words per minute
+    /// Net words per minute
     pub wpm: f64,
+    /// Raw (gross) words per minute
+    #[serde(default)]
+    pub raw_wpm: f64,
     /// Accuracy percentage
     pub accuracy: f64,
     /// Language used
-    pub language: String,
+    pub language: Language,
     /// Difficulty level
-    pub difficulty: String,
-    /// Timestamp when the score was achieved
-    pub timestamp: String,
+    pub difficulty: Difficulty,
+    /// When the score was achieved (UTC, whole seconds)
+    pub timestamp: DateTime<Utc>,
+    /// Test duration in seconds (missing in old entries)
+    #[serde(default)]
+    pub duration_secs: Option<f64>,
+    /// Test mode (words or time)
+    #[serde(default)]
+    pub mode: TestMode,
+    /// Time limit in seconds (only for timed tests)
+    #[serde(default)]
+    pub time_limit: Option<u64>,
+    /// Raw WPM of every second of the test
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wpm_series: Vec<f64>,
+    /// Coefficient of variation of the WPM series in percent
+    #[serde(default)]
+    pub consistency: f64,
+    /// Highest raw WPM within a single second
+    #[serde(default)]
+    pub burst_wpm: f64,
 }
 
 impl HighScore {
@@ -42,24 +76,81 @@ impl HighScore {
         difficulty: Difficulty,
     ) -> Self {
         Self {
+            id: Some(new_result_id()),
             name,
             wpm: result.wpm,
+            raw_wpm: result.raw_wpm,
             accuracy: result.accuracy,
-            language: language.code().to_string(),
-            difficulty: difficulty.to_string(),
-            timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            language,
+            difficulty,
+            timestamp: Utc::now().trunc_subsecs(0),
+            duration_secs: Some(result.duration.as_secs_f64()),
+            mode: result.mode,
+            time_limit: result.time_limit,
+            wpm_series: result.wpm_series.clone(),
+            consistency: result.consistency,
+            burst_wpm: result.burst_wpm,
+        }
+    }
+
+    /// Check whether two scores belong to the same ranking category.
+    ///
+    /// Scores are only comparable when language, difficulty, test mode and
+    /// time limit all match.
+    pub fn same_category(&self, other: &HighScore) -> bool {
+        self.language == other.language
+            && self.difficulty == other.difficulty
+            && self.mode == other.mode
+            && self.time_limit == other.time_limit
+    }
+
+    /// The timestamp in local time, e.g. "2024-01-01 12:00".
+    pub fn local_time(&self) -> String {
+        self.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    /// Human-readable label for the test mode, e.g. "words" or "time 30s".
+    pub fn mode_label(&self) -> String {
+        match (self.mode, self.time_limit) {
+            (TestMode::Time, Some(secs)) => format!("{} {}s", self.mode.code(), secs),
+            (mode, _) => mode.code().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl HighScore {
+    /// A 30 second English word-count result on medium difficulty at 95%
+    /// accuracy; tests change other fields with struct update syntax.
+    pub(crate) fn sample(name: &str, wpm: f64) -> Self {
+        Self {
+            id: None,
+            name: name.to_string(),
+            wpm,
+            raw_wpm: wpm,
+            accuracy: 95.0,
+            language: Language::English,
+            difficulty: Difficulty::Medium,
+            timestamp: "2024-01-01T12:00:00Z".parse().unwrap(),
+            duration_secs: Some(30.0),
+            mode: TestMode::Words,
+            time_limit: None,
+            wpm_series: Vec::new(),
+            consistency: 0.0,
+            burst_wpm: 0.0,
         }
     }
 }
 
 /// Highscore manager for loading and saving scores.
 pub struct HighScoreManager {
-    file_path: PathBuf,
     max_scores: usize,
+    validator: ScoreValidator,
+    storage: Box<dyn Storage>,
 }
 
 impl HighScoreManager {
-    /// Create a new highscore manager.
+    /// Create a new highscore manager that stores scores in JSON files.
     ///
     /// # Arguments
     ///
@@ -67,38 +158,69 @@ impl HighScoreManager {
     /// * `max_scores` - Maximum number of scores to keep
     pub fn new<P: AsRef<Path>>(file_path: P, max_scores: usize) -> Self {
         Self {
-            file_path: file_path.as_ref().to_path_buf(),
             max_scores,
+            validator: ScoreValidator::default(),
+            storage: Box::new(JsonStorage::new(file_path)),
         }
     }
 
-    /// Load all highscores from file.
+    /// Create a highscore manager with the storage backend, limits and
+    /// validation settings of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage backend cannot be opened.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::new(&config.paths.highscore_file, config.defaults.max_highscores)
+            .with_storage(storage::open(config)?)
+            .with_validator(ScoreValidator::new(config.defaults.min_accuracy_for_highscore)))
+    }
+
+    /// Keep scores and keystroke logs in the given storage backend.
+    pub fn with_storage(mut self, storage: Box<dyn Storage>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// The storage backend scores and results are kept in.
+    pub fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+
+    /// Check scores with the given validator before they enter the
+    /// highscore table.
+    ///
+    /// The default validator does not require a minimum accuracy;
+    /// implausible speeds and durations are always rejected.
+    pub fn with_validator(mut self, validator: ScoreValidator) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    /// Check a score, including the timing of its keystroke log if one is
+    /// stored.
+    pub fn validate(&self, score: &HighScore) -> Vec<ValidationIssue> {
+        let log = score
+            .id
+            .as_deref()
+            .and_then(|id| self.storage.load_keystroke_log(id).ok().flatten());
+        self.validator.check(score, log.as_ref())
+    }
+
+    /// Load the highscore table.
     ///
     /// # Returns
     ///
-    /// A vector of highscores, or an empty vector if the file doesn't exist.
+    /// A vector of highscores, or an empty vector if none were saved yet.
     ///
     /// # Errors
     ///
-    /// Returns an error if the file exists but cannot be read or parsed.
+    /// Returns an error if the stored scores cannot be read or parsed.
     pub fn load(&self) -> Result<Vec<HighScore>> {
-        if !self.file_path.exists() {
-            return Ok(Vec::new());
-        }
-
-        let file = File::open(&self.file_path).map_err(|e| UtilError::HighscoreError(
-            format!("Failed to open highscore file: {}", e),
-        ))?;
-
-        let reader = BufReader::new(file);
-        let scores = serde_json::from_reader(reader).map_err(|e| {
-            UtilError::HighscoreError(format!("Failed to parse highscore file: {}", e))
-        })?;
-
-        Ok(scores)
+        self.storage.load_highscores()
     }
 
-    /// Save highscores to file.
+    /// Replace the highscore table.
     ///
     /// # Arguments
     ///
@@ -106,28 +228,69 @@ impl HighScoreManager {
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be written.
+    /// Returns an error if the scores cannot be written.
     pub fn save(&self, scores: &[HighScore]) -> Result<()> {
-        // Ensure parent directory exists
-        if let Some(parent) = self.file_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        self.storage.save_highscores(scores)
+    }
+
+    /// Load every recorded result, oldest first.
+    ///
+    /// Before the history existed only the highscore table was kept; in that
+    /// case its real runs are returned in chronological order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history or highscore table cannot be read.
+    pub fn load_history(&self) -> Result<Vec<HighScore>> {
+        self.get_history(&ResultFilter::default())
+    }
+
+    /// Load the recorded results matching a filter, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history or highscore table cannot be read.
+    pub fn get_history(&self, filter: &ResultFilter) -> Result<Vec<HighScore>> {
+        if self.storage.has_history()? {
+            return self.storage.load_results(filter);
         }
 
-        let file = File::create(&self.file_path).map_err(|e| {
-            UtilError::HighscoreError(format!("Failed to create highscore file: {}", e))
-        })?;
+        let mut scores = self.legacy_results()?;
+        scores.retain(|s| filter.matches(s) && self.implausible_issues(s).is_empty());
+        Ok(scores)
+    }
+
+    /// The highscore table in chronological order, the only record of the
+    /// results from before the history existed.
+    fn legacy_results(&self) -> Result<Vec<HighScore>> {
+        let mut scores = self.load()?;
+        scores.sort_by_key(|s| s.timestamp);
+        Ok(scores)
+    }
 
-        serde_json::to_writer_pretty(file, scores).map_err(|e| {
-            UtilError::HighscoreError(format!("Failed to write highscore file: {}", e))
-        })?;
+    /// Start the history with the real runs of the current highscores if it
+    /// does not exist yet, so results from before the history are not lost.
+    ///
+    /// Returns the implausible highscores that were left out.
+    fn ensure_history(&self) -> Result<Vec<(HighScore, Vec<ValidationIssue>)>> {
+        if self.storage.has_history()? {
+            return Ok(Vec::new());
+        }
 
-        Ok(())
+        let (kept, left_out) = self.split_implausible(self.legacy_results()?);
+        self.storage.replace_results(&kept)?;
+        Ok(left_out)
     }
 
-    /// Add a new highscore and save.
+    /// Add a new result to the history and the highscore table.
     ///
-    /// This function loads existing scores, adds the new one, sorts by WPM,
-    /// truncates to max_scores, and saves back to file.
+    /// The storage is locked for the whole update, so concurrent processes
+    /// cannot overwrite each other's results. The result is appended to the
+    /// history unless it is not a real run (see
+    /// [`ValidationIssue::is_implausible`]); if it passes validation it also
+    /// enters the table, which keeps at most max_scores per ranking category
+    /// (see [`HighScore::same_category`]), sorted by WPM. Entries of the
+    /// table that fail validation, e.g. from older data, are dropped.
     ///
     /// # Arguments
     ///
@@ -135,22 +298,184 @@ impl HighScoreManager {
     ///
     /// # Errors
     ///
-    /// Returns an error if loading or saving fails.
+    /// Returns [`UtilError::ScoreRejected`] if the score fails validation
+    /// (see [`HighScoreManager::validate`]), or an error if the existing
+    /// scores cannot be loaded or saving fails.
     pub fn add_score(&self, score: HighScore) -> Result<()> {
-        let mut scores = self.load().unwrap_or_default();
+        let issues = self.validate(&score);
+        self.record(score, issues)
+    }
+
+    /// Add a new result together with the keystroke log of its test.
+    ///
+    /// The score is checked against the timing of the log (see
+    /// [`HighScoreManager::add_score`]). The log is saved after the result
+    /// has been recorded, so pasted or automated input, which is not
+    /// recorded at all, never reaches the replay and the key analysis.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UtilError::ScoreRejected`] if the score fails validation,
+    /// or an error if recording the result or saving the log fails.
+    pub fn add_score_with_log(&self, score: HighScore, log: &KeystrokeLog) -> Result<()> {
+        let issues = self.validator.check(&score, Some(log));
+        let implausible = issues.iter().any(ValidationIssue::is_implausible);
+
+        let outcome = self.record(score, issues);
+        // Only results that were appended to the history keep their log
+        let recorded = matches!(outcome, Ok(()) | Err(UtilError::ScoreRejected(_))) && !implausible;
+        if recorded {
+            self.storage.save_keystroke_log(log).map_err(|e| {
+                UtilError::HighscoreError(format!("Keystroke log not saved: {}", e))
+            })?;
+        }
+        outcome
+    }
+
+    /// Append a checked result to the history if it is a real run and,
+    /// without issues, to the highscore table.
+    fn record(&self, score: HighScore, issues: Vec<ValidationIssue>) -> Result<()> {
+        let _lock = self.storage.lock()?;
+
+        // A table that cannot be read is not replaced by one with this score only
+        let mut scores = self.load()?;
+
+        self.ensure_history()?;
+        if !issues.iter().any(ValidationIssue::is_implausible) {
+            self.storage.append_result(&score)?;
+        }
+
+        if !issues.is_empty() {
+            let reasons: Vec<String> = issues.iter().map(ToString::to_string).collect();
+            return Err(UtilError::ScoreRejected(reasons.join(", ")));
+        }
+
         scores.push(score);
+        self.save(&self.top_per_category(scores))
+    }
+
+    /// Rebuild the highscore table from the history.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading or saving fails.
+    pub fn rebuild(&self) -> Result<()> {
+        let _lock = self.storage.lock()?;
+        self.rebuild_table()
+    }
+
+    /// Replace the whole history and rebuild the highscore table from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `scores` - The new history, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing the history or the table fails.
+    pub fn replace_history(&self, scores: &[HighScore]) -> Result<()> {
+        let _lock = self.storage.lock()?;
+        self.storage.replace_results(scores)?;
+        self.rebuild_table()
+    }
+
+    fn rebuild_table(&self) -> Result<()> {
+        self.save(&self.top_per_category(self.load_history()?))
+    }
+
+    /// Keep the scores that pass validation and the top of each category
+    /// among them.
+    fn top_per_category(&self, scores: Vec<HighScore>) -> Vec<HighScore> {
+        let valid = scores
+            .into_iter()
+            .filter(|score| self.validate(score).is_empty())
+            .collect();
+        self.rank_per_category(valid)
+    }
 
-        // Sort by WPM (descending)
+    /// Sort scores by WPM (descending) and keep the top of each category.
+    fn rank_per_category(&self, mut scores: Vec<HighScore>) -> Vec<HighScore> {
         scores.sort_by(|a, b| {
             b.wpm
                 .partial_cmp(&a.wpm)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
 
-        // Keep only top scores
-        scores.truncate(self.max_scores);
+        let mut kept: Vec<HighScore> = Vec::with_capacity(scores.len());
+        for score in scores {
+            let rank = kept.iter().filter(|s| s.same_category(&score)).count();
+            if rank < self.max_scores {
+                kept.push(score);
+            }
+        }
+        kept
+    }
+
+    /// Check a recorded result for signs that it is not a real run.
+    ///
+    /// Low accuracy is not such a sign: those runs stay in the history and
+    /// are only left out of the highscore table.
+    fn implausible_issues(&self, score: &HighScore) -> Vec<ValidationIssue> {
+        self.validate(score)
+            .into_iter()
+            .filter(ValidationIssue::is_implausible)
+            .collect()
+    }
+
+    /// Separate the real runs from the implausible results with their
+    /// issues.
+    fn split_implausible(
+        &self,
+        scores: Vec<HighScore>,
+    ) -> (Vec<HighScore>, Vec<(HighScore, Vec<ValidationIssue>)>) {
+        let mut kept = Vec::new();
+        let mut implausible = Vec::new();
+        for score in scores {
+            let issues = self.implausible_issues(&score);
+            if issues.is_empty() {
+                kept.push(score);
+            } else {
+                implausible.push((score, issues));
+            }
+        }
+        (kept, implausible)
+    }
+
+    /// Find recorded results that are not real runs (see
+    /// [`ValidationIssue::is_implausible`]).
+    ///
+    /// # Returns
+    ///
+    /// Every implausible result of the history (or, before the history
+    /// exists, of the highscore table) with its issues, oldest first.
+    pub fn find_implausible(&self) -> Result<Vec<(HighScore, Vec<ValidationIssue>)>> {
+        let scores = if self.storage.has_history()? {
+            self.load_history()?
+        } else {
+            self.legacy_results()?
+        };
+        Ok(self.split_implausible(scores).1)
+    }
+
+    /// Remove all implausible results from the history and rebuild the
+    /// highscore table from the rest.
+    ///
+    /// # Returns
+    ///
+    /// The removed results with their issues.
+    pub fn prune(&self) -> Result<Vec<(HighScore, Vec<ValidationIssue>)>> {
+        let _lock = self.storage.lock()?;
+        let mut removed = self.ensure_history()?;
+
+        let (kept, implausible) = self.split_implausible(self.load_history()?);
+        if !implausible.is_empty() {
+            self.storage.replace_results(&kept)?;
+        }
+        removed.extend(implausible);
 
-        self.save(&scores)
+        // Older tables can still hold results that never passed validation
+        self.rebuild_table()?;
+        Ok(removed)
     }
 
     /// Get top N highscores.
@@ -167,46 +492,132 @@ impl HighScoreManager {
         Ok(scores.into_iter().take(n).collect())
     }
 
-    /// Get highscores filtered by language and/or difficulty.
+    /// Get the ranking for a single category, sorted by WPM (descending).
+    ///
+    /// Only scores with the given language, difficulty, mode and time limit
+    /// are included, so timed and word-count runs are never mixed.
+    pub fn get_ranking(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        mode: TestMode,
+        time_limit: Option<u64>,
+    ) -> Result<Vec<HighScore>> {
+        let filter = ResultFilter::new(Some(language), Some(difficulty)).with_mode(Some(mode));
+        let scores = self.get_filtered(&filter)?;
+        Ok(scores
+            .into_iter()
+            .filter(|s| s.time_limit == time_limit)
+            .collect())
+    }
+
+    /// Get highscores filtered by language, difficulty, mode and/or date.
     ///
     /// # Arguments
     ///
-    /// * `language` - Optional language filter
-    /// * `difficulty` - Optional difficulty filter
+    /// * `filter` - Criteria the highscores must match
     ///
     /// # Returns
     ///
     /// Filtered highscores.
-    pub fn get_filtered(
-        &self,
-        language: Option<Language>,
-        difficulty: Option<Difficulty>,
-    ) -> Result<Vec<HighScore>> {
-        let scores = self.load()?;
+    pub fn get_filtered(&self, filter: &ResultFilter) -> Result<Vec<HighScore>> {
+        let mut scores = self.load()?;
+        scores.retain(|s| filter.matches(s));
+        Ok(scores)
+    }
 
-        let filtered = scores
-            .into_iter()
-            .filter(|s| {
-                let lang_match = language
-                    .map(|l| s.language == l.code())
-                    .unwrap_or(true);
-                let diff_match = difficulty
-                    .map(|d| s.difficulty.contains(&d.to_string()))
-                    .unwrap_or(true);
-
-                lang_match && diff_match
-            })
-            .collect();
+    /// Calculate statistics from the results in the history matching a
+    /// filter (`ResultFilter::default()` for all results).
+    pub fn get_statistics(&self, filter: &ResultFilter) -> Result<HighScoreStatistics> {
+        Ok(HighScoreStatistics::from_history(&self.get_history(filter)?))
+    }
 
-        Ok(filtered)
+    /// Compare a new result with the earlier results of its language and
+    /// difficulty (see [`RunFeedback::evaluate`]).
+    ///
+    /// Implausible results that are still in the history, e.g. from before
+    /// they were kept out of it, are neither a personal best to beat nor a
+    /// reached goal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history cannot be read.
+    pub fn run_feedback(&self, score: &HighScore, goals: &[Goal]) -> Result<RunFeedback> {
+        let filter = ResultFilter::new(Some(score.language), Some(score.difficulty));
+        let mut history = self.get_history(&filter)?;
+        history.retain(|s| self.implausible_issues(s).is_empty());
+        Ok(RunFeedback::evaluate(score, &history, goals))
     }
+}
 
-    /// Calculate statistics from all highscores.
-    pub fn get_statistics(&self) -> Result<HighScoreStatistics> {
-        let scores = self.load()?;
+/// Statistics calculated from the result history.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HighScoreStatistics {
+    pub total_tests: usize,
+    pub avg_wpm: f64,
+    pub avg_accuracy: f64,
+    pub best_wpm: f64,
+    pub easy_count: usize,
+    pub medium_count: usize,
+    pub hard_count: usize,
+    /// Total time spent typing in seconds (where known)
+    pub total_time_secs: f64,
+    /// Average WPM of the last [`RECENT_TESTS`] tests
+    pub recent_avg_wpm: f64,
+    /// Change in WPM per test (slope of a linear fit over the history)
+    pub wpm_trend: f64,
+    /// 10th percentile of the WPM
+    pub p10_wpm: f64,
+    /// Median (50th percentile) of the WPM
+    pub median_wpm: f64,
+    /// 90th percentile of the WPM
+    pub p90_wpm: f64,
+    /// Standard deviation of the WPM
+    pub wpm_std_dev: f64,
+    /// Average WPM of the results in the 7 days up to the most recent one
+    pub rolling_7d_wpm: f64,
+    /// Average WPM of the results in the 30 days up to the most recent one
+    pub rolling_30d_wpm: f64,
+    /// Change in WPM per week (slope of a linear fit over time)
+    pub wpm_per_week: f64,
+    /// Average of WPM × accuracy, so fast but sloppy runs count less
+    pub weighted_wpm: f64,
+    /// Statistics of the results in each language
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_language: BTreeMap<Language, HighScoreStatistics>,
+    /// Statistics of the results with each difficulty
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_difficulty: BTreeMap<Difficulty, HighScoreStatistics>,
+}
+
+impl HighScoreStatistics {
+    /// Calculate statistics from results in chronological order, including
+    /// the statistics of each language and difficulty.
+    pub fn from_history(scores: &[HighScore]) -> Self {
+        let mut by_language: BTreeMap<Language, Vec<HighScore>> = BTreeMap::new();
+        let mut by_difficulty: BTreeMap<Difficulty, Vec<HighScore>> = BTreeMap::new();
+        for score in scores {
+            by_language.entry(score.language).or_default().push(score.clone());
+            by_difficulty.entry(score.difficulty).or_default().push(score.clone());
+        }
+
+        Self {
+            by_language: by_language
+                .into_iter()
+                .map(|(language, scores)| (language, Self::summarize(&scores)))
+                .collect(),
+            by_difficulty: by_difficulty
+                .into_iter()
+                .map(|(difficulty, scores)| (difficulty, Self::summarize(&scores)))
+                .collect(),
+            ..Self::summarize(scores)
+        }
+    }
 
+    /// Statistics of results in chronological order, without breakdowns.
+    fn summarize(scores: &[HighScore]) -> Self {
         if scores.is_empty() {
-            return Ok(HighScoreStatistics::default());
+            return Self::default();
         }
 
         let total_tests = scores.len();
@@ -219,18 +630,34 @@ impl HighScoreManager {
 
         let easy_count = scores
             .iter()
-            .filter(|s| s.difficulty.contains("Easy"))
+            .filter(|s| s.difficulty == Difficulty::Easy)
             .count();
         let medium_count = scores
             .iter()
-            .filter(|s| s.difficulty.contains("Medium"))
+            .filter(|s| s.difficulty == Difficulty::Medium)
             .count();
         let hard_count = scores
             .iter()
-            .filter(|s| s.difficulty.contains("Hard"))
+            .filter(|s| s.difficulty == Difficulty::Hard)
             .count();
 
-        Ok(HighScoreStatistics {
+        let recent = &scores[total_tests.saturating_sub(RECENT_TESTS)..];
+        let recent_avg_wpm = recent.iter().map(|s| s.wpm).sum::<f64>() / recent.len() as f64;
+
+        let wpms: Vec<f64> = scores.iter().map(|s| s.wpm).collect();
+        let mut sorted = wpms.clone();
+        sorted.sort_by(f64::total_cmp);
+        let variance = wpms.iter().map(|wpm| (wpm - avg_wpm).powi(2)).sum::<f64>() / total_tests as f64;
+
+        // Days since the first result, for the trend over time
+        let first = scores[0].timestamp;
+        let days: Vec<(f64, f64)> = scores
+            .iter()
+            .map(|s| ((s.timestamp - first).num_seconds() as f64 / 86_400.0, s.wpm))
+            .collect();
+        let indexed: Vec<(f64, f64)> = wpms.iter().enumerate().map(|(i, &wpm)| (i as f64, wpm)).collect();
+
+        Self {
             total_tests,
             avg_wpm,
             avg_accuracy,
@@ -238,45 +665,100 @@ impl HighScoreManager {
             easy_count,
             medium_count,
             hard_count,
-        })
+            total_time_secs: scores.iter().filter_map(|s| s.duration_secs).sum(),
+            recent_avg_wpm,
+            wpm_trend: linear_slope(&indexed),
+            p10_wpm: percentile(&sorted, 10.0),
+            median_wpm: percentile(&sorted, 50.0),
+            p90_wpm: percentile(&sorted, 90.0),
+            wpm_std_dev: variance.sqrt(),
+            rolling_7d_wpm: rolling_average(scores, 7),
+            rolling_30d_wpm: rolling_average(scores, 30),
+            wpm_per_week: linear_slope(&days) * 7.0,
+            weighted_wpm: scores.iter().map(|s| s.wpm * s.accuracy / 100.0).sum::<f64>()
+                / total_tests as f64,
+            by_language: BTreeMap::new(),
+            by_difficulty: BTreeMap::new(),
+        }
     }
 }
 
-/// Statistics calculated from highscores.
-#[derive(Debug, Default)]
-pub struct HighScoreStatistics {
-    pub total_tests: usize,
-    pub avg_wpm: f64,
-    pub avg_accuracy: f64,
-    pub best_wpm: f64,
-    pub easy_count: usize,
-    pub medium_count: usize,
-    pub hard_count: usize,
+/// Percentile of sorted values, interpolating linearly between the two
+/// closest ranks.
+///
+/// # Arguments
+///
+/// * `sorted` - The values in ascending order
+/// * `percent` - The percentile (0.0-100.0)
+pub fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let Some(&last) = sorted.last() else {
+        return 0.0;
+    };
+
+    let rank = percent.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    match sorted.get(lower + 1) {
+        Some(&upper) => sorted[lower] + (upper - sorted[lower]) * (rank - lower as f64),
+        None => last,
+    }
+}
+
+/// Average WPM of the results in the `days` days up to the most recent
+/// result (results in chronological order).
+pub fn rolling_average(scores: &[HighScore], days: i64) -> f64 {
+    let Some(latest) = scores.last().map(|s| s.timestamp) else {
+        return 0.0;
+    };
+
+    let start = latest - chrono::Duration::days(days);
+    let window: Vec<f64> = scores
+        .iter()
+        .filter(|s| s.timestamp > start)
+        .map(|s| s.wpm)
+        .collect();
+    window.iter().sum::<f64>() / window.len() as f64
+}
+
+/// Slope of the least-squares line through `(x, y)` points, 0 if the
+/// points do not spread along x.
+fn linear_slope(points: &[(f64, f64)]) -> f64 {
+    if points.len() < 2 {
+        return 0.0;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+
+    let (covariance, variance) = points
+        .iter()
+        .fold((0.0, 0.0), |(cov, var), &(x, y)| {
+            let dx = x - mean_x;
+            (cov + dx * (y - mean_y), var + dx * dx)
+        });
+    if variance == 0.0 {
+        return 0.0;
+    }
+    covariance / variance
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::NamedTempFile;
+    use tempfile::TempDir;
 
     #[test]
     fn test_highscore_manager() {
-        let temp_file = NamedTempFile::new().unwrap();
-        let manager = HighScoreManager::new(temp_file.path(), 10);
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        let manager = HighScoreManager::new(&path, 10);
 
         // Initially empty
         let scores = manager.load().unwrap();
         assert!(scores.is_empty());
 
         // Add a score
-        let score = HighScore {
-            name: "Test".to_string(),
-            wpm: 50.0,
-            accuracy: 95.0,
-            language: "en".to_string(),
-            difficulty: "Medium".to_string(),
-            timestamp: "2024-01-01 12:00:00".to_string(),
-        };
+        let score = HighScore::sample("Test", 50.0);
 
         manager.add_score(score).unwrap();
 
@@ -288,19 +770,13 @@ mod tests {
 
     #[test]
     fn test_max_scores_truncation() {
-        let temp_file = NamedTempFile::new().unwrap();
-        let manager = HighScoreManager::new(temp_file.path(), 3);
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        let manager = HighScoreManager::new(&path, 3);
 
         // Add 5 scores
         for i in 0..5 {
-            let score = HighScore {
-                name: format!("Player{}", i),
-                wpm: (i * 10) as f64,
-                accuracy: 95.0,
-                language: "en".to_string(),
-                difficulty: "Medium".to_string(),
-                timestamp: "2024-01-01 12:00:00".to_string(),
-            };
+            let score = HighScore::sample(&format!("Player{}", i), (i * 10) as f64);
             manager.add_score(score).unwrap();
         }
 
@@ -309,4 +785,328 @@ mod tests {
         assert_eq!(scores.len(), 3);
         assert_eq!(scores[0].wpm, 40.0); // Highest WPM first
     }
+
+    #[test]
+    fn test_history_keeps_every_result() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        let manager = HighScoreManager::new(&path, 2);
+
+        for wpm in [40.0, 30.0, 50.0, 60.0, 70.0] {
+            let mut score = HighScore::from_result(
+                "Player".to_string(),
+                &TestResult::calculate("a", "a", std::time::Duration::from_secs(30), 0),
+                Language::English,
+                Difficulty::Medium,
+            );
+            score.wpm = wpm;
+            manager.add_score(score).unwrap();
+        }
+
+        assert_eq!(manager.load().unwrap().len(), 2);
+        let history = manager.load_history().unwrap();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[1].wpm, 30.0);
+
+        // The table is derived from the history
+        std::fs::remove_file(&path).unwrap();
+        manager.rebuild().unwrap();
+        let scores = manager.load().unwrap();
+        assert_eq!(scores[0].wpm, 70.0);
+        assert_eq!(scores[1].wpm, 60.0);
+
+        let stats = manager.get_statistics(&ResultFilter::default()).unwrap();
+        assert_eq!(stats.total_tests, 5);
+        assert_eq!(stats.avg_wpm, 50.0);
+        assert_eq!(stats.total_time_secs, 150.0);
+        assert!((stats.wpm_trend - 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_distribution_and_trend_statistics() {
+        let score = |day: u32, wpm: f64, accuracy: f64, language: Language| HighScore {
+            accuracy,
+            language,
+            timestamp: format!("2024-01-{:02}T12:00:00Z", day).parse().unwrap(),
+            duration_secs: None,
+            ..HighScore::sample("Player", wpm)
+        };
+        let scores = vec![
+            score(1, 40.0, 100.0, Language::English),
+            score(8, 50.0, 90.0, Language::German),
+            score(15, 60.0, 100.0, Language::English),
+            score(22, 70.0, 100.0, Language::English),
+            score(29, 80.0, 100.0, Language::English),
+        ];
+        let stats = HighScoreStatistics::from_history(&scores);
+
+        assert_eq!(stats.median_wpm, 60.0);
+        assert_eq!(stats.p10_wpm, 44.0);
+        assert_eq!(stats.p90_wpm, 76.0);
+        assert!((stats.wpm_std_dev - 200.0f64.sqrt()).abs() < 1e-9);
+        assert_eq!(stats.rolling_7d_wpm, 80.0);
+        assert_eq!(stats.rolling_30d_wpm, 60.0);
+        assert!((stats.wpm_per_week - 10.0).abs() < 1e-9);
+        assert_eq!(stats.weighted_wpm, 59.0);
+
+        assert_eq!(stats.by_language[&Language::German].total_tests, 1);
+        let english = &stats.by_language[&Language::English];
+        assert_eq!(english.median_wpm, 65.0);
+        assert!(english.by_language.is_empty());
+        assert_eq!(stats.by_difficulty[&Difficulty::Medium].total_tests, 5);
+
+        assert_eq!(percentile(&[], 50.0), 0.0);
+        assert_eq!(percentile(&[42.0], 90.0), 42.0);
+    }
+
+    #[test]
+    fn test_history_starts_with_existing_table() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        std::fs::write(
+            &path,
+            r#"[{"name": "Old", "wpm": 42.0, "accuracy": 97.0, "language": "en",
+                "difficulty": "Medium", "timestamp": "2024-01-01 12:00:00"}]"#,
+        )
+        .unwrap();
+        let manager = HighScoreManager::new(&path, 10);
+        assert_eq!(manager.load_history().unwrap().len(), 1);
+
+        let mut score = manager.load().unwrap().remove(0);
+        score.name = "New".to_string();
+        manager.add_score(score).unwrap();
+
+        let history = manager.load_history().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].name, "Old");
+    }
+
+    #[test]
+    fn test_modes_ranked_separately() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        let manager = HighScoreManager::new(&path, 2);
+
+        let make = |wpm: f64, mode: TestMode, time_limit: Option<u64>| HighScore {
+            mode,
+            time_limit,
+            ..HighScore::sample("Player", wpm)
+        };
+
+        for wpm in [40.0, 50.0, 60.0] {
+            manager.add_score(make(wpm, TestMode::Words, None)).unwrap();
+        }
+        manager.add_score(make(30.0, TestMode::Time, Some(30))).unwrap();
+        manager.add_score(make(20.0, TestMode::Time, Some(60))).unwrap();
+
+        // Slow timed runs are not pushed out by faster word-count runs
+        let words = manager
+            .get_ranking(Language::English, Difficulty::Medium, TestMode::Words, None)
+            .unwrap();
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].wpm, 60.0);
+
+        let timed = manager
+            .get_ranking(Language::English, Difficulty::Medium, TestMode::Time, Some(30))
+            .unwrap();
+        assert_eq!(timed.len(), 1);
+        assert_eq!(timed[0].wpm, 30.0);
+        assert_eq!(timed[0].mode_label(), "time 30s");
+    }
+
+    #[test]
+    fn test_load_entry_without_speed_metrics() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        std::fs::write(
+            &path,
+            r#"[{"name": "Old", "wpm": 42.0, "accuracy": 97.0, "language": "en",
+                "difficulty": "Medium", "timestamp": "2024-01-01 12:00:00"}]"#,
+        )
+        .unwrap();
+
+        let scores = HighScoreManager::new(&path, 10).load().unwrap();
+        assert_eq!(scores[0].wpm, 42.0);
+        assert_eq!(scores[0].raw_wpm, 0.0);
+        assert!(scores[0].wpm_series.is_empty());
+    }
+
+    #[test]
+    fn test_implausible_scores_rejected_and_pruned() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        std::fs::write(
+            &path,
+            r#"[{"name": "Cheat", "wpm": 2439024.39, "accuracy": 0.0, "language": "en",
+                "difficulty": "Medium", "timestamp": "2024-01-01 12:00:00"},
+               {"name": "Sloppy", "wpm": 80.0, "accuracy": 27.0, "language": "en",
+                "difficulty": "Medium", "timestamp": "2024-01-01 12:00:00"},
+               {"name": "Fine", "wpm": 60.0, "accuracy": 96.0, "language": "en",
+                "difficulty": "Medium", "timestamp": "2024-01-01 12:00:00"}]"#,
+        )
+        .unwrap();
+        let manager = HighScoreManager::new(&path, 10).with_validator(ScoreValidator::new(80.0));
+
+        // The old table is not counted as history where it is implausible
+        let found = manager.find_implausible().unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.name, "Cheat");
+        assert_eq!(manager.get_statistics(&ResultFilter::default()).unwrap().best_wpm, 80.0);
+
+        let mut score = manager.load().unwrap().remove(2);
+        score.name = "Fast".to_string();
+        score.duration_secs = Some(0.2);
+        assert!(matches!(manager.add_score(score), Err(UtilError::ScoreRejected(_))));
+
+        // A run with low accuracy is recorded, but does not enter the table
+        let mut score = manager.load().unwrap().remove(2);
+        score.name = "Careless".to_string();
+        score.accuracy = 50.0;
+        assert!(matches!(manager.add_score(score), Err(UtilError::ScoreRejected(_))));
+        assert_eq!(manager.load_history().unwrap().len(), 3);
+
+        // Entries of the old table that fail validation leave it with the next score
+        let mut score = manager.load().unwrap().remove(2);
+        score.name = "Steady".to_string();
+        score.wpm = 50.0;
+        manager.add_score(score).unwrap();
+        let table: Vec<String> = manager.load().unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(table, vec!["Fine", "Steady"]);
+
+        assert!(manager.find_implausible().unwrap().is_empty());
+        assert!(manager.prune().unwrap().is_empty());
+        let history: Vec<String> = manager.load_history().unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(history, vec!["Sloppy", "Fine", "Careless", "Steady"]);
+        assert_eq!(manager.load().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_implausible_runs_not_recorded() {
+        let dir = TempDir::new().unwrap();
+        let manager = HighScoreManager::new(dir.path().join("highscores.json"), 10);
+
+        let add = |name: &str, gap: fn(u64) -> u64| {
+            let mut score = HighScore::from_result(
+                name.to_string(),
+                &TestResult::calculate("a", "a", std::time::Duration::from_secs(30), 0),
+                Language::English,
+                Difficulty::Medium,
+            );
+            score.wpm = 60.0;
+            let mut log = KeystrokeLog::new();
+            log.id = score.id.clone().unwrap();
+            let mut time = 0;
+            for i in 0..40 {
+                time += gap(i);
+                log.record_char(std::time::Duration::from_millis(time), 'a', Some('a'), i as usize, false);
+            }
+            let id = log.id.clone();
+            (manager.add_score_with_log(score, &log), id)
+        };
+
+        let statistics = || {
+            let stats = manager.get_statistics(&ResultFilter::default()).unwrap();
+            serde_json::to_value(stats).unwrap()
+        };
+
+        let (outcome, human) = add("Human", |i| 120 + (i * 37) % 90);
+        assert!(outcome.is_ok());
+        let before = statistics();
+
+        let (outcome, pasted) = add("Pasted", |_| 1);
+        assert!(matches!(outcome, Err(UtilError::ScoreRejected(_))));
+        let mut short = manager.load().unwrap().remove(0);
+        short.id = None;
+        short.duration_secs = Some(0.5);
+        assert!(matches!(manager.add_score(short), Err(UtilError::ScoreRejected(_))));
+
+        // Only the real run is recorded and keeps its log
+        assert_eq!(manager.load_history().unwrap().len(), 1);
+        assert_eq!(manager.load().unwrap().len(), 1);
+        assert_eq!(statistics(), before);
+        assert!(manager.storage().load_keystroke_log(&human).unwrap().is_some());
+        assert!(manager.storage().load_keystroke_log(&pasted).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_feedback_ignores_implausible_history() {
+        let dir = TempDir::new().unwrap();
+        let manager = HighScoreManager::new(dir.path().join("highscores.json"), 10);
+        let run = |name: &str, wpm: f64| {
+            let mut score = HighScore::from_result(
+                name.to_string(),
+                &TestResult::calculate("a", "a", std::time::Duration::from_secs(30), 0),
+                Language::English,
+                Difficulty::Medium,
+            );
+            score.wpm = wpm;
+            score
+        };
+
+        // Recorded before implausible runs were kept out of the history
+        manager.storage().append_result(&run("Cheat", 2439024.4)).unwrap();
+        manager.add_score(run("Fine", 60.0)).unwrap();
+
+        let goal = Goal {
+            wpm: 100.0,
+            accuracy: 0.0,
+            language: None,
+            difficulty: None,
+            mode: None,
+        };
+        let feedback = manager.run_feedback(&run("New", 55.0), &[goal]).unwrap();
+        assert_eq!(feedback.previous_best, Some(60.0));
+        assert_eq!((feedback.rank, feedback.total), (2, 2));
+        assert!(!feedback.goals[0].reached_before);
+    }
+
+    #[test]
+    fn test_damaged_file_not_overwritten() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+        std::fs::write(&path, "[{\"name\": \"Trunc").unwrap();
+        let manager = HighScoreManager::new(&path, 10);
+
+        let score = HighScore::from_result(
+            "Player".to_string(),
+            &TestResult::calculate("a", "a", std::time::Duration::from_secs(30), 0),
+            Language::English,
+            Difficulty::Medium,
+        );
+        assert!(manager.add_score(score.clone()).is_err());
+        assert!(manager.save(&[score]).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[{\"name\": \"Trunc");
+    }
+
+    #[test]
+    fn test_concurrent_add_score() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("highscores.json");
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let manager = HighScoreManager::new(&path, 50);
+                    for j in 0..5 {
+                        let mut score = HighScore::from_result(
+                            format!("Player{}", i),
+                            &TestResult::calculate("a", "a", std::time::Duration::from_secs(30), 0),
+                            Language::English,
+                            Difficulty::Medium,
+                        );
+                        score.wpm = (i * 10 + j) as f64;
+                        manager.add_score(score).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let manager = HighScoreManager::new(&path, 50);
+        assert_eq!(manager.load().unwrap().len(), 20);
+        assert_eq!(manager.load_history().unwrap().len(), 20);
+    }
 }
@@ -0,0 +1,302 @@
+//! Append-only history of typing test results.
+//!
+//! The highscore table only keeps the fastest runs of each category. The
+//! history keeps every real run with its full metrics, including runs with
+//! too low accuracy for the table, one JSON object per line, so performance
+//! can be tracked over time. Implausible results are not recorded. It is
+//! stored next to the highscore file (`highscores.json` →
+//! `highscores.history.jsonl`).
+//! The first line is a header with the schema version (see [`schema`]).
+
+use crate::core::{Result, UtilError};
+use crate::modules::typing::highscore::HighScore;
+use crate::modules::typing::schema::{self, SCHEMA_VERSION};
+use crate::utils::fs as safe_fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Append-only store of test results in JSON Lines format.
+pub struct ResultHistory {
+    file_path: PathBuf,
+}
+
+impl ResultHistory {
+    /// Create a history stored in the given file.
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        Self {
+            file_path: file_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Create the history that belongs to a highscore file.
+    pub fn for_highscore_file<P: AsRef<Path>>(highscore_file: P) -> Self {
+        Self::new(highscore_file.as_ref().with_extension("history.jsonl"))
+    }
+
+    /// Path of the history file.
+    pub fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Check whether the history file exists.
+    pub fn exists(&self) -> bool {
+        self.file_path.exists()
+    }
+
+    /// Append a result to the history.
+    ///
+    /// Only the header line is read; a history in an older format is
+    /// migrated first. The caller holds the storage lock (see
+    /// [`Storage::lock`](crate::storage::Storage::lock)), so appends of
+    /// several processes do not interleave.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn append(&self, score: &HighScore) -> Result<()> {
+        if self.version()? < SCHEMA_VERSION {
+            self.migrate()?;
+        }
+        if let Some(parent) = self.file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.file_path)
+            .map_err(|e| UtilError::HighscoreError(format!("Failed to open history file: {}", e)))?;
+
+        let mut line = String::new();
+        if file.metadata()?.len() == 0 {
+            line.push_str(&schema::history_header()?);
+            line.push('\n');
+        } else if !ends_with_newline(&mut file)? {
+            // Start on a new line if an earlier append was interrupted
+            line.push('\n');
+        }
+        line.push_str(&serde_json::to_string(score)?);
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load all results, oldest first.
+    ///
+    /// A missing file is an empty history. Lines that are not complete JSON
+    /// (left behind by a crash while appending) are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is from a newer version
+    /// or has a result that cannot be decoded (naming its line), so the
+    /// result is never dropped by a later rewrite.
+    pub fn load(&self) -> Result<Vec<HighScore>> {
+        Ok(self.read()?.1)
+    }
+
+    /// Read the schema version from the header line only.
+    ///
+    /// A missing or empty file is in the current format.
+    fn version(&self) -> Result<u32> {
+        if !self.file_path.exists() {
+            return Ok(SCHEMA_VERSION);
+        }
+
+        let file = File::open(&self.file_path)
+            .map_err(|e| UtilError::HighscoreError(format!("Failed to open history file: {}", e)))?;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                return Ok(schema::history_version(&line)?.unwrap_or(1));
+            }
+        }
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// Read the schema version and all results.
+    fn read(&self) -> Result<(u32, Vec<HighScore>)> {
+        if !self.file_path.exists() {
+            return Ok((SCHEMA_VERSION, Vec::new()));
+        }
+
+        let file = File::open(&self.file_path)
+            .map_err(|e| UtilError::HighscoreError(format!("Failed to open history file: {}", e)))?;
+
+        // Without a header line the file is in the first format
+        let mut version = None;
+        let mut scores = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if version.is_none() {
+                if let Some(header) = schema::history_version(&line)? {
+                    version = Some(header);
+                    continue;
+                }
+                version = Some(1);
+            }
+            if serde_json::from_str::<serde_json::Value>(&line).is_err() {
+                continue;
+            }
+            let score = schema::decode_history_line(&line, version.unwrap_or(1)).map_err(|e| {
+                UtilError::HighscoreError(format!(
+                    "Failed to read line {} of {}: {}",
+                    index + 1,
+                    self.file_path.display(),
+                    e
+                ))
+            })?;
+            scores.push(score);
+        }
+        Ok((version.unwrap_or(SCHEMA_VERSION), scores))
+    }
+
+    /// Upgrade a history in an older format in place.
+    ///
+    /// The previous file is kept as `<file>.bak`. A history with a result
+    /// that cannot be decoded is left untouched.
+    ///
+    /// # Returns
+    ///
+    /// Whether the file was migrated.
+    pub fn migrate(&self) -> Result<bool> {
+        if self.version()? >= SCHEMA_VERSION {
+            return Ok(false);
+        }
+        let (_, scores) = self.read()?;
+        self.rewrite(&scores)?;
+        Ok(true)
+    }
+
+    /// Replace the whole history, e.g. after removing implausible results.
+    ///
+    /// The file is replaced atomically and the previous version is kept as
+    /// `<file>.bak`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn rewrite(&self, scores: &[HighScore]) -> Result<()> {
+        let mut contents = schema::history_header()?.into_bytes();
+        contents.push(b'\n');
+        for score in scores {
+            serde_json::to_writer(&mut contents, score)?;
+            contents.push(b'\n');
+        }
+        safe_fs::write_atomic_with_backup(&self.file_path, &contents)
+    }
+}
+
+/// Check whether a file is empty or ends with a newline.
+fn ends_with_newline(file: &mut File) -> Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Difficulty;
+    use tempfile::TempDir;
+
+    fn score(name: &str, wpm: f64) -> HighScore {
+        HighScore::sample(name, wpm)
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = TempDir::new().unwrap();
+        let history = ResultHistory::for_highscore_file(dir.path().join("highscores.json"));
+        assert_eq!(history.path(), dir.path().join("highscores.history.jsonl"));
+        assert!(history.load().unwrap().is_empty());
+
+        history.append(&score("a", 40.0)).unwrap();
+        history.append(&score("b", 50.0)).unwrap();
+
+        let scores = history.load().unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0].name, "a");
+        assert_eq!(scores[1].wpm, 50.0);
+
+        history.rewrite(&scores[1..]).unwrap();
+        assert_eq!(history.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_interrupted_append_skipped() {
+        let dir = TempDir::new().unwrap();
+        let history = ResultHistory::new(dir.path().join("history.jsonl"));
+        history.append(&score("a", 40.0)).unwrap();
+
+        let mut file = OpenOptions::new().append(true).open(history.path()).unwrap();
+        file.write_all(b"{\"name\": \"b\", \"wp").unwrap();
+        assert_eq!(history.load().unwrap().len(), 1);
+
+        // The next result starts on its own line
+        history.append(&score("c", 60.0)).unwrap();
+        let scores = history.load().unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[1].name, "c");
+    }
+
+    #[test]
+    fn test_migrate_first_format() {
+        let dir = TempDir::new().unwrap();
+        let history = ResultHistory::new(dir.path().join("history.jsonl"));
+        std::fs::write(
+            history.path(),
+            concat!(
+                r#"{"name": "a", "wpm": 40.0, "accuracy": 95.0, "language": "en", "#,
+                r#""difficulty": "Mittel/Medium", "timestamp": "2024-01-01 12:00:00"}"#,
+                "\n"
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(history.load().unwrap()[0].difficulty, Difficulty::Medium);
+        assert_eq!(history.version().unwrap(), 1);
+        history.append(&score("b", 50.0)).unwrap();
+
+        let content = std::fs::read_to_string(history.path()).unwrap();
+        assert!(content.starts_with(&schema::history_header().unwrap()));
+        assert_eq!(history.version().unwrap(), SCHEMA_VERSION);
+        assert!(!history.migrate().unwrap());
+        let scores = history.load().unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0].name, "a");
+    }
+
+    #[test]
+    fn test_undecodable_line_not_dropped() {
+        let dir = TempDir::new().unwrap();
+        let history = ResultHistory::new(dir.path().join("history.jsonl"));
+        let contents = concat!(
+            r#"{"name": "a", "wpm": 40.0, "accuracy": 95.0, "language": "en", "#,
+            r#""difficulty": "Medium", "timestamp": "2024-01-01 12:00:00"}"#,
+            "\n",
+            r#"{"name": "b", "wpm": 45.0, "accuracy": 95.0, "language": "en", "#,
+            r#""difficulty": "Extreme", "timestamp": "2024-01-02 12:00:00"}"#,
+            "\n",
+            r#"{"name": "c", "wpm": 50.0, "accuracy": 95.0, "language": "en", "#,
+            r#""difficulty": "Hard", "timestamp": "2024-01-03 12:00:00"}"#,
+            "\n"
+        );
+        std::fs::write(history.path(), contents).unwrap();
+
+        let error = history.load().unwrap_err().to_string();
+        assert!(error.contains("line 2"), "{}", error);
+        assert!(history.migrate().is_err());
+        assert!(history.append(&score("d", 60.0)).is_err());
+        assert_eq!(std::fs::read_to_string(history.path()).unwrap(), contents);
+    }
+}
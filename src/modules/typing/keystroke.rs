@@ -0,0 +1,315 @@
+//! Keystroke event log for typing tests.
+//!
+//! Every keystroke of a test is recorded with its timestamp, the character
+//! that was expected at the cursor and whether it was a backspace. Logs are
+//! kept in the configured [`Storage`](crate::storage::Storage) backend so a
+//! run can be replayed and audited later; the JSON backend stores one file
+//! per test in a `keystrokes` directory next to the highscore file
+//! ([`KeystrokeStore`]).
+
+use crate::core::{Difficulty, Language, Result, TestMode, UtilError};
+use crate::modules::typing::unicode;
+use crate::utils::fs as safe_fs;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// What a keystroke did to the typed text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAction {
+    /// A character was appended
+    Char(char),
+    /// The last character (grapheme cluster) was removed
+    Backspace,
+}
+
+/// A single recorded keystroke.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keystroke {
+    /// Milliseconds since the first keystroke of the test
+    pub time_ms: u64,
+    /// What the keystroke did
+    pub action: KeyAction,
+    /// Character expected at the cursor (`None` beyond the end of the text);
+    /// the first character of the expected grapheme cluster
+    pub expected: Option<char>,
+    /// Cursor position (in grapheme clusters) before the keystroke
+    pub position: usize,
+    /// Inserted automatically (e.g. auto-indent) rather than typed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub synthetic: bool,
+}
+
+impl Keystroke {
+    /// Check whether this keystroke typed the expected character.
+    ///
+    /// Backspaces are never counted as correct.
+    pub fn is_correct(&self) -> bool {
+        match self.action {
+            KeyAction::Char(c) => self.expected == Some(c),
+            KeyAction::Backspace => false,
+        }
+    }
+}
+
+/// Complete keystroke log of one typing test.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeystrokeLog {
+    /// Result ID, shared with the highscore entry
+    pub id: String,
+    /// When the test was recorded (missing in old logs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Language of the test
+    pub language: Option<Language>,
+    /// Difficulty of the test
+    pub difficulty: Option<Difficulty>,
+    /// Test mode
+    #[serde(default)]
+    pub mode: TestMode,
+    /// Time limit in seconds (only for timed tests)
+    #[serde(default)]
+    pub time_limit: Option<u64>,
+    /// The text that had to be typed
+    pub target_text: String,
+    /// Recorded keystrokes in order
+    pub keystrokes: Vec<Keystroke>,
+}
+
+impl KeystrokeLog {
+    /// Create an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a typed character.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - Time since the first keystroke
+    /// * `c` - The typed character
+    /// * `expected` - The character expected at the cursor
+    /// * `position` - Cursor position before the keystroke
+    /// * `synthetic` - Whether the character was inserted automatically
+    pub fn record_char(
+        &mut self,
+        elapsed: Duration,
+        c: char,
+        expected: Option<char>,
+        position: usize,
+        synthetic: bool,
+    ) {
+        self.keystrokes.push(Keystroke {
+            time_ms: elapsed.as_millis() as u64,
+            action: KeyAction::Char(c),
+            expected,
+            position,
+            synthetic,
+        });
+    }
+
+    /// Record a backspace.
+    pub fn record_backspace(&mut self, elapsed: Duration, expected: Option<char>, position: usize) {
+        self.keystrokes.push(Keystroke {
+            time_ms: elapsed.as_millis() as u64,
+            action: KeyAction::Backspace,
+            expected,
+            position,
+            synthetic: false,
+        });
+    }
+
+    /// Time of the last keystroke.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.keystrokes.last().map(|k| k.time_ms).unwrap_or(0))
+    }
+
+    /// Reconstruct the typed text after the first `count` keystrokes.
+    pub fn typed_text_after(&self, count: usize) -> String {
+        let mut typed = String::new();
+        for keystroke in self.keystrokes.iter().take(count) {
+            match keystroke.action {
+                KeyAction::Char(c) => unicode::push_char(&mut typed, c),
+                KeyAction::Backspace => {
+                    unicode::pop_grapheme(&mut typed);
+                }
+            }
+        }
+        typed
+    }
+
+    /// Number of keystrokes that happened within `elapsed` since the start.
+    pub fn keystrokes_until(&self, elapsed: Duration) -> usize {
+        let ms = elapsed.as_millis() as u64;
+        self.keystrokes.partition_point(|k| k.time_ms <= ms)
+    }
+}
+
+/// Storage for keystroke logs, one JSON file per test.
+pub struct KeystrokeStore {
+    dir: PathBuf,
+}
+
+impl KeystrokeStore {
+    /// Create a store in the given directory.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Create the store that lives next to a highscore file
+    /// (`<highscore dir>/keystrokes`).
+    pub fn for_highscore_file<P: AsRef<Path>>(highscore_file: P) -> Self {
+        let parent = highscore_file
+            .as_ref()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Self::new(parent.join("keystrokes"))
+    }
+
+    fn path_for(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Save a log under its ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log has no ID or the file cannot be written.
+    pub fn save(&self, log: &KeystrokeLog) -> Result<PathBuf> {
+        if log.id.is_empty() {
+            return Err(UtilError::HighscoreError(
+                "Keystroke log has no result ID".to_string(),
+            ));
+        }
+
+        let path = self.path_for(&log.id);
+        safe_fs::write_atomic(&path, &serde_json::to_vec(log)?)?;
+        Ok(path)
+    }
+
+    /// Load the log of a result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no log exists for the ID or it cannot be parsed.
+    pub fn load(&self, id: &str) -> Result<KeystrokeLog> {
+        let path = self.path_for(id);
+        let file = File::open(&path).map_err(|e| {
+            UtilError::HighscoreError(format!("No keystroke log for result {}: {}", id, e))
+        })?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    /// Check whether a log exists for a result.
+    pub fn exists(&self, id: &str) -> bool {
+        self.path_for(id).exists()
+    }
+
+    /// List the IDs of all stored logs, oldest first.
+    pub fn list_ids(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut ids: Vec<String> = std::fs::read_dir(&self.dir)?
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    return None;
+                }
+                path.file_stem().and_then(|s| s.to_str()).map(str::to_string)
+            })
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Load all stored logs, oldest first.
+    ///
+    /// Logs that cannot be read or parsed are skipped.
+    pub fn load_all(&self) -> Result<Vec<KeystrokeLog>> {
+        Ok(self
+            .list_ids()?
+            .iter()
+            .filter_map(|id| self.load(id).ok())
+            .collect())
+    }
+}
+
+/// Generate a new result ID.
+///
+/// IDs are UTC timestamps with millisecond precision plus a random suffix,
+/// so they sort chronologically and are unique across processes.
+pub fn new_result_id() -> String {
+    use rand::Rng;
+    format!(
+        "{}-{:04x}",
+        chrono::Utc::now().format("%Y%m%d%H%M%S%3f"),
+        rand::thread_rng().gen::<u16>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_log() -> KeystrokeLog {
+        let mut log = KeystrokeLog::new();
+        log.id = "test-1".to_string();
+        log.target_text = "abc".to_string();
+        log.record_char(Duration::from_millis(0), 'a', Some('a'), 0, false);
+        log.record_char(Duration::from_millis(120), 'x', Some('b'), 1, false);
+        log.record_backspace(Duration::from_millis(300), Some('c'), 2);
+        log.record_char(Duration::from_millis(410), 'b', Some('b'), 1, false);
+        log.record_char(Duration::from_millis(500), 'c', Some('c'), 2, false);
+        log
+    }
+
+    #[test]
+    fn test_replay_reconstruction() {
+        let log = sample_log();
+
+        assert_eq!(log.typed_text_after(2), "ax");
+        assert_eq!(log.typed_text_after(3), "a");
+        assert_eq!(log.typed_text_after(log.keystrokes.len()), "abc");
+        assert_eq!(log.keystrokes_until(Duration::from_millis(300)), 3);
+        assert_eq!(log.duration(), Duration::from_millis(500));
+        assert!(!log.keystrokes[1].is_correct());
+        assert!(log.keystrokes[3].is_correct());
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let store = KeystrokeStore::for_highscore_file(dir.path().join("highscores.json"));
+
+        let log = sample_log();
+        let path = store.save(&log).unwrap();
+        assert!(path.starts_with(dir.path().join("keystrokes")));
+
+        assert!(store.exists("test-1"));
+        assert_eq!(store.load("test-1").unwrap(), log);
+        assert_eq!(store.list_ids().unwrap(), vec!["test-1"]);
+        assert!(store.load("missing").is_err());
+
+        std::fs::write(dir.path().join("keystrokes/broken.json"), "{").unwrap();
+        assert_eq!(store.load_all().unwrap(), vec![log]);
+    }
+
+    #[test]
+    fn test_new_result_id_unique() {
+        let a = new_result_id();
+        let b = new_result_id();
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 22);
+    }
+}
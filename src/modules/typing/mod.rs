@@ -1,13 +1,37 @@
 //! Typing test module.
 //!
 //! This module contains all logic related to typing speed tests,
-//! including word loading, scoring, and highscore management.
+//! including word loading, custom text sources, code snippets, scoring,
+//! keystroke logging and analysis, adaptive practice, highscore management and statistics reports.
 
+pub mod adaptive;
+pub mod analysis;
+pub mod custom_source;
+pub mod dashboard;
+pub mod goals;
 pub mod highscore;
+pub mod history;
+pub mod keystroke;
+pub mod report;
+pub mod schema;
 pub mod scorer;
+pub mod snippets;
+pub mod unicode;
+pub mod validation;
 pub mod word_loader;
 
 // Re-export commonly used items
+pub use adaptive::{AdaptiveGenerator, WeaknessProfile};
+pub use analysis::{KeyStat, KeystrokeAnalysis};
+pub use custom_source::{CustomSource, SourceOrder};
+pub use dashboard::{Dashboard, StatsPeriod};
+pub use goals::{Goal, GoalProgress, RunFeedback};
 pub use highscore::{HighScore, HighScoreManager, HighScoreStatistics};
+pub use history::ResultHistory;
+pub use keystroke::{KeyAction, Keystroke, KeystrokeLog, KeystrokeStore};
+pub use report::{OutputFormat, StatsGrouping, StatsReport};
+pub use schema::SCHEMA_VERSION;
 pub use scorer::TestResult;
-pub use word_loader::WordLoader;
+pub use snippets::{IndentStyle, SnippetLoader};
+pub use validation::{ScoreValidator, ValidationIssue};
+pub use word_loader::{FrequencyBand, WordFilter, WordLoader, WordRecord};
@@ -0,0 +1,493 @@
+//! Statistics reports for the command line.
+//!
+//! `rut stats` prints highscores or grouped statistics as an aligned table,
+//! JSON or CSV, so the numbers can be used in scripts and dashboards.
+
+use crate::core::{Difficulty, Language, Result};
+use crate::modules::typing::dashboard::local_day;
+use crate::modules::typing::highscore::{HighScore, HighScoreManager, HighScoreStatistics};
+use crate::storage::ResultFilter;
+use chrono::{Datelike, SecondsFormat};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Output format of a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Aligned columns for reading in a terminal
+    #[default]
+    Table,
+    /// One JSON document
+    Json,
+    /// Comma-separated values with a header line
+    Csv,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!("Unknown output format: {} (expected table, json or csv)", s)),
+        }
+    }
+}
+
+/// How results are grouped in a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsGrouping {
+    Language,
+    Difficulty,
+    /// ISO calendar week (local time)
+    Week,
+}
+
+impl fmt::Display for StatsGrouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StatsGrouping::Language => "language",
+            StatsGrouping::Difficulty => "difficulty",
+            StatsGrouping::Week => "week",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for StatsGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "language" | "lang" => Ok(StatsGrouping::Language),
+            "difficulty" | "diff" => Ok(StatsGrouping::Difficulty),
+            "week" => Ok(StatsGrouping::Week),
+            _ => Err(format!(
+                "Unknown grouping: {} (expected language, difficulty or week)",
+                s
+            )),
+        }
+    }
+}
+
+/// Group of results, ordered naturally (languages and difficulties in
+/// declaration order, weeks chronologically).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum GroupKey {
+    Language(Language),
+    Difficulty(Difficulty),
+    Week { year: i32, week: u32 },
+}
+
+impl GroupKey {
+    fn of(score: &HighScore, grouping: StatsGrouping) -> Self {
+        match grouping {
+            StatsGrouping::Language => GroupKey::Language(score.language),
+            StatsGrouping::Difficulty => GroupKey::Difficulty(score.difficulty),
+            StatsGrouping::Week => {
+                let week = local_day(score).iso_week();
+                GroupKey::Week {
+                    year: week.year(),
+                    week: week.week(),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for GroupKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupKey::Language(language) => write!(f, "{}", language.code()),
+            GroupKey::Difficulty(difficulty) => write!(f, "{}", difficulty.code()),
+            GroupKey::Week { year, week } => write!(f, "{}-W{:02}", year, week),
+        }
+    }
+}
+
+/// Statistics of one group of results.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupStats {
+    /// Language code, difficulty or ISO week (`2024-W05`)
+    pub group: String,
+    #[serde(flatten)]
+    pub stats: HighScoreStatistics,
+}
+
+/// Group results and calculate the statistics of each group.
+///
+/// # Arguments
+///
+/// * `scores` - Results in chronological order
+/// * `grouping` - What to group by
+pub fn group_results(scores: &[HighScore], grouping: StatsGrouping) -> Vec<GroupStats> {
+    let mut groups: BTreeMap<GroupKey, Vec<HighScore>> = BTreeMap::new();
+    for score in scores {
+        groups
+            .entry(GroupKey::of(score, grouping))
+            .or_default()
+            .push(score.clone());
+    }
+    groups
+        .into_iter()
+        .map(|(key, scores)| GroupStats {
+            group: key.to_string(),
+            stats: HighScoreStatistics::from_history(&scores),
+        })
+        .collect()
+}
+
+/// Rows of a report: single highscores or grouped statistics.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportRows {
+    Results(Vec<HighScore>),
+    Groups(Vec<GroupStats>),
+}
+
+/// Statistics of the matching results and the rows to print.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsReport {
+    /// Statistics over all matching results in the history
+    pub summary: HighScoreStatistics,
+    #[serde(flatten)]
+    pub rows: ReportRows,
+}
+
+impl StatsReport {
+    /// Build a report from the highscores and history of a manager.
+    ///
+    /// Without grouping the rows are the matching highscores, fastest
+    /// first. With grouping they are the statistics of each group of
+    /// matching results in the history.
+    ///
+    /// # Arguments
+    ///
+    /// * `manager` - Highscore manager to read from
+    /// * `filter` - Criteria the results must match
+    /// * `grouping` - What to group by, if anything
+    /// * `top` - Only the N fastest highscores (or groups with the highest
+    ///   average WPM)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the highscores or the history cannot be read.
+    pub fn build(
+        manager: &HighScoreManager,
+        filter: &ResultFilter,
+        grouping: Option<StatsGrouping>,
+        top: Option<usize>,
+    ) -> Result<Self> {
+        let summary = manager.get_statistics(filter)?;
+        let rows = match grouping {
+            None => {
+                let mut scores = manager.get_filtered(filter)?;
+                scores.sort_by(|a, b| b.wpm.total_cmp(&a.wpm));
+                if let Some(n) = top {
+                    scores.truncate(n);
+                }
+                ReportRows::Results(scores)
+            }
+            Some(grouping) => {
+                let mut groups = group_results(&manager.get_history(filter)?, grouping);
+                if let Some(n) = top {
+                    groups.sort_by(|a, b| b.stats.avg_wpm.total_cmp(&a.stats.avg_wpm));
+                    groups.truncate(n);
+                }
+                ReportRows::Groups(groups)
+            }
+        };
+        Ok(Self { summary, rows })
+    }
+
+    /// Render the report in a format.
+    ///
+    /// CSV contains only the rows; table and JSON include the summary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the report cannot be serialised.
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Json => {
+                let mut json = serde_json::to_string_pretty(self)?;
+                json.push('\n');
+                Ok(json)
+            }
+            OutputFormat::Csv => Ok(self.render_csv()),
+            OutputFormat::Table => Ok(self.render_table()),
+        }
+    }
+
+    fn render_csv(&self) -> String {
+        let (header, rows): (&[&str], Vec<Vec<String>>) = match &self.rows {
+            ReportRows::Results(scores) => (
+                &[
+                    "name", "wpm", "raw_wpm", "accuracy", "language", "difficulty", "mode",
+                    "time_limit", "timestamp", "duration_secs",
+                ],
+                scores
+                    .iter()
+                    .map(|s| {
+                        vec![
+                            s.name.clone(),
+                            format!("{:.2}", s.wpm),
+                            format!("{:.2}", s.raw_wpm),
+                            format!("{:.2}", s.accuracy),
+                            s.language.code().to_string(),
+                            s.difficulty.code().to_string(),
+                            s.mode.code().to_string(),
+                            s.time_limit.map(|t| t.to_string()).unwrap_or_default(),
+                            s.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+                            s.duration_secs.map(|d| format!("{:.2}", d)).unwrap_or_default(),
+                        ]
+                    })
+                    .collect(),
+            ),
+            ReportRows::Groups(groups) => (
+                &[
+                    "group", "tests", "avg_wpm", "best_wpm", "avg_accuracy", "p10_wpm",
+                    "median_wpm", "p90_wpm", "wpm_std_dev", "rolling_7d_wpm", "rolling_30d_wpm",
+                    "wpm_trend", "wpm_per_week", "weighted_wpm", "total_time_secs",
+                ],
+                groups
+                    .iter()
+                    .map(|g| {
+                        vec![
+                            g.group.clone(),
+                            g.stats.total_tests.to_string(),
+                            format!("{:.2}", g.stats.avg_wpm),
+                            format!("{:.2}", g.stats.best_wpm),
+                            format!("{:.2}", g.stats.avg_accuracy),
+                            format!("{:.2}", g.stats.p10_wpm),
+                            format!("{:.2}", g.stats.median_wpm),
+                            format!("{:.2}", g.stats.p90_wpm),
+                            format!("{:.2}", g.stats.wpm_std_dev),
+                            format!("{:.2}", g.stats.rolling_7d_wpm),
+                            format!("{:.2}", g.stats.rolling_30d_wpm),
+                            format!("{:.2}", g.stats.wpm_trend),
+                            format!("{:.2}", g.stats.wpm_per_week),
+                            format!("{:.2}", g.stats.weighted_wpm),
+                            format!("{:.0}", g.stats.total_time_secs),
+                        ]
+                    })
+                    .collect(),
+            ),
+        };
+
+        let mut csv = csv_line(header.iter().map(|h| h.to_string()));
+        for row in rows {
+            csv.push_str(&csv_line(row));
+        }
+        csv
+    }
+
+    fn render_table(&self) -> String {
+        let summary = &self.summary;
+        let mut out = format!(
+            "Tests: {} | Avg WPM: {:.1} | Avg Accuracy: {:.1}% | Best WPM: {:.1} | Acc-weighted: {:.1}\n",
+            summary.total_tests, summary.avg_wpm, summary.avg_accuracy, summary.best_wpm, summary.weighted_wpm
+        );
+        out.push_str(&format!(
+            "Median: {:.1} (p10 {:.1}, p90 {:.1}) | Std dev: {:.1} | 7 days: {:.1} | 30 days: {:.1} | Trend: {:+.2} WPM per week\n\n",
+            summary.median_wpm,
+            summary.p10_wpm,
+            summary.p90_wpm,
+            summary.wpm_std_dev,
+            summary.rolling_7d_wpm,
+            summary.rolling_30d_wpm,
+            summary.wpm_per_week
+        ));
+
+        let (header, rows): (&[&str], Vec<Vec<String>>) = match &self.rows {
+            ReportRows::Results(scores) => (
+                &["#", "Name", "WPM", "Acc", "Lang", "Diff", "Mode", "Date"],
+                scores
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| {
+                        vec![
+                            (i + 1).to_string(),
+                            s.name.clone(),
+                            format!("{:.1}", s.wpm),
+                            format!("{:.1}%", s.accuracy),
+                            s.language.code().to_string(),
+                            s.difficulty.code().to_string(),
+                            s.mode_label(),
+                            s.local_time(),
+                        ]
+                    })
+                    .collect(),
+            ),
+            ReportRows::Groups(groups) => (
+                &[
+                    "Group", "Tests", "Avg WPM", "Median", "p10", "p90", "Std dev", "Best WPM",
+                    "Avg Acc", "Acc-weighted", "WPM/week",
+                ],
+                groups
+                    .iter()
+                    .map(|g| {
+                        vec![
+                            g.group.clone(),
+                            g.stats.total_tests.to_string(),
+                            format!("{:.1}", g.stats.avg_wpm),
+                            format!("{:.1}", g.stats.median_wpm),
+                            format!("{:.1}", g.stats.p10_wpm),
+                            format!("{:.1}", g.stats.p90_wpm),
+                            format!("{:.1}", g.stats.wpm_std_dev),
+                            format!("{:.1}", g.stats.best_wpm),
+                            format!("{:.1}%", g.stats.avg_accuracy),
+                            format!("{:.1}", g.stats.weighted_wpm),
+                            format!("{:+.2}", g.stats.wpm_per_week),
+                        ]
+                    })
+                    .collect(),
+            ),
+        };
+
+        if rows.is_empty() {
+            out.push_str("No matching results.\n");
+            return out;
+        }
+
+        let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+        for row in std::iter::once(&header).chain(&rows) {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            out.push_str(cells.join("  ").trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// One CSV line, quoting fields that contain separators or quotes.
+fn csv_line(fields: impl IntoIterator<Item = String>) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, NaiveDate, TimeZone, Utc};
+    use tempfile::TempDir;
+
+    fn score(name: &str, wpm: f64, language: Language, day: u32) -> HighScore {
+        HighScore {
+            language,
+            timestamp: Local
+                .with_ymd_and_hms(2024, 1, day, 12, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            ..HighScore::sample(name, wpm)
+        }
+    }
+
+    fn manager(dir: &TempDir) -> HighScoreManager {
+        let manager = HighScoreManager::new(dir.path().join("highscores.json"), 10);
+        for (name, wpm, language, day) in [
+            ("a", 40.0, Language::English, 1),
+            ("b, jr", 60.0, Language::German, 2),
+            ("c", 50.0, Language::English, 8),
+            ("d", 70.0, Language::English, 9),
+        ] {
+            manager.add_score(score(name, wpm, language, day)).unwrap();
+        }
+        manager
+    }
+
+    #[test]
+    fn test_results_report() {
+        let dir = TempDir::new().unwrap();
+        let manager = manager(&dir);
+
+        let filter = ResultFilter::new(Some(Language::English), None);
+        let report = StatsReport::build(&manager, &filter, None, Some(2)).unwrap();
+        assert_eq!(report.summary.total_tests, 3);
+        let ReportRows::Results(scores) = &report.rows else {
+            panic!("expected results");
+        };
+        let names: Vec<&str> = scores.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["d", "c"]);
+
+        let table = report.render(OutputFormat::Table).unwrap();
+        assert!(table.contains("Tests: 3"));
+        assert!(table.lines().any(|l| l.starts_with("1  d")));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.render(OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["summary"]["total_tests"], 3);
+        assert_eq!(json["results"][0]["name"], "d");
+
+        let dated = ResultFilter::default().between(None, NaiveDate::from_ymd_opt(2024, 1, 2));
+        let report = StatsReport::build(&manager, &dated, None, None).unwrap();
+        let csv = report.render(OutputFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("name,wpm,"));
+        assert!(lines[1].starts_with("\"b, jr\",60.00,"));
+    }
+
+    #[test]
+    fn test_grouped_report() {
+        let dir = TempDir::new().unwrap();
+        let manager = manager(&dir);
+        let filter = ResultFilter::default();
+
+        let report =
+            StatsReport::build(&manager, &filter, Some(StatsGrouping::Language), None).unwrap();
+        let ReportRows::Groups(groups) = &report.rows else {
+            panic!("expected groups");
+        };
+        let labels: Vec<&str> = groups.iter().map(|g| g.group.as_str()).collect();
+        assert_eq!(labels, ["de", "en"]);
+        assert_eq!(groups[1].stats.total_tests, 3);
+        assert_eq!(groups[1].stats.median_wpm, 50.0);
+
+        // 2024-01-01 and -02 are in week 1, -08 and -09 in week 2
+        let weeks = StatsReport::build(&manager, &filter, Some(StatsGrouping::Week), Some(1))
+            .unwrap()
+            .render(OutputFormat::Csv)
+            .unwrap();
+        assert_eq!(weeks.lines().nth(1).unwrap().split(',').next(), Some("2024-W02"));
+        assert_eq!(weeks.lines().count(), 2);
+
+        assert!("weekly".parse::<StatsGrouping>().is_err());
+        assert_eq!("CSV".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+    }
+}
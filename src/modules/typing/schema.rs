@@ -0,0 +1,241 @@
+//! Versioned on-disk format of highscores and the result history.
+//!
+//! - Version 1 (implicit): the highscore file is a bare JSON array and the
+//!   history has no header. Language and difficulty are free strings (the
+//!   localised difficulty name, e.g. "Mittel/Medium") and the timestamp is a
+//!   formatted local time (`2024-01-01 12:00:00`).
+//! - Version 2: the highscore file is `{"version": 2, "scores": [...]}` and
+//!   the history starts with a `{"version": 2}` header line. Language and
+//!   difficulty are the [`Language`]/[`Difficulty`] enums and timestamps are
+//!   RFC 3339 in UTC.
+//!
+//! Version 1 data is read through [`LegacyHighScore`] and upgraded when the
+//! file is migrated or next written.
+
+use crate::core::{Difficulty, Language, Result, TestMode, UtilError};
+use crate::modules::typing::highscore::HighScore;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Current schema version.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Timestamp format of version 1 (local time).
+const LEGACY_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// The highscore file of version 2 and later.
+#[derive(Debug, Serialize, Deserialize)]
+struct HighscoreFile {
+    version: u32,
+    scores: Vec<HighScore>,
+}
+
+/// Header line of the history file.
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryHeader {
+    version: u32,
+}
+
+/// A highscore entry in the version 1 format.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LegacyHighScore {
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    wpm: f64,
+    #[serde(default)]
+    raw_wpm: f64,
+    accuracy: f64,
+    language: String,
+    difficulty: String,
+    timestamp: String,
+    #[serde(default)]
+    duration_secs: Option<f64>,
+    #[serde(default)]
+    mode: TestMode,
+    #[serde(default)]
+    time_limit: Option<u64>,
+    #[serde(default)]
+    wpm_series: Vec<f64>,
+    #[serde(default)]
+    consistency: f64,
+    #[serde(default)]
+    burst_wpm: f64,
+}
+
+impl TryFrom<LegacyHighScore> for HighScore {
+    type Error = UtilError;
+
+    fn try_from(legacy: LegacyHighScore) -> Result<Self> {
+        Ok(HighScore {
+            id: legacy.id,
+            name: legacy.name,
+            wpm: legacy.wpm,
+            raw_wpm: legacy.raw_wpm,
+            accuracy: legacy.accuracy,
+            language: parse_legacy_language(&legacy.language)?,
+            difficulty: parse_legacy_difficulty(&legacy.difficulty)?,
+            timestamp: parse_legacy_timestamp(&legacy.timestamp)?,
+            duration_secs: legacy.duration_secs,
+            mode: legacy.mode,
+            time_limit: legacy.time_limit,
+            wpm_series: legacy.wpm_series,
+            consistency: legacy.consistency,
+            burst_wpm: legacy.burst_wpm,
+        })
+    }
+}
+
+/// Parse a version 1 language ("en", "de", "English", ...).
+pub fn parse_legacy_language(value: &str) -> Result<Language> {
+    value.trim().parse().map_err(UtilError::HighscoreError)
+}
+
+/// Parse a version 1 difficulty, including the localised display names
+/// ("Mittel/Medium").
+pub fn parse_legacy_difficulty(value: &str) -> Result<Difficulty> {
+    value
+        .split('/')
+        .find_map(|part| part.trim().parse().ok())
+        .ok_or_else(|| UtilError::HighscoreError(format!("Unknown difficulty: {}", value)))
+}
+
+/// Parse a version 1 timestamp (local time); RFC 3339 is accepted as well.
+pub fn parse_legacy_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, LEGACY_TIMESTAMP_FORMAT)
+        .map_err(|e| UtilError::HighscoreError(format!("Invalid timestamp {}: {}", value, e)))?;
+    Ok(Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&naive)))
+}
+
+fn check_version(version: u32) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        return Err(UtilError::HighscoreError(format!(
+            "Data was written by a newer version (schema {}, supported up to {})",
+            version, SCHEMA_VERSION
+        )));
+    }
+    Ok(())
+}
+
+/// Parse a highscore file of any version.
+///
+/// # Returns
+///
+/// The schema version of the file and its scores in the current format.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be parsed or is from a newer version.
+pub fn decode_table(contents: &[u8]) -> Result<(u32, Vec<HighScore>)> {
+    let value: serde_json::Value = serde_json::from_slice(contents)?;
+    if value.is_array() {
+        let legacy: Vec<LegacyHighScore> = serde_json::from_value(value)?;
+        let scores = legacy.into_iter().map(HighScore::try_from).collect::<Result<_>>()?;
+        return Ok((1, scores));
+    }
+
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    check_version(version)?;
+    let file: HighscoreFile = serde_json::from_value(value)?;
+    Ok((file.version, file.scores))
+}
+
+/// Serialise highscores in the current format.
+pub fn encode_table(scores: &[HighScore]) -> Result<Vec<u8>> {
+    let file = HighscoreFile {
+        version: SCHEMA_VERSION,
+        scores: scores.to_vec(),
+    };
+    Ok(serde_json::to_vec_pretty(&file)?)
+}
+
+/// Header line for a history file in the current format (without newline).
+pub fn history_header() -> Result<String> {
+    Ok(serde_json::to_string(&HistoryHeader {
+        version: SCHEMA_VERSION,
+    })?)
+}
+
+/// Schema version announced by the first line of a history file, `None` if
+/// the line is not a header (version 1).
+///
+/// # Errors
+///
+/// Returns an error if the history is from a newer version.
+pub fn history_version(first_line: &str) -> Result<Option<u32>> {
+    match serde_json::from_str::<HistoryHeader>(first_line) {
+        Ok(header) => {
+            check_version(header.version)?;
+            Ok(Some(header.version))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parse a history line written with the given schema version.
+pub fn decode_history_line(line: &str, version: u32) -> Result<HighScore> {
+    if version < 2 {
+        let legacy: LegacyHighScore = serde_json::from_str(line)?;
+        return legacy.try_into();
+    }
+    Ok(serde_json::from_str(line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_values() {
+        assert_eq!(parse_legacy_difficulty("Mittel/Medium").unwrap(), Difficulty::Medium);
+        assert_eq!(parse_legacy_difficulty("Schwer/Hard").unwrap(), Difficulty::Hard);
+        assert_eq!(parse_legacy_difficulty("Easy").unwrap(), Difficulty::Easy);
+        assert!(parse_legacy_difficulty("Extreme").is_err());
+        assert_eq!(parse_legacy_language("en").unwrap(), Language::English);
+
+        let utc = parse_legacy_timestamp("2024-01-01T12:00:00Z").unwrap();
+        assert_eq!(utc.to_rfc3339(), "2024-01-01T12:00:00+00:00");
+        let local = parse_legacy_timestamp("2024-01-01 12:00:00").unwrap();
+        assert_eq!(
+            local.with_timezone(&Local).format(LEGACY_TIMESTAMP_FORMAT).to_string(),
+            "2024-01-01 12:00:00"
+        );
+    }
+
+    #[test]
+    fn test_decode_and_encode_table() {
+        let legacy = br#"[{"name": "Old", "wpm": 42.0, "accuracy": 97.0, "language": "de",
+            "difficulty": "Schwer/Hard", "timestamp": "2024-01-01 12:00:00"}]"#;
+        let (version, scores) = decode_table(legacy).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(scores[0].language, Language::German);
+        assert_eq!(scores[0].difficulty, Difficulty::Hard);
+
+        let encoded = encode_table(&scores).unwrap();
+        let text = String::from_utf8(encoded.clone()).unwrap();
+        assert!(text.contains("\"version\": 2"));
+        assert!(text.contains("\"difficulty\": \"hard\""));
+
+        let (version, decoded) = decode_table(&encoded).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        assert_eq!(decoded[0].timestamp, scores[0].timestamp);
+
+        assert!(decode_table(br#"{"version": 99, "scores": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_history_header() {
+        let header = history_header().unwrap();
+        assert_eq!(history_version(&header).unwrap(), Some(SCHEMA_VERSION));
+        assert_eq!(history_version(r#"{"name": "Old", "wpm": 1.0}"#).unwrap(), None);
+        assert!(history_version(r#"{"version": 99}"#).is_err());
+    }
+}
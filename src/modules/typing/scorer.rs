@@ -2,15 +2,26 @@
 //!
 //! This module provides pure functions for calculating WPM, CPM, accuracy,
 //! and other typing test metrics.
-
+//!
+//! WPM follows the usual definition of typing tools: a word is five
+//! characters, raw (gross) WPM counts every typed character and net WPM
+//! subtracts one word per uncorrected error. Characters are grapheme
+//! clusters of the NFC-normalised text (see [`unicode`]).
+
+use crate::core::TestMode;
+use crate::modules::typing::keystroke::{KeyAction, KeystrokeLog};
+use crate::modules::typing::unicode::{self, GraphemeMatch};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 /// Result of a typing test with all calculated metrics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestResult {
-    /// Words per minute
+    /// Net words per minute (uncorrected errors penalised)
     pub wpm: f64,
+    /// Raw (gross) words per minute, counting every typed character
+    #[serde(default)]
+    pub raw_wpm: f64,
     /// Characters per minute
     pub cpm: f64,
     /// Accuracy percentage (0.0-100.0)
@@ -24,6 +35,21 @@ pub struct TestResult {
     pub total_chars: usize,
     /// Correct characters typed
     pub correct_chars: usize,
+    /// How the test was ended
+    #[serde(default)]
+    pub mode: TestMode,
+    /// Time limit in seconds (only for timed tests)
+    #[serde(default)]
+    pub time_limit: Option<u64>,
+    /// Raw WPM of every second of the test
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wpm_series: Vec<f64>,
+    /// Coefficient of variation of `wpm_series` in percent (lower is steadier)
+    #[serde(default)]
+    pub consistency: f64,
+    /// Highest raw WPM reached within a single second
+    #[serde(default)]
+    pub burst_wpm: f64,
 }
 
 /// Serde helper for Duration serialization
@@ -61,19 +87,26 @@ impl TestResult {
     ///
     /// A `TestResult` with all metrics calculated.
     pub fn calculate(target: &str, typed: &str, duration: Duration, error_count: usize) -> Self {
+        let target = &unicode::normalize(target);
+        let typed = &unicode::normalize(typed);
         let seconds = duration.as_secs_f64();
+        let minutes = seconds / 60.0;
+        let (correct_chars, total_chars) = Self::calculate_accuracy_metrics(target, typed);
 
-        // Calculate WPM: (Total Characters / 5) / Minutes
         // Standard definition of a "word" is 5 characters, including spaces.
-        let wpm = if seconds > 0.0 {
-            let normalized_words = typed.len() as f64 / 5.0;
-            (normalized_words / seconds) * 60.0
+        let normalized_words = total_chars as f64 / 5.0;
+        let uncorrected_errors = total_chars - correct_chars;
+        let (raw_wpm, wpm) = if seconds > 0.0 {
+            (
+                normalized_words / minutes,
+                ((normalized_words - uncorrected_errors as f64) / minutes).max(0.0),
+            )
         } else {
-            0.0
+            (0.0, 0.0)
         };
 
         // Calculate CPM
-        let chars = target.chars().count();
+        let chars = unicode::grapheme_count(target);
         let cpm = if seconds > 0.0 {
             (chars as f64 / seconds) * 60.0
         } else {
@@ -83,42 +116,82 @@ impl TestResult {
         // Calculate accuracy
         // We use the error_count (which tracks all mistakes, including corrected ones)
         // relative to the total characters typed.
-        let total_typed_len = typed.len();
-        let accuracy = if total_typed_len > 0 {
-            let net_correct = total_typed_len.saturating_sub(error_count);
-            (net_correct as f64 / total_typed_len as f64) * 100.0
+        let accuracy = if total_chars > 0 {
+            let net_correct = total_chars.saturating_sub(error_count);
+            (net_correct as f64 / total_chars as f64) * 100.0
         } else {
             100.0
         };
 
-        // We still calculate correct_chars for other metrics, but accuracy uses the error_count
-        let (correct_chars, total_chars) = Self::calculate_accuracy_metrics(target, typed);
-
         Self {
             wpm,
+            raw_wpm,
             cpm,
             accuracy,
             duration,
             error_count,
             total_chars,
             correct_chars,
+            mode: TestMode::Words,
+            time_limit: None,
+            wpm_series: Vec::new(),
+            consistency: 0.0,
+            burst_wpm: 0.0,
         }
     }
 
+    /// Add the per-second WPM series, consistency and burst speed from the
+    /// keystroke log of the test.
+    ///
+    /// Every typed (not auto-inserted) character counts towards the second
+    /// it was typed in; the last, incomplete second is scaled to its length.
+    pub fn with_keystrokes(mut self, log: &KeystrokeLog) -> Self {
+        self.wpm_series = wpm_series(log, self.duration);
+        self.burst_wpm = self.wpm_series.iter().copied().fold(0.0, f64::max);
+        self.consistency = coefficient_of_variation(&self.wpm_series) * 100.0;
+        self
+    }
+
+    /// Record the test mode and time limit this result was achieved with.
+    ///
+    /// Results from different modes or time limits are never ranked together.
+    pub fn with_mode(mut self, mode: TestMode, time_limit: Option<u64>) -> Self {
+        self.mode = mode;
+        self.time_limit = match mode {
+            TestMode::Time => time_limit,
+            TestMode::Words | TestMode::Code | TestMode::Adaptive => None,
+        };
+        self
+    }
+
+    /// Calculate test results for a code snippet test.
+    ///
+    /// Whitespace in code is not typed character by character: an indentation
+    /// is one Tab (or inserted by auto-indent) no matter how wide it is. Both
+    /// texts are therefore normalised with [`normalize_code_whitespace`]
+    /// before scoring, so every run of spaces/tabs counts as a single
+    /// character and every newline as one keystroke (Enter).
+    pub fn calculate_code(target: &str, typed: &str, duration: Duration, error_count: usize) -> Self {
+        let target = normalize_code_whitespace(target);
+        let typed = normalize_code_whitespace(typed);
+
+        Self::calculate(&target, &typed, duration, error_count).with_mode(TestMode::Code, None)
+    }
+
     /// Calculate correct and total characters for accuracy.
     ///
-    /// Compares the typed text character-by-character with the target.
+    /// Compares the typed text grapheme by grapheme with the target.
     fn calculate_accuracy_metrics(target: &str, typed: &str) -> (usize, usize) {
-        let target_chars: Vec<char> = target.chars().collect();
-        let typed_chars: Vec<char> = typed.chars().collect();
+        let target_graphemes = unicode::graphemes(target);
+        let typed_graphemes = unicode::graphemes(typed);
 
-        let correct = typed_chars
+        let correct = typed_graphemes
             .iter()
-            .zip(target_chars.iter())
-            .filter(|(t, s)| t == s)
+            .zip(target_graphemes.iter())
+            .filter(|(t, s)| unicode::compare(t, Some(s)) == GraphemeMatch::Correct)
             .count();
 
-        let total = typed_chars.len();
+        let total = typed_graphemes.len();
 
         (correct, total)
     }
@@ -159,6 +232,55 @@ impl TestResult {
     }
 }
 
+/// Raw WPM of every second of a test.
+///
+/// # Arguments
+///
+/// * `log` - The keystrokes of the test
+/// * `duration` - Length of the test (keystrokes after it are ignored)
+pub fn wpm_series(log: &KeystrokeLog, duration: Duration) -> Vec<f64> {
+    let total_ms = duration.as_millis() as u64;
+    if total_ms == 0 {
+        return Vec::new();
+    }
+
+    let mut chars_per_second = vec![0usize; total_ms.div_ceil(1000) as usize];
+    for keystroke in &log.keystrokes {
+        if keystroke.synthetic || keystroke.time_ms >= total_ms {
+            continue;
+        }
+        if let KeyAction::Char(_) = keystroke.action {
+            chars_per_second[(keystroke.time_ms / 1000) as usize] += 1;
+        }
+    }
+
+    chars_per_second
+        .iter()
+        .enumerate()
+        .map(|(second, &chars)| {
+            let length = (total_ms - second as u64 * 1000).min(1000) as f64 / 1000.0;
+            (chars as f64 / 5.0) * 60.0 / length
+        })
+        .collect()
+}
+
+/// Coefficient of variation (standard deviation / mean) of a series.
+///
+/// Returns 0.0 for series with fewer than two values or a mean of zero.
+pub fn coefficient_of_variation(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    if mean <= 0.0 {
+        return 0.0;
+    }
+
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt() / mean
+}
+
 /// Calculate real-time accuracy during typing.
 ///
 /// This is used for live feedback during the test.
@@ -176,16 +298,42 @@ pub fn calculate_realtime_accuracy(target: &str, typed: &str) -> f64 {
         return 100.0;
     }
 
-    let target_chars: Vec<char> = target.chars().collect();
-    let typed_chars: Vec<char> = typed.chars().collect();
+    let (correct, total) = TestResult::calculate_accuracy_metrics(
+        &unicode::normalize(target),
+        &unicode::normalize(typed),
+    );
 
-    let correct = typed_chars
-        .iter()
-        .zip(target_chars.iter())
-        .filter(|(t, s)| t == s)
-        .count();
+    (correct as f64 / total as f64) * 100.0
+}
 
-    (correct as f64 / typed_chars.len() as f64) * 100.0
+/// Normalise whitespace in source code for scoring.
+///
+/// Each run of spaces and tabs is collapsed into a single space, trailing
+/// whitespace at the end of a line is removed and newlines are kept.
+///
+/// # Arguments
+///
+/// * `text` - The source code to normalise
+pub fn normalize_code_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let mut normalized = String::with_capacity(line.len());
+            let mut in_run = false;
+            for c in line.trim_end().chars() {
+                if c == ' ' || c == '\t' {
+                    if !in_run {
+                        normalized.push(' ');
+                    }
+                    in_run = true;
+                } else {
+                    normalized.push(c);
+                    in_run = false;
+                }
+            }
+            normalized
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// Calculate real-time progress percentage.
@@ -203,7 +351,9 @@ pub fn calculate_progress(target: &str, typed: &str) -> f64 {
         return 100.0;
     }
 
-    (typed.len() as f64 / target.len() as f64) * 100.0
+    let target_len = unicode::grapheme_count(&unicode::normalize(target));
+    let typed_len = unicode::grapheme_count(&unicode::normalize(typed));
+    (typed_len as f64 / target_len as f64) * 100.0
 }
 
 #[cfg(test)]
@@ -226,7 +376,7 @@ mod tests {
 
     #[test]
     fn test_wpm_calculation() {
-        // "hello world" = 2 words, 10 seconds = 12 WPM
+        // "hello world" = 11 characters = 2.2 words, 10 seconds = 13.2 WPM
         let result = TestResult::calculate(
             "hello world",
             "hello world",
@@ -234,7 +384,7 @@ mod tests {
             0,
         );
 
-        assert_eq!(result.wpm, 12.0);
+        assert!((result.wpm - 13.2).abs() < 1e-9);
     }
 
     #[test]
@@ -260,11 +410,95 @@ mod tests {
             error_count: 0,
             total_chars: 100,
             correct_chars: 99,
+            mode: TestMode::Words,
+            time_limit: None,
+            raw_wpm: 70.0,
+            wpm_series: Vec::new(),
+            consistency: 0.0,
+            burst_wpm: 0.0,
         };
 
         assert_eq!(perfect.rating(), "PERFEKT! Ausgezeichnete Leistung!");
     }
 
+    #[test]
+    fn test_with_mode() {
+        let result = TestResult::calculate("hello", "hello", Duration::from_secs(15), 0)
+            .with_mode(TestMode::Time, Some(15));
+        assert_eq!(result.mode, TestMode::Time);
+        assert_eq!(result.time_limit, Some(15));
+
+        // Word-count tests never carry a time limit
+        let result = result.with_mode(TestMode::Words, Some(15));
+        assert_eq!(result.time_limit, None);
+    }
+
+    #[test]
+    fn test_code_whitespace() {
+        assert_eq!(
+            normalize_code_whitespace("fn a() {\n\t\tb();  \n    }"),
+            "fn a() {\n b();\n }"
+        );
+
+        // Tab and spaces indentation score the same
+        let target = "if x:\n        y = 1";
+        let with_tab = TestResult::calculate_code(target, "if x:\n\ty = 1", Duration::from_secs(6), 0);
+        let with_spaces = TestResult::calculate_code(target, target, Duration::from_secs(6), 0);
+
+        assert_eq!(with_tab.correct_chars, with_tab.total_chars);
+        assert_eq!(with_tab.total_chars, 12);
+        assert_eq!(with_tab.wpm, with_spaces.wpm);
+        assert_eq!(with_tab.mode, TestMode::Code);
+    }
+
+    #[test]
+    fn test_raw_and_net_wpm() {
+        // 10 chars in 6 seconds = 2 words in 0.1 min = 20 raw WPM,
+        // two uncorrected errors cost 2 words = 0 net WPM
+        let result = TestResult::calculate("abcdefghij", "abcdefghxx", Duration::from_secs(6), 2);
+        assert!((result.raw_wpm - 20.0).abs() < 1e-9);
+        assert!((result.wpm - 0.0).abs() < 1e-9);
+
+        // Characters, not bytes, are counted
+        let umlauts = TestResult::calculate("äöü", "äöü", Duration::from_secs(6), 0);
+        assert_eq!(umlauts.total_chars, 3);
+        assert!((umlauts.wpm - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unicode_scoring() {
+        // Decomposed input scores like composed input
+        let result = TestResult::calculate("Grüße", "Gru\u{0308}ße", Duration::from_secs(6), 0);
+        assert_eq!(result.correct_chars, 5);
+        assert_eq!(result.total_chars, 5);
+        assert_eq!(result.accuracy, 100.0);
+
+        let emoji = TestResult::calculate("hi 👋🏽!", "hi 👋🏽?", Duration::from_secs(6), 1);
+        assert_eq!(emoji.total_chars, 5);
+        assert_eq!(emoji.correct_chars, 4);
+
+        assert_eq!(calculate_realtime_accuracy("café", "cafe"), 75.0);
+        assert_eq!(calculate_progress("Straße", "Stra"), 4.0 / 6.0 * 100.0);
+        assert_eq!(calculate_progress("🇩🇪🇫🇷", "🇩🇪"), 50.0);
+    }
+
+    #[test]
+    fn test_wpm_series_consistency_and_burst() {
+        let mut log = KeystrokeLog::new();
+        // 10 chars in the first second, 5 in the second, 5 in the last half second
+        let times = (0..10).map(|i| i * 100).chain((0..5).map(|i| 1000 + i * 200)).chain((0..5).map(|i| 2000 + i * 100));
+        for (i, ms) in times.enumerate() {
+            log.record_char(Duration::from_millis(ms), 'a', Some('a'), i, false);
+        }
+        log.record_char(Duration::from_millis(2100), ' ', Some(' '), 20, true);
+
+        let result = TestResult::calculate("a", "a", Duration::from_millis(2500), 0).with_keystrokes(&log);
+        assert_eq!(result.wpm_series, vec![120.0, 60.0, 120.0]);
+        assert_eq!(result.burst_wpm, 120.0);
+        // mean 100, standard deviation 28.28
+        assert!((result.consistency - 28.284).abs() < 0.01);
+    }
+
     #[test]
     fn test_realtime_accuracy() {
         let accuracy = calculate_realtime_accuracy("hello", "hallo");
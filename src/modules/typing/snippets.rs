@@ -0,0 +1,324 @@
+//! Code snippet loading for code typing tests.
+//!
+//! Snippets are taken either from real source files (a single file or a
+//! directory) or from the bundled corpus of Rust, Python and JavaScript
+//! examples. Indentation, newlines and symbols are kept as they are; only
+//! the indentation unit is converted to the configured style.
+
+use crate::core::{CodeLanguage, Difficulty, Result, UtilError};
+use crate::modules::typing::unicode;
+use rand::seq::SliceRandom;
+use std::path::{Path, PathBuf};
+
+/// Bundled snippet corpus, compiled into the binary.
+const BUNDLED_RUST: &str = include_str!("../../../data/snippets/rust.rs");
+const BUNDLED_PYTHON: &str = include_str!("../../../data/snippets/python.py");
+const BUNDLED_JAVASCRIPT: &str = include_str!("../../../data/snippets/javascript.js");
+
+/// Indentation style used for the snippet text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    /// Indent with the given number of spaces per level
+    Spaces(usize),
+    /// Indent with one tab per level
+    Tab,
+}
+
+impl IndentStyle {
+    /// Create an indent style from the config values (`"spaces"` or `"tab"`).
+    pub fn from_config(style: &str, width: usize) -> Self {
+        match style {
+            "tab" => IndentStyle::Tab,
+            _ => IndentStyle::Spaces(width.max(1)),
+        }
+    }
+
+    /// The text inserted for one indentation level (e.g. when pressing Tab).
+    pub fn unit(&self) -> String {
+        match self {
+            IndentStyle::Spaces(width) => " ".repeat(*width),
+            IndentStyle::Tab => "\t".to_string(),
+        }
+    }
+}
+
+/// Snippet loader for code typing tests.
+pub struct SnippetLoader {
+    source: Option<PathBuf>,
+}
+
+impl SnippetLoader {
+    /// Create a loader that uses the bundled snippet corpus.
+    pub fn bundled() -> Self {
+        Self { source: None }
+    }
+
+    /// Create a loader that takes snippets from a source file or directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A source file, or a directory that is searched recursively
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            source: Some(path.as_ref().to_path_buf()),
+        }
+    }
+
+    /// Load all snippets for a language.
+    ///
+    /// A single source file is used regardless of its extension; directories
+    /// only contribute files with a matching extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a source file cannot be read or no snippets are found.
+    pub fn load_snippets(&self, language: CodeLanguage) -> Result<Vec<String>> {
+        let snippets = match &self.source {
+            None => split_blocks(bundled_source(language)),
+            Some(path) if path.is_dir() => {
+                let mut files = Vec::new();
+                collect_source_files(path, language, &mut files)?;
+                files.sort();
+
+                let mut snippets = Vec::new();
+                for file in files {
+                    snippets.extend(split_blocks(&read_source(&file)?));
+                }
+                snippets
+            }
+            Some(path) => split_blocks(&read_source(path)?),
+        };
+
+        if snippets.is_empty() {
+            return Err(UtilError::NoMatchingSnippets {
+                language: language.to_string(),
+            });
+        }
+
+        Ok(snippets)
+    }
+
+    /// Pick a random snippet for a code typing test.
+    ///
+    /// Snippets that fit into the line limit of the difficulty are preferred;
+    /// if none fit, a random snippet is cut to the limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - Programming language of the snippet
+    /// * `difficulty` - Difficulty level (limits the number of lines)
+    /// * `indent` - Indentation style the snippet is converted to
+    pub fn generate_snippet(
+        &self,
+        language: CodeLanguage,
+        difficulty: Difficulty,
+        indent: IndentStyle,
+    ) -> Result<String> {
+        let snippets = self.load_snippets(language)?;
+        let max_lines = difficulty.max_snippet_lines();
+        let mut rng = rand::thread_rng();
+
+        let fitting: Vec<&String> = snippets
+            .iter()
+            .filter(|s| s.lines().count() <= max_lines)
+            .collect();
+
+        let snippet = match fitting.choose(&mut rng) {
+            Some(snippet) => (*snippet).clone(),
+            None => snippets
+                .choose(&mut rng)
+                .map(|s| s.lines().take(max_lines).collect::<Vec<_>>().join("\n"))
+                .unwrap_or_default(),
+        };
+
+        Ok(reindent(&snippet, indent))
+    }
+}
+
+fn bundled_source(language: CodeLanguage) -> &'static str {
+    match language {
+        CodeLanguage::Rust => BUNDLED_RUST,
+        CodeLanguage::Python => BUNDLED_PYTHON,
+        CodeLanguage::JavaScript => BUNDLED_JAVASCRIPT,
+    }
+}
+
+fn read_source(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|e| UtilError::SnippetLoadError {
+        path: path.to_path_buf(),
+        source: e,
+    })
+}
+
+fn collect_source_files(dir: &Path, language: CodeLanguage, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir).map_err(|e| UtilError::SnippetLoadError {
+        path: dir.to_path_buf(),
+        source: e,
+    })?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_source_files(&path, language, files)?;
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(CodeLanguage::from_extension)
+            == Some(language)
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Split source code into top-level blocks.
+///
+/// A new block starts after one or more blank lines when the following line
+/// is not indented, so functions, classes and impl blocks stay together even
+/// if they contain blank lines themselves. Trailing whitespace is removed.
+pub fn split_blocks(source: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut pending_blank = false;
+
+    for line in source.lines() {
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            pending_blank = !current.is_empty();
+            continue;
+        }
+
+        let indented = line.starts_with(' ') || line.starts_with('\t');
+        if pending_blank && !indented {
+            blocks.push(current.join("\n"));
+            current.clear();
+        } else if pending_blank {
+            current.push("");
+        }
+
+        pending_blank = false;
+        current.push(line);
+    }
+
+    if !current.is_empty() {
+        blocks.push(current.join("\n"));
+    }
+
+    blocks
+}
+
+/// Convert the indentation of a snippet to the given style.
+///
+/// The indentation unit of the snippet is detected from the smallest
+/// non-zero run of leading spaces; tabs always count as one level.
+pub fn reindent(snippet: &str, style: IndentStyle) -> String {
+    let unit = snippet
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start_matches(' ').len())
+        .filter(|&n| n > 0)
+        .min()
+        .unwrap_or(4);
+
+    snippet
+        .lines()
+        .map(|line| {
+            let content = line.trim_start_matches([' ', '\t']);
+            let leading = &line[..line.len() - content.len()];
+            let tabs = leading.chars().filter(|&c| c == '\t').count();
+            let spaces = leading.chars().filter(|&c| c == ' ').count();
+            let levels = tabs + spaces / unit;
+            let rest = spaces % unit;
+
+            format!("{}{}{}", style.unit().repeat(levels), " ".repeat(rest), content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Leading whitespace of the target line that starts at `position` (in
+/// grapheme clusters).
+///
+/// Used for auto-indent: after the user presses Enter, the indentation of
+/// the next line is inserted automatically.
+pub fn indentation_at(target: &str, position: usize) -> String {
+    target[unicode::prefix(target, position).len()..]
+        .chars()
+        .take_while(|&c| c == ' ' || c == '\t')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_split_blocks_keeps_inner_blank_lines() {
+        let source = "class A:\n    def a(self):\n        pass\n\n    def b(self):\n        pass\n\n\nx = 1\n";
+        let blocks = split_blocks(source);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            blocks[0],
+            "class A:\n    def a(self):\n        pass\n\n    def b(self):\n        pass"
+        );
+        assert_eq!(blocks[1], "x = 1");
+    }
+
+    #[test]
+    fn test_reindent() {
+        let snippet = "fn a() {\n  if x {\n    y();\n  }\n}";
+
+        assert_eq!(
+            reindent(snippet, IndentStyle::Tab),
+            "fn a() {\n\tif x {\n\t\ty();\n\t}\n}"
+        );
+        assert_eq!(
+            reindent(snippet, IndentStyle::Spaces(4)),
+            "fn a() {\n    if x {\n        y();\n    }\n}"
+        );
+    }
+
+    #[test]
+    fn test_indentation_at() {
+        let target = "fn a() {\n    b();\n}";
+        assert_eq!(indentation_at(target, 9), "    ");
+        assert_eq!(indentation_at(target, 0), "");
+
+        // Positions count graphemes, not bytes
+        let target = "// é\n  x";
+        assert_eq!(indentation_at(target, 5), "  ");
+    }
+
+    #[test]
+    fn test_bundled_corpus() {
+        let loader = SnippetLoader::bundled();
+        for language in [CodeLanguage::Rust, CodeLanguage::Python, CodeLanguage::JavaScript] {
+            let snippets = loader.load_snippets(language).unwrap();
+            assert!(snippets.len() >= 5, "too few {} snippets", language);
+
+            let snippet = loader
+                .generate_snippet(language, Difficulty::Easy, IndentStyle::Spaces(4))
+                .unwrap();
+            assert!(snippet.lines().count() <= Difficulty::Easy.max_snippet_lines());
+        }
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(b"def a():\n    return 1\n\ndef b():\n    return 2\n")
+            .unwrap();
+        temp_file.flush().unwrap();
+
+        let loader = SnippetLoader::from_path(temp_file.path());
+        let snippets = loader.load_snippets(CodeLanguage::Python).unwrap();
+        assert_eq!(snippets, vec!["def a():\n    return 1", "def b():\n    return 2"]);
+    }
+}
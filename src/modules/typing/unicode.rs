@@ -0,0 +1,235 @@
+//! Unicode-aware text handling for typing tests.
+//!
+//! Target and typed text are compared grapheme by grapheme: a grapheme
+//! cluster is what the user sees as one character (`ä`, `é`, `👍🏽`), no
+//! matter how many bytes or code points it has. All text is normalised to
+//! NFC first, so composed (`ä`) and decomposed (`a` + combining diaeresis)
+//! input compare equal.
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How a typed grapheme compares to the expected one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphemeMatch {
+    /// Typed grapheme equals the expected one
+    Correct,
+    /// Typed grapheme is the start of the expected one (e.g. `a` of `ä`
+    /// typed with a combining mark still to come)
+    Partial,
+    /// Typed grapheme differs, or nothing was expected
+    Wrong,
+}
+
+/// Normalise text to NFC (canonical composition).
+pub fn normalize(text: &str) -> String {
+    text.nfc().collect()
+}
+
+/// Split text into extended grapheme clusters.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
+
+/// Number of grapheme clusters in a text.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// The grapheme at `index`, if the text is long enough.
+pub fn grapheme_at(text: &str, index: usize) -> Option<&str> {
+    text.graphemes(true).nth(index)
+}
+
+/// The first `count` graphemes of a text.
+pub fn prefix(text: &str, count: usize) -> &str {
+    match text.grapheme_indices(true).nth(count) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Remove the last grapheme of a text, returning it.
+pub fn pop_grapheme(text: &mut String) -> Option<String> {
+    let (start, last) = text.grapheme_indices(true).next_back()?;
+    let last = last.to_string();
+    text.truncate(start);
+    Some(last)
+}
+
+/// Compare a typed grapheme with the expected one.
+pub fn compare(typed: &str, expected: Option<&str>) -> GraphemeMatch {
+    let Some(expected) = expected else {
+        return GraphemeMatch::Wrong;
+    };
+
+    let typed: String = typed.nfd().collect();
+    let expected: String = expected.nfd().collect();
+    if typed == expected {
+        GraphemeMatch::Correct
+    } else if expected.starts_with(&typed) {
+        GraphemeMatch::Partial
+    } else {
+        GraphemeMatch::Wrong
+    }
+}
+
+/// Number of errors caused by a keystroke that changed the typed text from
+/// `before` to `after` (both NFC).
+///
+/// A keystroke either starts a new grapheme or, for combining marks, extends
+/// the last one. A grapheme that is left incomplete when the next one starts
+/// counts as an error, as does a grapheme that turns wrong.
+pub fn keystroke_errors(target: &str, before: &str, after: &str) -> usize {
+    let target = graphemes(target);
+    let before = graphemes(before);
+    let after = graphemes(after);
+
+    let status = |typed: &[&str], index: usize| {
+        typed
+            .get(index)
+            .map(|g| compare(g, target.get(index).copied()))
+    };
+
+    let Some(last) = after.len().checked_sub(1) else {
+        return 0;
+    };
+    let new_status = status(&after, last);
+
+    if after.len() > before.len() {
+        // New grapheme; a partial one before it is now final
+        let abandoned = last > 0 && status(&before, last - 1) == Some(GraphemeMatch::Partial);
+        usize::from(abandoned) + usize::from(new_status == Some(GraphemeMatch::Wrong))
+    } else {
+        // The keystroke extended the last grapheme
+        let old_status = status(&before, last);
+        usize::from(
+            new_status == Some(GraphemeMatch::Wrong) && old_status != Some(GraphemeMatch::Wrong),
+        )
+    }
+}
+
+/// Check whether the whole target has been typed.
+///
+/// The last grapheme must not be waiting for a combining mark.
+pub fn is_complete(target: &str, typed: &str) -> bool {
+    let target_count = grapheme_count(target);
+    let typed_count = grapheme_count(typed);
+
+    match typed_count.cmp(&target_count) {
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Equal if typed_count == 0 => true,
+        std::cmp::Ordering::Equal => {
+            let last = typed_count - 1;
+            compare(
+                grapheme_at(typed, last).unwrap_or_default(),
+                grapheme_at(target, last),
+            ) != GraphemeMatch::Partial
+        }
+    }
+}
+
+/// Type a character into a text: append it and renormalise, so combining
+/// marks merge with the preceding character.
+pub fn push_char(text: &mut String, c: char) {
+    text.push(c);
+    if !text.is_ascii() {
+        *text = normalize(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A_UMLAUT_DECOMPOSED: &str = "a\u{0308}";
+
+    fn type_all(target: &str, input: &str) -> (String, usize) {
+        let mut typed = String::new();
+        let mut errors = 0;
+        for c in input.chars() {
+            let before = typed.clone();
+            push_char(&mut typed, c);
+            errors += keystroke_errors(target, &before, &typed);
+        }
+        (typed, errors)
+    }
+
+    #[test]
+    fn test_german_text() {
+        let target = "Grüße aus Köln";
+        assert_eq!(grapheme_count(target), 14);
+        assert_ne!(target.len(), 14);
+
+        let (typed, errors) = type_all(target, target);
+        assert_eq!(errors, 0);
+        assert!(is_complete(target, &typed));
+
+        // ß is one grapheme, "ss" is not the same
+        let (_, errors) = type_all("Straße", "Strasse");
+        assert_eq!(errors, 3);
+    }
+
+    #[test]
+    fn test_decomposed_input() {
+        let target = normalize("Bär");
+        let input = format!("B{}r", A_UMLAUT_DECOMPOSED);
+
+        let (typed, errors) = type_all(&target, &input);
+        assert_eq!(typed, "Bär");
+        assert_eq!(errors, 0);
+        assert!(is_complete(&target, &typed));
+
+        // Waiting for the combining mark is not finished yet
+        assert!(!is_complete("ä", "a"));
+        assert_eq!(compare("a", Some("ä")), GraphemeMatch::Partial);
+
+        // A missing mark counts once the next letter is typed
+        let (_, errors) = type_all(&target, "Bar");
+        assert_eq!(errors, 1);
+    }
+
+    #[test]
+    fn test_decomposed_target() {
+        let target = normalize(&format!("Bl{}ck", A_UMLAUT_DECOMPOSED));
+        assert_eq!(target, "Bläck");
+
+        let (_, errors) = type_all(&target, "Bläck");
+        assert_eq!(errors, 0);
+    }
+
+    #[test]
+    fn test_accented_text() {
+        let target = "café déjà vu";
+        let (_, errors) = type_all(target, "cafe déjà vu");
+        assert_eq!(errors, 1);
+
+        // A combining mark that turns a correct letter wrong is an error
+        let (_, errors) = type_all("e", "e\u{0301}");
+        assert_eq!(errors, 1);
+    }
+
+    #[test]
+    fn test_emoji() {
+        let target = "ok 👍🏽 👨‍👩‍👧 🇩🇪";
+        assert_eq!(grapheme_count(target), 8);
+        assert_eq!(grapheme_at(target, 3), Some("👍🏽"));
+        assert_eq!(prefix(target, 4), "ok 👍🏽");
+
+        let (typed, errors) = type_all(target, target);
+        assert_eq!(errors, 0);
+        assert!(is_complete(target, &typed));
+
+        let mut text = "hi 👍🏽".to_string();
+        assert_eq!(pop_grapheme(&mut text).as_deref(), Some("👍🏽"));
+        assert_eq!(text, "hi ");
+    }
+
+    #[test]
+    fn test_typing_beyond_target() {
+        let (_, errors) = type_all("ab", "abc");
+        assert_eq!(errors, 1);
+        assert!(is_complete("ab", "abc"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Plausibility checks for highscores.
+//!
+//! Scores are checked before they enter the highscore table: too low
+//! accuracy, impossible speeds and tests shorter than a second are rejected.
+//! Only the last two (and the keystroke timing below) mean that a result is
+//! not a real run; see [`ValidationIssue::is_implausible`]. When the keystroke
+//! log of a test is available, the timing between keys is checked as well,
+//! because pasted text or automated input arrives with no (or perfectly
+//! regular) gaps between keys.
+
+use crate::modules::typing::highscore::HighScore;
+use crate::modules::typing::keystroke::{KeyAction, KeystrokeLog};
+use crate::modules::typing::scorer::coefficient_of_variation;
+use std::fmt;
+use std::time::Duration;
+
+/// Fastest speed considered humanly possible (the typing world record is
+/// just above 300 WPM).
+pub const MAX_PLAUSIBLE_WPM: f64 = 350.0;
+
+/// Shortest test that is accepted.
+pub const MIN_DURATION: Duration = Duration::from_secs(1);
+
+/// Gaps between keys shorter than this count as simultaneous.
+const INSTANT_GAP_MS: u64 = 5;
+
+/// Share of simultaneous keys above which input counts as pasted.
+const MAX_INSTANT_SHARE: f64 = 0.25;
+
+/// Humans never type with perfectly even gaps; below this coefficient of
+/// variation the input counts as automated.
+const MIN_GAP_VARIATION: f64 = 0.05;
+
+/// Keystrokes needed before the timing is checked.
+const MIN_KEYSTROKES: usize = 20;
+
+/// A reason why a score is not accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// Accuracy below the configured minimum
+    LowAccuracy { accuracy: f64, min: f64 },
+    /// Speed faster than any human types (or not a number)
+    ImplausibleWpm { wpm: f64 },
+    /// Test shorter than [`MIN_DURATION`]
+    TooShort { seconds: f64 },
+    /// Many keys arrived at the same instant
+    PastedInput { share: f64 },
+    /// Keys arrived with machine-like regularity
+    AutomatedInput { variation: f64 },
+}
+
+impl ValidationIssue {
+    /// Whether the issue means the result is not a real run (impossible
+    /// speed, too short, pasted or automated input), as opposed to a real
+    /// run that does not qualify for the highscore table.
+    pub fn is_implausible(&self) -> bool {
+        !matches!(self, ValidationIssue::LowAccuracy { .. })
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::LowAccuracy { accuracy, min } => {
+                write!(f, "accuracy {:.1}% is below {:.1}%", accuracy, min)
+            }
+            ValidationIssue::ImplausibleWpm { wpm } => {
+                write!(f, "{:.1} WPM is not plausible (max {:.0})", wpm, MAX_PLAUSIBLE_WPM)
+            }
+            ValidationIssue::TooShort { seconds } => {
+                write!(f, "test took only {:.2}s", seconds)
+            }
+            ValidationIssue::PastedInput { share } => {
+                write!(f, "{:.0}% of the keys arrived at once (pasted?)", share * 100.0)
+            }
+            ValidationIssue::AutomatedInput { variation } => {
+                write!(f, "keys arrived at a constant pace (variation {:.1}%, automated?)", variation * 100.0)
+            }
+        }
+    }
+}
+
+/// Checks highscores for plausibility.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreValidator {
+    min_accuracy: f64,
+}
+
+impl Default for ScoreValidator {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl ScoreValidator {
+    /// Create a validator.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_accuracy` - Minimum accuracy percentage (`min_accuracy_for_highscore`)
+    pub fn new(min_accuracy: f64) -> Self {
+        Self { min_accuracy }
+    }
+
+    /// Check a score and, if available, the keystroke log of its test.
+    ///
+    /// # Returns
+    ///
+    /// All issues found; an empty list means the score is accepted.
+    pub fn check(&self, score: &HighScore, log: Option<&KeystrokeLog>) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        if score.accuracy < self.min_accuracy {
+            issues.push(ValidationIssue::LowAccuracy {
+                accuracy: score.accuracy,
+                min: self.min_accuracy,
+            });
+        }
+
+        if !score.wpm.is_finite() || !(0.0..=MAX_PLAUSIBLE_WPM).contains(&score.wpm) {
+            issues.push(ValidationIssue::ImplausibleWpm { wpm: score.wpm });
+        }
+
+        let duration = score
+            .duration_secs
+            .or_else(|| log.filter(|l| !l.keystrokes.is_empty()).map(|l| l.duration().as_secs_f64()));
+        if let Some(seconds) = duration {
+            if seconds < MIN_DURATION.as_secs_f64() {
+                issues.push(ValidationIssue::TooShort { seconds });
+            }
+        }
+
+        if let Some(log) = log {
+            issues.extend(check_keystroke_timing(log));
+        }
+
+        issues
+    }
+}
+
+/// Check the gaps between typed keys for pasted or automated input.
+///
+/// Only typed characters are considered (not backspaces or auto-indent);
+/// logs with fewer than `MIN_KEYSTROKES` keys are not judged.
+pub fn check_keystroke_timing(log: &KeystrokeLog) -> Option<ValidationIssue> {
+    let times: Vec<u64> = log
+        .keystrokes
+        .iter()
+        .filter(|k| !k.synthetic && matches!(k.action, KeyAction::Char(_)))
+        .map(|k| k.time_ms)
+        .collect();
+    if times.len() < MIN_KEYSTROKES {
+        return None;
+    }
+
+    let gaps: Vec<u64> = times.windows(2).map(|w| w[1].saturating_sub(w[0])).collect();
+
+    let instant = gaps.iter().filter(|&&gap| gap < INSTANT_GAP_MS).count();
+    let share = instant as f64 / gaps.len() as f64;
+    if share > MAX_INSTANT_SHARE {
+        return Some(ValidationIssue::PastedInput { share });
+    }
+
+    let gaps: Vec<f64> = gaps.into_iter().map(|gap| gap as f64).collect();
+    let variation = coefficient_of_variation(&gaps);
+    if variation < MIN_GAP_VARIATION {
+        return Some(ValidationIssue::AutomatedInput { variation });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wpm: f64, accuracy: f64, duration_secs: Option<f64>) -> HighScore {
+        HighScore {
+            accuracy,
+            duration_secs,
+            ..HighScore::sample("Player", wpm)
+        }
+    }
+
+    fn log_with_gaps(gaps: impl Iterator<Item = u64>) -> KeystrokeLog {
+        let mut log = KeystrokeLog::new();
+        let mut time = 0;
+        for (i, gap) in gaps.enumerate() {
+            time += gap;
+            log.record_char(Duration::from_millis(time), 'a', Some('a'), i, false);
+        }
+        log
+    }
+
+    #[test]
+    fn test_plausible_score_accepted() {
+        let validator = ScoreValidator::new(80.0);
+        let log = log_with_gaps((0..40).map(|i| 120 + (i * 37) % 90));
+
+        assert!(validator.check(&score(65.0, 97.0, Some(20.0)), Some(&log)).is_empty());
+    }
+
+    #[test]
+    fn test_rejections() {
+        let validator = ScoreValidator::new(80.0);
+
+        let issues = validator.check(&score(2_439_024.39, 0.0, None), None);
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(issues[0], ValidationIssue::LowAccuracy { .. }));
+        assert!(matches!(issues[1], ValidationIssue::ImplausibleWpm { .. }));
+        assert!(!issues[0].is_implausible() && issues[1].is_implausible());
+
+        let issues = validator.check(&score(60.0, 100.0, Some(0.4)), None);
+        assert_eq!(issues, vec![ValidationIssue::TooShort { seconds: 0.4 }]);
+
+        assert!(!validator.check(&score(f64::NAN, 100.0, None), None).is_empty());
+    }
+
+    #[test]
+    fn test_pasted_and_automated_input() {
+        // Half of the text arrives in one burst
+        let pasted = log_with_gaps((0..40).map(|i| if i < 20 { 1 } else { 150 + i }));
+        assert!(matches!(
+            check_keystroke_timing(&pasted),
+            Some(ValidationIssue::PastedInput { .. })
+        ));
+
+        // A script typing one key every 50ms
+        let automated = log_with_gaps(vec![50; 40].into_iter());
+        assert!(matches!(
+            check_keystroke_timing(&automated),
+            Some(ValidationIssue::AutomatedInput { .. })
+        ));
+
+        // Too few keys to judge
+        assert_eq!(check_keystroke_timing(&log_with_gaps(vec![50; 5].into_iter())), None);
+    }
+}
@@ -3,14 +3,16 @@
 //! This module handles loading word lists from JSON files and filtering
 //! them based on language, difficulty, and CEFR level.
 
-use crate::core::{Difficulty, Language, Result, UtilError};
+use crate::core::{CefrLevel, Difficulty, Language, Result, UtilError};
 use once_cell::sync::Lazy;
 use rand::seq::SliceRandom;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Mutex;
 
 /// English word structure from JSON file.
@@ -43,8 +45,126 @@ pub struct GermanWord {
     pub capitalization_sensitive: bool,
 }
 
+/// A word with its metadata, as kept in the cache.
+///
+/// Both `EnglishWord` and `GermanWord` records are converted into this form;
+/// German words that are not capitalization sensitive are lowercased.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordRecord {
+    pub word: String,
+    /// CEFR level, `None` if missing or unknown in the word file
+    pub cefr_level: Option<CefrLevel>,
+    /// Part of speech (e.g. "noun", "verb")
+    pub pos: String,
+    pub word_frequency: u32,
+}
+
+impl From<EnglishWord> for WordRecord {
+    fn from(w: EnglishWord) -> Self {
+        Self {
+            cefr_level: w.cefr_level.parse().ok(),
+            word: w.word,
+            pos: w.pos.to_lowercase(),
+            word_frequency: w.word_frequency,
+        }
+    }
+}
+
+impl From<GermanWord> for WordRecord {
+    fn from(w: GermanWord) -> Self {
+        Self {
+            cefr_level: w.cefr_level.parse().ok(),
+            word: if w.capitalization_sensitive {
+                w.word
+            } else {
+                w.word.to_lowercase()
+            },
+            pos: w.pos.to_lowercase(),
+            word_frequency: w.word_frequency,
+        }
+    }
+}
+
+/// Frequency band of a word, relative to the other words of its language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyBand {
+    /// The most frequent third of the word list
+    Common,
+    /// The middle third
+    Medium,
+    /// The least frequent third
+    Rare,
+}
+
+impl fmt::Display for FrequencyBand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FrequencyBand::Common => "common",
+            FrequencyBand::Medium => "medium",
+            FrequencyBand::Rare => "rare",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for FrequencyBand {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "common" | "high" | "häufig" => Ok(FrequencyBand::Common),
+            "medium" | "mid" | "mittel" => Ok(FrequencyBand::Medium),
+            "rare" | "low" | "selten" => Ok(FrequencyBand::Rare),
+            _ => Err(format!("Unknown frequency band: {}", s)),
+        }
+    }
+}
+
+/// Criteria for selecting words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordFilter {
+    /// Allowed CEFR levels (empty = any level)
+    pub cefr_levels: Vec<CefrLevel>,
+    /// Maximum word length in characters
+    pub max_length: usize,
+    /// Allowed parts of speech (empty = any)
+    pub parts_of_speech: Vec<String>,
+    /// Frequency band (`None` = any)
+    pub frequency: Option<FrequencyBand>,
+}
+
+impl WordFilter {
+    /// Create the default filter for a difficulty (CEFR levels and word length).
+    pub fn for_difficulty(difficulty: Difficulty) -> Self {
+        Self {
+            cefr_levels: difficulty.allowed_cefr_levels().to_vec(),
+            max_length: difficulty.max_word_length(),
+            parts_of_speech: Vec::new(),
+            frequency: None,
+        }
+    }
+
+    /// Restrict the filter to the given parts of speech.
+    pub fn with_parts_of_speech<S: AsRef<str>>(mut self, pos: &[S]) -> Self {
+        self.parts_of_speech = pos.iter().map(|p| p.as_ref().trim().to_lowercase()).collect();
+        self
+    }
+
+    /// Restrict the filter to a frequency band.
+    pub fn with_frequency(mut self, band: Option<FrequencyBand>) -> Self {
+        self.frequency = band;
+        self
+    }
+}
+
+/// Cache key: data directory and language.
+type CacheKey = (PathBuf, Language);
+
 /// Global word cache to avoid reloading files.
-static WORD_CACHE: Lazy<Mutex<HashMap<Language, Vec<String>>>> =
+///
+/// Keyed by data directory and language, so loaders for different
+/// directories never see each other's words.
+static WORD_CACHE: Lazy<Mutex<HashMap<CacheKey, Vec<WordRecord>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
 /// Word loader with caching capabilities.
@@ -85,27 +205,67 @@ impl WordLoader {
     /// - The JSON is malformed
     /// - No words match the filtering criteria
     pub fn load_words(&self, language: Language, difficulty: Difficulty) -> Result<Vec<String>> {
-        // Try to get from cache first
-        let cache = WORD_CACHE.lock().unwrap();
-        let cached = cache.get(&language);
-
-        let all_words = if let Some(words) = cached {
-            words.clone()
-        } else {
-            drop(cache); // Release lock before loading
-            self.load_and_cache_words(language)?
-        };
+        self.load_words_filtered(language, difficulty, &WordFilter::for_difficulty(difficulty))
+    }
 
-        // Filter and select words
-        let mut working_words = all_words;
-        self.filter_words(&mut working_words, language, difficulty)?;
-        let selected = self.select_random_words(working_words, difficulty.word_count());
+    /// Load words matching a custom filter.
+    ///
+    /// Like [`WordLoader::load_words`], but with additional part-of-speech
+    /// and frequency criteria. The difficulty still sets the word count.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the word file cannot be loaded or no words match.
+    pub fn load_words_filtered(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        filter: &WordFilter,
+    ) -> Result<Vec<String>> {
+        let words = self.load_candidates(language, difficulty, filter)?;
+        let selected = self.select_random_words(words, difficulty.word_count());
 
         Ok(selected)
     }
 
+    /// Load all words matching a filter, without selecting or shuffling.
+    ///
+    /// Used by generators that pick words themselves, such as the
+    /// adaptive practice generator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the word file cannot be loaded or no words match.
+    pub fn load_candidates(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        filter: &WordFilter,
+    ) -> Result<Vec<String>> {
+        let mut working_words = self.load_records(language)?;
+        self.filter_words(&mut working_words, language, difficulty, filter)?;
+        Ok(working_words.into_iter().map(|w| w.word).collect())
+    }
+
+    /// Load all word records of a language, from the cache if possible.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the word file cannot be read or parsed.
+    pub fn load_records(&self, language: Language) -> Result<Vec<WordRecord>> {
+        // Try to get from cache first
+        let key = (self.data_dir.clone(), language);
+        let cache = WORD_CACHE.lock().unwrap();
+        if let Some(words) = cache.get(&key) {
+            return Ok(words.clone());
+        }
+        drop(cache); // Release lock before loading
+
+        self.load_and_cache_words(language)
+    }
+
     /// Load words from file and cache them.
-    fn load_and_cache_words(&self, language: Language) -> Result<Vec<String>> {
+    fn load_and_cache_words(&self, language: Language) -> Result<Vec<WordRecord>> {
         let words = match language {
             Language::English => self.load_english_words()?,
             Language::German => self.load_german_words()?,
@@ -113,14 +273,14 @@ impl WordLoader {
 
         // Cache the loaded words
         let mut cache = WORD_CACHE.lock().unwrap();
-        cache.insert(language, words.clone());
+        cache.insert((self.data_dir.clone(), language), words.clone());
 
         Ok(words)
     }
 
     /// Load English words from JSON file.
-    fn load_english_words(&self) -> Result<Vec<String>> {
-        let path = self.data_dir.join("english_words.json");
+    fn load_english_words(&self) -> Result<Vec<WordRecord>> {
+        let path = self.data_dir.join(Language::English.word_file());
         let file = File::open(&path).map_err(|e| UtilError::WordLoadError {
             path: path.display().to_string(),
             source: e,
@@ -133,12 +293,12 @@ impl WordLoader {
                 source: std::io::Error::new(std::io::ErrorKind::InvalidData, e),
             })?;
 
-        Ok(words.into_iter().map(|w| w.word).collect())
+        Ok(words.into_iter().map(WordRecord::from).collect())
     }
 
     /// Load German words from JSON file.
-    fn load_german_words(&self) -> Result<Vec<String>> {
-        let path = self.data_dir.join("german_words.json");
+    fn load_german_words(&self) -> Result<Vec<WordRecord>> {
+        let path = self.data_dir.join(Language::German.word_file());
         let file = File::open(&path).map_err(|e| UtilError::WordLoadError {
             path: path.display().to_string(),
             source: e,
@@ -151,37 +311,53 @@ impl WordLoader {
                 source: std::io::Error::new(std::io::ErrorKind::InvalidData, e),
             })?;
 
-        // For German words, handle capitalization
-        Ok(words
-            .into_iter()
-            .map(|w| {
-                if w.capitalization_sensitive {
-                    w.word
-                } else {
-                    w.word.to_lowercase()
-                }
-            })
-            .collect())
+        // For German words, capitalization is handled in the conversion
+        Ok(words.into_iter().map(WordRecord::from).collect())
     }
 
-    /// Filter words based on difficulty criteria.
+    /// Filter words based on the filter criteria.
     ///
-    /// This is a placeholder - in the real implementation, we'd need to
-    /// reload the full word data with CEFR levels. For now, we just filter
-    /// by length as a proxy.
+    /// Words are kept if their CEFR level is allowed, they are not longer
+    /// than the maximum length, and they match the part of speech and
+    /// frequency band (if set). Word lists without any CEFR data are only
+    /// filtered by length, and words without a level are excluded otherwise.
+    /// Frequency bands are thirds of the list sorted by `word_frequency`.
     fn filter_words(
         &self,
-        words: &mut Vec<String>,
-        _language: Language,
+        words: &mut Vec<WordRecord>,
+        language: Language,
         difficulty: Difficulty,
+        filter: &WordFilter,
     ) -> Result<()> {
-        let max_length = difficulty.max_word_length();
+        let has_cefr_data = words.iter().any(|w| w.cefr_level.is_some());
 
-        words.retain(|w| w.len() <= max_length && !w.is_empty());
+        // Frequency thresholds must be computed on the full list
+        let mut frequencies: Vec<u32> = words.iter().map(|w| w.word_frequency).collect();
+        frequencies.sort_unstable_by(|a, b| b.cmp(a));
+        let third = frequencies.len() / 3;
+        let upper = frequencies.get(third).copied().unwrap_or(0);
+        let lower = frequencies.get(2 * third).copied().unwrap_or(0);
+
+        words.retain(|w| {
+            let length_ok = !w.word.is_empty() && w.word.chars().count() <= filter.max_length;
+            let cefr_ok = !has_cefr_data
+                || filter.cefr_levels.is_empty()
+                || w.cefr_level.map(|l| filter.cefr_levels.contains(&l)).unwrap_or(false);
+            let pos_ok = filter.parts_of_speech.is_empty()
+                || filter.parts_of_speech.contains(&w.pos);
+            let frequency_ok = match filter.frequency {
+                None => true,
+                Some(FrequencyBand::Common) => w.word_frequency > upper,
+                Some(FrequencyBand::Medium) => w.word_frequency > lower && w.word_frequency <= upper,
+                Some(FrequencyBand::Rare) => w.word_frequency <= lower,
+            };
+
+            length_ok && cefr_ok && pos_ok && frequency_ok
+        });
 
         if words.is_empty() {
             return Err(UtilError::NoMatchingWords {
-                language: _language.to_string(),
+                language: language.to_string(),
                 difficulty: difficulty.to_string(),
             });
         }
@@ -201,11 +377,32 @@ impl WordLoader {
         let words = self.load_words(language, difficulty)?;
         Ok(words.join(" "))
     }
+
+    /// Generate a text string from words matching a custom filter.
+    pub fn generate_text_filtered(
+        &self,
+        language: Language,
+        difficulty: Difficulty,
+        filter: &WordFilter,
+    ) -> Result<String> {
+        let words = self.load_words_filtered(language, difficulty, filter)?;
+        Ok(words.join(" "))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
+
+    fn record(word: &str, cefr: Option<CefrLevel>, pos: &str, frequency: u32) -> WordRecord {
+        WordRecord {
+            word: word.to_string(),
+            cefr_level: cefr,
+            pos: pos.to_string(),
+            word_frequency: frequency,
+        }
+    }
 
     #[test]
     fn test_word_loader_creation() {
@@ -217,18 +414,110 @@ mod tests {
     fn test_filter_by_length() {
         let loader = WordLoader::new("data");
         let mut words = vec![
-            "cat".to_string(),
-            "dog".to_string(),
-            "elephant".to_string(),
-            "a".to_string(),
+            record("cat", None, "", 0),
+            record("dog", None, "", 0),
+            record("elephant", None, "", 0),
+            record("a", None, "", 0),
         ];
 
         loader
-            .filter_words(&mut words, Language::English, Difficulty::Easy)
+            .filter_words(
+                &mut words,
+                Language::English,
+                Difficulty::Easy,
+                &WordFilter::for_difficulty(Difficulty::Easy),
+            )
             .unwrap();
 
         // Easy difficulty has max_length of 6
-        assert!(words.iter().all(|w| w.len() <= 6));
-        assert!(!words.contains(&"elephant".to_string()));
+        assert!(words.iter().all(|w| w.word.len() <= 6));
+        assert!(!words.iter().any(|w| w.word == "elephant"));
+    }
+
+    #[test]
+    fn test_filter_by_cefr_level() {
+        let loader = WordLoader::new("data");
+        let mut words = vec![
+            record("cat", Some(CefrLevel::A1), "noun", 0),
+            record("run", Some(CefrLevel::A2), "verb", 0),
+            record("vast", Some(CefrLevel::C1), "adjective", 0),
+            record("odd", None, "adjective", 0),
+        ];
+
+        loader
+            .filter_words(
+                &mut words,
+                Language::English,
+                Difficulty::Easy,
+                &WordFilter::for_difficulty(Difficulty::Easy),
+            )
+            .unwrap();
+
+        let kept: Vec<&str> = words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(kept, vec!["cat", "run"]);
+    }
+
+    #[test]
+    fn test_filter_by_pos_and_frequency() {
+        let loader = WordLoader::new("data");
+        let all = vec![
+            record("cat", Some(CefrLevel::A1), "noun", 900),
+            record("dog", Some(CefrLevel::A1), "noun", 500),
+            record("run", Some(CefrLevel::A1), "verb", 400),
+            record("emu", Some(CefrLevel::A1), "noun", 10),
+            record("yak", Some(CefrLevel::A1), "noun", 5),
+            record("hop", Some(CefrLevel::A1), "verb", 1),
+        ];
+
+        let mut nouns = all.clone();
+        let filter = WordFilter::for_difficulty(Difficulty::Easy).with_parts_of_speech(&["Noun"]);
+        loader
+            .filter_words(&mut nouns, Language::English, Difficulty::Easy, &filter)
+            .unwrap();
+        assert!(nouns.iter().all(|w| w.pos == "noun"));
+        assert_eq!(nouns.len(), 4);
+
+        let mut common = all.clone();
+        let filter = WordFilter::for_difficulty(Difficulty::Easy)
+            .with_frequency(Some(FrequencyBand::Common));
+        loader
+            .filter_words(&mut common, Language::English, Difficulty::Easy, &filter)
+            .unwrap();
+        let kept: Vec<&str> = common.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(kept, vec!["cat", "dog"]);
+
+        let mut rare = all;
+        let filter = WordFilter::for_difficulty(Difficulty::Easy)
+            .with_frequency(Some(FrequencyBand::Rare));
+        loader
+            .filter_words(&mut rare, Language::English, Difficulty::Easy, &filter)
+            .unwrap();
+        let kept: Vec<&str> = rare.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(kept, vec!["yak", "hop"]);
+    }
+
+    #[test]
+    fn test_load_german_records() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("german_words.json"),
+            r#"[
+                {"word": "Haus", "cefr_level": "A1", "pos": "Noun", "word_frequency": 10, "capitalization_sensitive": true},
+                {"word": "Gehen", "cefr_level": "A1", "pos": "verb", "word_frequency": 5},
+                {"word": "Ausnahmezustand", "cefr_level": "C1", "pos": "noun", "word_frequency": 1}
+            ]"#,
+        )
+        .unwrap();
+
+        let loader = WordLoader::new(dir.path());
+        let records = loader.load_records(Language::German).unwrap();
+        assert_eq!(records[0].word, "Haus");
+        assert_eq!(records[0].pos, "noun");
+        assert_eq!(records[1].word, "gehen");
+        assert_eq!(records[2].cefr_level, Some(CefrLevel::C1));
+
+        let mut words = loader.load_words(Language::German, Difficulty::Easy).unwrap();
+        words.sort();
+        assert_eq!(words, vec!["Haus", "gehen"]);
     }
 }
@@ -0,0 +1,167 @@
+//! JSON file storage.
+//!
+//! The highscore table is a pretty-printed, versioned JSON file (see
+//! [`schema`]), the result history an append-only JSON Lines file next to it
+//! (see [`ResultHistory`]), the learning progress another JSON file (see
+//! [`ProgressStore`]) and the learning sessions another JSON Lines file (see
+//! [`SessionHistory`]). All are replaced atomically with a `.bak` copy of
+//! the previous version. Keystroke logs are one JSON file per test (see
+//! [`KeystrokeStore`]).
+
+use crate::core::{Result, UtilError};
+use crate::modules::learning::history::{LearningSession, SessionHistory};
+use crate::modules::learning::progress::{LearningProgress, ProgressStore, SetProgress};
+use crate::modules::typing::schema::{self, SCHEMA_VERSION};
+use crate::modules::typing::{HighScore, KeystrokeLog, KeystrokeStore, ResultHistory};
+use crate::storage::{ResultFilter, Storage};
+use crate::utils::fs::{self as safe_fs, FileLock};
+use std::path::{Path, PathBuf};
+
+/// Storage in a highscore JSON file and its result history.
+pub struct JsonStorage {
+    file_path: PathBuf,
+    history: ResultHistory,
+    keystrokes: KeystrokeStore,
+    progress: ProgressStore,
+    sessions: SessionHistory,
+}
+
+impl JsonStorage {
+    /// Create a storage for the given highscore file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - Path to the highscores JSON file
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        Self {
+            file_path: file_path.as_ref().to_path_buf(),
+            history: ResultHistory::for_highscore_file(&file_path),
+            keystrokes: KeystrokeStore::for_highscore_file(&file_path),
+            progress: ProgressStore::for_highscore_file(&file_path),
+            sessions: SessionHistory::for_highscore_file(&file_path),
+        }
+    }
+
+    /// Read the highscore file with its schema version.
+    fn read(&self) -> Result<(u32, Vec<HighScore>)> {
+        if !self.file_path.exists() {
+            return Ok((SCHEMA_VERSION, Vec::new()));
+        }
+
+        let contents = std::fs::read(&self.file_path).map_err(|e| {
+            UtilError::HighscoreError(format!("Failed to open highscore file: {}", e))
+        })?;
+
+        // A freshly created (empty) file contains no scores yet
+        if contents.is_empty() {
+            return Ok((SCHEMA_VERSION, Vec::new()));
+        }
+
+        schema::decode_table(&contents).map_err(|e| {
+            let backup = safe_fs::backup_path(&self.file_path);
+            let hint = if backup.exists() {
+                format!(" (previous version in {})", backup.display())
+            } else {
+                String::new()
+            };
+            UtilError::HighscoreError(format!("Failed to parse highscore file: {}{}", e, hint))
+        })
+    }
+}
+
+impl Storage for JsonStorage {
+    /// Load all highscores from file.
+    ///
+    /// A missing or empty file contains no scores yet.
+    fn load_highscores(&self) -> Result<Vec<HighScore>> {
+        Ok(self.read()?.1)
+    }
+
+    /// Replace the highscore table.
+    ///
+    /// A file that cannot be parsed is never overwritten, so a damaged file
+    /// can still be repaired by hand.
+    fn save_highscores(&self, scores: &[HighScore]) -> Result<()> {
+        self.read()?;
+        safe_fs::write_atomic_with_backup(&self.file_path, &schema::encode_table(scores)?)
+    }
+
+    fn has_history(&self) -> Result<bool> {
+        Ok(self.history.exists())
+    }
+
+    fn append_result(&self, score: &HighScore) -> Result<()> {
+        self.history.append(score)
+    }
+
+    /// Load the matching results; the whole file is read and filtered.
+    fn load_results(&self, filter: &ResultFilter) -> Result<Vec<HighScore>> {
+        let mut scores = self.history.load()?;
+        scores.retain(|s| filter.matches(s));
+        Ok(scores)
+    }
+
+    fn replace_results(&self, scores: &[HighScore]) -> Result<()> {
+        self.history.rewrite(scores)
+    }
+
+    fn save_keystroke_log(&self, log: &KeystrokeLog) -> Result<()> {
+        self.keystrokes.save(log).map(|_| ())
+    }
+
+    fn load_keystroke_log(&self, id: &str) -> Result<Option<KeystrokeLog>> {
+        if !self.keystrokes.exists(id) {
+            return Ok(None);
+        }
+        self.keystrokes.load(id).map(Some)
+    }
+
+    fn keystroke_log_ids(&self) -> Result<Vec<String>> {
+        self.keystrokes.list_ids()
+    }
+
+    fn load_keystroke_logs(&self) -> Result<Vec<KeystrokeLog>> {
+        self.keystrokes.load_all()
+    }
+
+    fn load_progress(&self) -> Result<LearningProgress> {
+        self.progress.load()
+    }
+
+    fn save_set_progress(&self, name: &str, progress: &SetProgress) -> Result<()> {
+        self.progress.save_set(name, progress)
+    }
+
+    fn replace_progress(&self, progress: &LearningProgress) -> Result<()> {
+        self.progress.replace(progress)
+    }
+
+    fn append_session(&self, session: &LearningSession) -> Result<()> {
+        self.sessions.append(session)
+    }
+
+    fn load_sessions(&self) -> Result<Vec<LearningSession>> {
+        self.sessions.load()
+    }
+
+    fn replace_sessions(&self, sessions: &[LearningSession]) -> Result<()> {
+        self.sessions.rewrite(sessions)
+    }
+
+    fn lock(&self) -> Result<FileLock> {
+        FileLock::acquire(&self.file_path)
+    }
+
+    /// Rewrite the highscore file and the history in the current format if
+    /// either is older; the previous files are kept as `.bak`.
+    fn migrate(&self) -> Result<bool> {
+        let _lock = self.lock()?;
+        let (version, scores) = self.read()?;
+        let table_migrated = version < SCHEMA_VERSION;
+        if table_migrated {
+            safe_fs::write_atomic_with_backup(&self.file_path, &schema::encode_table(&scores)?)?;
+        }
+        let history_migrated = self.history.migrate()?;
+        Ok(table_migrated || history_migrated)
+    }
+}
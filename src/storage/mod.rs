@@ -0,0 +1,466 @@
+//! Persistence backends for scores and results.
+//!
+//! All typing data including the keystroke logs, the learning progress and
+//! the learning session history go through the [`Storage`] trait, so the
+//! backend can be chosen in the configuration (`paths.storage`):
+//!
+//! - `json`: the highscore table as a JSON file, the result and session
+//!   histories as JSON Lines, the learning progress as JSON next to it and
+//!   one JSON file per keystroke log ([`JsonStorage`])
+//! - `sqlite`: one embedded SQLite database with indexed tables
+//!   ([`SqliteStorage`]), for machines that accumulate thousands of runs
+
+pub mod json;
+pub mod sqlite;
+
+pub use json::JsonStorage;
+pub use sqlite::SqliteStorage;
+
+use crate::core::{Config, Difficulty, Language, Result, StorageBackend, TestMode, UtilError};
+use crate::modules::learning::history::LearningSession;
+use crate::modules::learning::progress::{LearningProgress, SetProgress};
+use crate::modules::typing::{HighScore, KeystrokeLog};
+use crate::utils::fs::FileLock;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+
+/// Filter for querying the result history.
+///
+/// All criteria are optional; an empty filter matches every result.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultFilter {
+    /// Only results in this language
+    pub language: Option<Language>,
+    /// Only results with this difficulty
+    pub difficulty: Option<Difficulty>,
+    /// Only results of this test mode
+    pub mode: Option<TestMode>,
+    /// Only results from this day on
+    pub from: Option<NaiveDate>,
+    /// Only results up to and including this day
+    pub to: Option<NaiveDate>,
+}
+
+impl ResultFilter {
+    /// Create a filter for a language and difficulty.
+    pub fn new(language: Option<Language>, difficulty: Option<Difficulty>) -> Self {
+        Self {
+            language,
+            difficulty,
+            ..Self::default()
+        }
+    }
+
+    /// Restrict the filter to a date range (both ends inclusive).
+    pub fn between(mut self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    /// Restrict the filter to a test mode.
+    pub fn with_mode(mut self, mode: Option<TestMode>) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Lower timestamp bound (inclusive): local midnight of `from`.
+    fn lower_bound(&self) -> Option<DateTime<Utc>> {
+        self.from.map(local_midnight)
+    }
+
+    /// Upper timestamp bound (exclusive): local midnight after `to`.
+    fn upper_bound(&self) -> Option<DateTime<Utc>> {
+        self.to.and_then(|date| date.succ_opt()).map(local_midnight)
+    }
+
+    /// Check whether a result matches the filter.
+    pub fn matches(&self, score: &HighScore) -> bool {
+        let lang_match = self
+            .language
+            .map(|l| score.language == l)
+            .unwrap_or(true);
+        let diff_match = self
+            .difficulty
+            .map(|d| score.difficulty == d)
+            .unwrap_or(true);
+        let mode_match = self.mode.map(|m| score.mode == m).unwrap_or(true);
+        let from_match = self
+            .lower_bound()
+            .map(|from| score.timestamp >= from)
+            .unwrap_or(true);
+        let to_match = self
+            .upper_bound()
+            .map(|to| score.timestamp < to)
+            .unwrap_or(true);
+
+        lang_match && diff_match && mode_match && from_match && to_match
+    }
+}
+
+/// Start of a day in local time, as UTC.
+pub(crate) fn local_midnight(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+/// A place to persist highscores, the result history, the keystroke logs,
+/// the learning progress and the learning session history.
+pub trait Storage {
+    /// Load the highscore table.
+    fn load_highscores(&self) -> Result<Vec<HighScore>>;
+
+    /// Replace the highscore table.
+    fn save_highscores(&self, scores: &[HighScore]) -> Result<()>;
+
+    /// Check whether a result history has been started.
+    ///
+    /// Data from before the history only has a highscore table.
+    fn has_history(&self) -> Result<bool>;
+
+    /// Append a result to the history.
+    fn append_result(&self, score: &HighScore) -> Result<()>;
+
+    /// Load the results matching a filter, oldest first.
+    fn load_results(&self, filter: &ResultFilter) -> Result<Vec<HighScore>>;
+
+    /// Replace the whole history.
+    fn replace_results(&self, scores: &[HighScore]) -> Result<()>;
+
+    /// Save the keystroke log of a test under its result ID, replacing an
+    /// earlier log with the same ID.
+    fn save_keystroke_log(&self, log: &KeystrokeLog) -> Result<()>;
+
+    /// Load the keystroke log of a result, `None` if none was saved.
+    fn load_keystroke_log(&self, id: &str) -> Result<Option<KeystrokeLog>>;
+
+    /// List the result IDs of all keystroke logs, oldest first.
+    fn keystroke_log_ids(&self) -> Result<Vec<String>>;
+
+    /// Load all keystroke logs, oldest first. Logs that cannot be parsed
+    /// are skipped.
+    fn load_keystroke_logs(&self) -> Result<Vec<KeystrokeLog>>;
+
+    /// Load the spaced-repetition progress of all learning sets.
+    fn load_progress(&self) -> Result<LearningProgress>;
+
+    /// Replace the progress of one learning set, keeping the others.
+    fn save_set_progress(&self, name: &str, progress: &SetProgress) -> Result<()>;
+
+    /// Replace the progress of all learning sets.
+    fn replace_progress(&self, progress: &LearningProgress) -> Result<()>;
+
+    /// Append a finished learning session to the session history.
+    fn append_session(&self, session: &LearningSession) -> Result<()>;
+
+    /// Load all learning sessions, oldest first.
+    fn load_sessions(&self) -> Result<Vec<LearningSession>>;
+
+    /// Replace the whole learning session history.
+    fn replace_sessions(&self, sessions: &[LearningSession]) -> Result<()>;
+
+    /// Lock the storage against other processes for a read-modify-write
+    /// cycle. The lock is released when the returned guard is dropped.
+    fn lock(&self) -> Result<FileLock>;
+
+    /// Upgrade stored data from an older schema version in place.
+    ///
+    /// Returns whether anything was migrated. Data from a newer version is
+    /// an error and left untouched.
+    fn migrate(&self) -> Result<bool>;
+}
+
+/// Open the storage backend selected in the configuration.
+///
+/// Data in an older schema version is migrated first. A new SQLite database
+/// starts with the data of the JSON files, so switching the backend keeps
+/// existing scores. If that import fails, it is tried again the next time
+/// until it succeeds.
+///
+/// # Errors
+///
+/// Returns an error if the backend is unknown or cannot be opened.
+pub fn open(config: &Config) -> Result<Box<dyn Storage>> {
+    let backend: StorageBackend = config
+        .paths
+        .storage
+        .parse()
+        .map_err(UtilError::ConfigError)?;
+    let json = JsonStorage::new(&config.paths.highscore_file);
+
+    match backend {
+        StorageBackend::Json => {
+            json.migrate()?;
+            Ok(Box::new(json))
+        }
+        StorageBackend::Sqlite => {
+            let sqlite = SqliteStorage::open(&config.paths.database_file)?;
+            sqlite.migrate()?;
+            if sqlite.json_import_pending()? {
+                json.migrate()?;
+                copy(&json, &sqlite)?;
+                sqlite.finish_json_import()?;
+            }
+            Ok(Box::new(sqlite))
+        }
+    }
+}
+
+/// Copy all highscores, results, keystroke logs, learning progress and
+/// learning sessions from one storage to another.
+///
+/// # Errors
+///
+/// Returns an error if reading or writing fails.
+pub fn copy(from: &dyn Storage, to: &dyn Storage) -> Result<()> {
+    to.save_highscores(&from.load_highscores()?)?;
+    if from.has_history()? {
+        to.replace_results(&from.load_results(&ResultFilter::default())?)?;
+    }
+    for log in from.load_keystroke_logs()? {
+        to.save_keystroke_log(&log)?;
+    }
+    to.replace_progress(&from.load_progress()?)?;
+    to.replace_sessions(&from.load_sessions()?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::TestMode;
+    use crate::modules::learning::models::SessionStats;
+    use crate::modules::learning::progress::CardProgress;
+    use crate::modules::learning::session::Movement;
+    use crate::modules::learning::spaced_rep::ItemState;
+    use crate::modules::typing::{schema, ResultHistory};
+    use tempfile::TempDir;
+
+    fn score(name: &str, language: Language, timestamp: &str) -> HighScore {
+        HighScore {
+            language,
+            timestamp: schema::parse_legacy_timestamp(timestamp).unwrap(),
+            wpm_series: vec![40.0, 60.0],
+            consistency: 20.0,
+            burst_wpm: 60.0,
+            ..HighScore::sample(name, 50.0)
+        }
+    }
+
+    fn sample() -> Vec<HighScore> {
+        vec![
+            score("a", Language::English, "2024-01-01 09:00:00"),
+            score("b", Language::German, "2024-01-02 10:00:00"),
+            score("c", Language::English, "2024-01-03 23:59:59"),
+            score("d", Language::English, "2024-01-04 00:00:00"),
+        ]
+    }
+
+    fn check_backend(storage: &dyn Storage) {
+        assert!(!storage.has_history().unwrap());
+        assert!(storage.load_highscores().unwrap().is_empty());
+
+        for score in sample() {
+            storage.append_result(&score).unwrap();
+        }
+        assert!(storage.has_history().unwrap());
+
+        let all = storage.load_results(&ResultFilter::default()).unwrap();
+        let names: Vec<&str> = all.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        assert_eq!(all[0].wpm_series, vec![40.0, 60.0]);
+        assert_eq!(all[0].duration_secs, Some(30.0));
+
+        let filter = ResultFilter::new(Some(Language::English), Some(Difficulty::Medium)).between(
+            NaiveDate::from_ymd_opt(2024, 1, 2),
+            NaiveDate::from_ymd_opt(2024, 1, 3),
+        );
+        let filtered = storage.load_results(&filter).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].name, "c");
+
+        let hard = ResultFilter::new(None, Some(Difficulty::Hard));
+        assert!(storage.load_results(&hard).unwrap().is_empty());
+        let timed = ResultFilter::default().with_mode(Some(TestMode::Time));
+        assert!(storage.load_results(&timed).unwrap().is_empty());
+        let words = ResultFilter::default().with_mode(Some(TestMode::Words));
+        assert_eq!(storage.load_results(&words).unwrap().len(), 4);
+
+        storage.replace_results(&all[2..]).unwrap();
+        assert_eq!(storage.load_results(&ResultFilter::default()).unwrap().len(), 2);
+
+        storage.save_highscores(&all[..1]).unwrap();
+        assert_eq!(storage.load_highscores().unwrap()[0].name, "a");
+
+        // Keystroke logs
+        assert!(storage.load_keystroke_log("later").unwrap().is_none());
+        let mut later = KeystrokeLog::new();
+        later.id = "later".to_string();
+        later.timestamp = Some(all[1].timestamp);
+        later.target_text = "ab".to_string();
+        later.record_char(std::time::Duration::from_millis(0), 'a', Some('a'), 0, false);
+        let earlier_log = KeystrokeLog {
+            id: "earlier".to_string(),
+            timestamp: Some(all[0].timestamp),
+            ..later.clone()
+        };
+        storage.save_keystroke_log(&later).unwrap();
+        storage.save_keystroke_log(&earlier_log).unwrap();
+        later.record_char(std::time::Duration::from_millis(150), 'b', Some('b'), 1, false);
+        storage.save_keystroke_log(&later).unwrap();
+        assert_eq!(storage.load_keystroke_log("later").unwrap(), Some(later.clone()));
+        assert_eq!(storage.keystroke_log_ids().unwrap(), ["earlier", "later"]);
+        assert_eq!(storage.load_keystroke_logs().unwrap(), vec![earlier_log, later]);
+        assert!(storage.save_keystroke_log(&KeystrokeLog::new()).is_err());
+
+        assert!(storage.load_progress().unwrap().sets.is_empty());
+        let mut set = SetProgress::default();
+        set.cards.insert(
+            "france".to_string(),
+            CardProgress {
+                fingerprint: "1234".to_string(),
+                state: ItemState {
+                    box_index: 2,
+                    repetitions: 3,
+                    interval_days: 6,
+                    ease: Some(2.36),
+                    stability: Some(4.2),
+                    difficulty: Some(5.5),
+                    last_reviewed: Some(all[0].timestamp),
+                    due: Some(all[1].timestamp),
+                    ..Default::default()
+                },
+            },
+        );
+        storage.save_set_progress("Capitals", &set).unwrap();
+        storage.save_set_progress("Rivers", &SetProgress::default()).unwrap();
+        set.cards.get_mut("france").unwrap().state.due = None;
+        storage.save_set_progress("Capitals", &set).unwrap();
+        let progress = storage.load_progress().unwrap();
+        assert_eq!(progress.sets["Capitals"], set);
+        assert_eq!(progress.card_count(), 1);
+
+        storage.replace_progress(&LearningProgress::default()).unwrap();
+        assert_eq!(storage.load_progress().unwrap().card_count(), 0);
+
+        // Learning sessions
+        assert!(storage.load_sessions().unwrap().is_empty());
+        let session = LearningSession {
+            set_name: "Capitals".to_string(),
+            started: all[0].timestamp,
+            duration_secs: 95.5,
+            stats: SessionStats {
+                total_reviewed: 4,
+                correct: 3,
+                incorrect: 1,
+                user_overrides: 1,
+            },
+            quiz_total: 1,
+            quiz_correct: 1,
+            algorithm: Some(crate::core::SchedulerAlgorithm::Fsrs),
+            movement: Movement {
+                moved_up: 2,
+                moved_down: 1,
+                unchanged: 0,
+            },
+            mastery: Some(25.0),
+            hardest: vec!["France".to_string()],
+        };
+        let earlier = LearningSession {
+            started: all[1].timestamp,
+            algorithm: None,
+            mastery: None,
+            hardest: Vec::new(),
+            ..session.clone()
+        };
+        storage.append_session(&session).unwrap();
+        storage.append_session(&earlier).unwrap();
+        let sessions = storage.load_sessions().unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.contains(&session) && sessions.contains(&earlier));
+
+        let remaining = vec![earlier];
+        storage.replace_sessions(&remaining).unwrap();
+        assert_eq!(storage.load_sessions().unwrap(), remaining);
+    }
+
+    #[test]
+    fn test_json_backend() {
+        let dir = TempDir::new().unwrap();
+        check_backend(&JsonStorage::new(dir.path().join("highscores.json")));
+    }
+
+    #[test]
+    fn test_sqlite_backend() {
+        let dir = TempDir::new().unwrap();
+        check_backend(&SqliteStorage::open(dir.path().join("rut.sqlite3")).unwrap());
+    }
+
+    #[test]
+    fn test_open_sqlite_imports_json() {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.paths.highscore_file = dir.path().join("highscores.json");
+        config.paths.database_file = dir.path().join("rut.sqlite3");
+
+        let json = open(&config).unwrap();
+        json.save_highscores(&sample()[..2]).unwrap();
+        json.append_result(&sample()[0]).unwrap();
+        let log = KeystrokeLog {
+            id: "20240101120000000-0001".to_string(),
+            ..KeystrokeLog::default()
+        };
+        json.save_keystroke_log(&log).unwrap();
+
+        // An import that fails partway is tried again on the next start
+        let history = ResultHistory::for_highscore_file(&config.paths.highscore_file);
+        let contents = std::fs::read_to_string(history.path()).unwrap();
+        let damaged = contents.clone()
+            + concat!(
+                r#"{"name": "x", "wpm": 40.0, "accuracy": 95.0, "language": "en", "#,
+                r#""difficulty": "Extreme", "timestamp": "2024-01-02 12:00:00"}"#,
+                "\n"
+            );
+        std::fs::write(history.path(), damaged).unwrap();
+        config.paths.storage = "sqlite".to_string();
+        assert!(open(&config).is_err());
+        assert!(config.paths.database_file.exists());
+        std::fs::write(history.path(), contents).unwrap();
+
+        let sqlite = open(&config).unwrap();
+        assert_eq!(sqlite.load_highscores().unwrap().len(), 2);
+        assert_eq!(sqlite.load_results(&ResultFilter::default()).unwrap().len(), 1);
+        assert_eq!(sqlite.load_keystroke_log(&log.id).unwrap(), Some(log));
+
+        // Only a new database is filled from the JSON files
+        sqlite.replace_results(&[]).unwrap();
+        let sqlite = open(&config).unwrap();
+        assert!(sqlite.load_results(&ResultFilter::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_migrates_json() {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.paths.highscore_file = dir.path().join("highscores.json");
+        std::fs::write(
+            &config.paths.highscore_file,
+            r#"[{"name": "Old", "wpm": 42.0, "accuracy": 97.0, "language": "de",
+                "difficulty": "Schwer/Hard", "timestamp": "2024-01-01 12:00:00"}]"#,
+        )
+        .unwrap();
+
+        let storage = open(&config).unwrap();
+        let contents = std::fs::read(&config.paths.highscore_file).unwrap();
+        assert_eq!(schema::decode_table(&contents).unwrap().0, schema::SCHEMA_VERSION);
+        assert!(crate::utils::fs::backup_path(&config.paths.highscore_file).exists());
+
+        let scores = storage.load_highscores().unwrap();
+        assert_eq!(scores[0].language, Language::German);
+        assert_eq!(scores[0].difficulty, Difficulty::Hard);
+        assert!(!storage.migrate().unwrap());
+    }
+}
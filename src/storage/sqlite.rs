@@ -0,0 +1,711 @@
+//! Embedded SQLite storage.
+//!
+//! Highscores and results live in two tables with the same columns. The
+//! results table is indexed by language, difficulty and timestamp, so
+//! filtered queries stay fast with thousands of runs. Keystroke logs are
+//! stored as JSON, one row per test indexed by timestamp. The learning
+//! progress has one row per card. Languages and
+//! difficulties are stored by code and timestamps as RFC 3339 in UTC; the
+//! schema version is kept in `PRAGMA user_version`.
+
+use crate::core::{Result, TestMode, UtilError};
+use crate::modules::learning::history::LearningSession;
+use crate::modules::learning::models::SessionStats;
+use crate::modules::learning::progress::{CardProgress, LearningProgress, SetProgress};
+use crate::modules::learning::session::Movement;
+use crate::modules::learning::spaced_rep::ItemState;
+use crate::modules::typing::schema::{self, SCHEMA_VERSION};
+use crate::modules::typing::{HighScore, KeystrokeLog};
+use crate::storage::{ResultFilter, Storage};
+use crate::utils::fs::FileLock;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::types::Type;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long to wait for another process that is writing to the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Columns shared by the `results` and `highscores` tables.
+const SCORE_COLUMNS: &str = "
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT,
+    name TEXT NOT NULL,
+    wpm REAL NOT NULL,
+    raw_wpm REAL NOT NULL,
+    accuracy REAL NOT NULL,
+    language TEXT NOT NULL,
+    difficulty TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    duration_secs REAL,
+    mode TEXT NOT NULL,
+    time_limit INTEGER,
+    wpm_series TEXT NOT NULL,
+    consistency REAL NOT NULL,
+    burst_wpm REAL NOT NULL
+";
+
+/// Indexes for filtered queries, the metadata, keystroke log and learning
+/// tables.
+const INDEXES: &str = "
+    CREATE INDEX IF NOT EXISTS results_category
+        ON results (language, difficulty, timestamp);
+    CREATE INDEX IF NOT EXISTS results_timestamp ON results (timestamp);
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS keystroke_logs (
+        id TEXT PRIMARY KEY,
+        timestamp TEXT,
+        log TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS keystroke_logs_timestamp
+        ON keystroke_logs (timestamp, id);
+    CREATE TABLE IF NOT EXISTS learning_progress (
+        set_name TEXT NOT NULL,
+        card_key TEXT NOT NULL,
+        box_index INTEGER NOT NULL,
+        fingerprint TEXT NOT NULL,
+        last_reviewed TEXT,
+        due TEXT,
+        repetitions INTEGER NOT NULL DEFAULT 0,
+        lapses INTEGER NOT NULL DEFAULT 0,
+        interval_days INTEGER NOT NULL DEFAULT 0,
+        ease REAL,
+        stability REAL,
+        difficulty REAL,
+        PRIMARY KEY (set_name, card_key)
+    );
+    CREATE TABLE IF NOT EXISTS learning_sessions (
+        set_name TEXT NOT NULL,
+        started TEXT NOT NULL,
+        duration_secs REAL NOT NULL,
+        total_reviewed INTEGER NOT NULL,
+        correct INTEGER NOT NULL,
+        incorrect INTEGER NOT NULL,
+        user_overrides INTEGER NOT NULL,
+        quiz_total INTEGER NOT NULL,
+        quiz_correct INTEGER NOT NULL,
+        algorithm TEXT,
+        moved_up INTEGER NOT NULL,
+        moved_down INTEGER NOT NULL,
+        unchanged INTEGER NOT NULL,
+        mastery REAL,
+        hardest TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS learning_sessions_set
+        ON learning_sessions (set_name, started);
+";
+
+/// Learning progress columns added after the table was introduced, with
+/// their definitions.
+const ADDED_PROGRESS_COLUMNS: [(&str, &str); 7] = [
+    ("due", "TEXT"),
+    ("repetitions", "INTEGER NOT NULL DEFAULT 0"),
+    ("lapses", "INTEGER NOT NULL DEFAULT 0"),
+    ("interval_days", "INTEGER NOT NULL DEFAULT 0"),
+    ("ease", "REAL"),
+    ("stability", "REAL"),
+    ("difficulty", "REAL"),
+];
+
+/// Columns of a card's progress, in the order used by
+/// [`insert_set_progress`] and [`read_card_progress`].
+const PROGRESS_COLUMNS: &str = "set_name, card_key, box_index, fingerprint, last_reviewed, due, \
+                                repetitions, lapses, interval_days, ease, stability, difficulty";
+
+/// Columns of a score, in the order used by [`insert`] and [`read_score`].
+const COLUMNS: &str = "id, name, wpm, raw_wpm, accuracy, language, difficulty, timestamp, \
+                       duration_secs, mode, time_limit, wpm_series, consistency, burst_wpm";
+
+/// Columns of a learning session, in the order used by [`insert_session`]
+/// and [`read_session`].
+const SESSION_COLUMNS: &str = "set_name, started, duration_secs, total_reviewed, correct, incorrect, \
+                               user_overrides, quiz_total, quiz_correct, algorithm, moved_up, \
+                               moved_down, unchanged, mastery, hardest";
+
+/// Storage in an SQLite database file.
+pub struct SqliteStorage {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    /// Open (or create) a database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or is not a database.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(&path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let existing: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'results'",
+            [],
+            |row| row.get(0),
+        )?;
+        for table in ["results", "highscores"] {
+            conn.execute_batch(&format!(
+                "CREATE TABLE IF NOT EXISTS {} ({});",
+                table, SCORE_COLUMNS
+            ))?;
+        }
+        conn.execute_batch(INDEXES)?;
+        // Older learning progress tables lack the due dates and the state of
+        // the SM-2 and FSRS schedulers
+        for (column, definition) in ADDED_PROGRESS_COLUMNS {
+            let exists: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('learning_progress') WHERE name = ?1",
+                params![column],
+                |row| row.get(0),
+            )?;
+            if exists == 0 {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE learning_progress ADD COLUMN {} {}",
+                    column, definition
+                ))?;
+            }
+        }
+        if existing == 0 {
+            set_schema_version(&conn, SCHEMA_VERSION)?;
+            conn.execute(
+                "INSERT OR IGNORE INTO meta (key, value) VALUES ('json_import', 'pending')",
+                [],
+            )?;
+        }
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            conn,
+        })
+    }
+
+    /// Check whether the database was created but the data of the JSON
+    /// files has not been imported into it yet.
+    pub fn json_import_pending(&self) -> Result<bool> {
+        let pending: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM meta WHERE key = 'json_import' AND value = 'pending'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(pending > 0)
+    }
+
+    /// Record that the data of the JSON files has been imported.
+    pub fn finish_json_import(&self) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('json_import', 'done')",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn mark_history_started(&self) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO meta (key, value) VALUES ('history_started', '1')",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn load(&self, table: &str, filter: &ResultFilter) -> Result<Vec<HighScore>> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(language) = filter.language {
+            conditions.push("language = ?");
+            values.push(language.code().to_string());
+        }
+        if let Some(difficulty) = filter.difficulty {
+            conditions.push("difficulty = ?");
+            values.push(difficulty.code().to_string());
+        }
+        if let Some(mode) = filter.mode {
+            conditions.push("mode = ?");
+            values.push(mode.code().to_string());
+        }
+        if let Some(from) = filter.lower_bound() {
+            conditions.push("timestamp >= ?");
+            values.push(format_timestamp(&from));
+        }
+        if let Some(to) = filter.upper_bound() {
+            conditions.push("timestamp < ?");
+            values.push(format_timestamp(&to));
+        }
+
+        let mut sql = format!("SELECT {} FROM {}", COLUMNS, table);
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY seq");
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let scores = stmt
+            .query_map(params_from_iter(values), read_score)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(scores)
+    }
+
+    fn replace(&self, table: &str, scores: &[HighScore]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(&format!("DELETE FROM {}", table), [])?;
+        for score in scores {
+            insert(&tx, table, score)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn schema_version(&self) -> Result<u32> {
+        let version: i64 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(version as u32)
+    }
+
+    /// Convert the language, difficulty and timestamp columns of a version 1
+    /// table (display names and local time) to the current format.
+    fn migrate_table(conn: &Connection, table: &str) -> Result<()> {
+        let rows = {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT seq, language, difficulty, timestamp FROM {}",
+                table
+            ))?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+
+        for (seq, language, difficulty, timestamp) in rows {
+            conn.execute(
+                &format!(
+                    "UPDATE {} SET language = ?1, difficulty = ?2, timestamp = ?3 WHERE seq = ?4",
+                    table
+                ),
+                params![
+                    schema::parse_legacy_language(&language)?.code(),
+                    schema::parse_legacy_difficulty(&difficulty)?.code(),
+                    format_timestamp(&schema::parse_legacy_timestamp(&timestamp)?),
+                    seq,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load_highscores(&self) -> Result<Vec<HighScore>> {
+        self.load("highscores", &ResultFilter::default())
+    }
+
+    fn save_highscores(&self, scores: &[HighScore]) -> Result<()> {
+        self.replace("highscores", scores)
+    }
+
+    fn has_history(&self) -> Result<bool> {
+        let started: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM meta WHERE key = 'history_started'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(started > 0)
+    }
+
+    fn append_result(&self, score: &HighScore) -> Result<()> {
+        self.mark_history_started()?;
+        insert(&self.conn, "results", score)
+    }
+
+    fn load_results(&self, filter: &ResultFilter) -> Result<Vec<HighScore>> {
+        self.load("results", filter)
+    }
+
+    fn replace_results(&self, scores: &[HighScore]) -> Result<()> {
+        self.mark_history_started()?;
+        self.replace("results", scores)
+    }
+
+    fn save_keystroke_log(&self, log: &KeystrokeLog) -> Result<()> {
+        if log.id.is_empty() {
+            return Err(UtilError::HighscoreError(
+                "Keystroke log has no result ID".to_string(),
+            ));
+        }
+        self.conn.execute(
+            "INSERT OR REPLACE INTO keystroke_logs (id, timestamp, log) VALUES (?1, ?2, ?3)",
+            params![
+                log.id,
+                log.timestamp.as_ref().map(format_timestamp),
+                serde_json::to_string(log)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn load_keystroke_log(&self, id: &str) -> Result<Option<KeystrokeLog>> {
+        let log: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT log FROM keystroke_logs WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match log {
+            Some(log) => Ok(Some(serde_json::from_str(&log)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Logs without a timestamp (recorded before it was kept) come first.
+    fn keystroke_log_ids(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM keystroke_logs ORDER BY timestamp, id")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    fn load_keystroke_logs(&self) -> Result<Vec<KeystrokeLog>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT log FROM keystroke_logs ORDER BY timestamp, id")?;
+        let logs = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(logs
+            .iter()
+            .filter_map(|log| serde_json::from_str(log).ok())
+            .collect())
+    }
+
+    fn load_progress(&self) -> Result<LearningProgress> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM learning_progress",
+            PROGRESS_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, read_card_progress(row)?))
+        })?;
+
+        let mut progress = LearningProgress::default();
+        for row in rows {
+            let (set_name, card_key, card) = row?;
+            progress
+                .sets
+                .entry(set_name)
+                .or_default()
+                .cards
+                .insert(card_key, card);
+        }
+        Ok(progress)
+    }
+
+    fn save_set_progress(&self, name: &str, progress: &SetProgress) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM learning_progress WHERE set_name = ?1", params![name])?;
+        insert_set_progress(&tx, name, progress)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn replace_progress(&self, progress: &LearningProgress) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM learning_progress", [])?;
+        for (name, set) in &progress.sets {
+            insert_set_progress(&tx, name, set)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn append_session(&self, session: &LearningSession) -> Result<()> {
+        insert_session(&self.conn, session)
+    }
+
+    fn load_sessions(&self) -> Result<Vec<LearningSession>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM learning_sessions ORDER BY started, rowid",
+            SESSION_COLUMNS
+        ))?;
+        let sessions = stmt
+            .query_map([], read_session)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(sessions)
+    }
+
+    fn replace_sessions(&self, sessions: &[LearningSession]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM learning_sessions", [])?;
+        for session in sessions {
+            insert_session(&tx, session)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Single statements are atomic in SQLite; the lock keeps the
+    /// read-modify-write cycles of several processes apart.
+    fn lock(&self) -> Result<FileLock> {
+        FileLock::acquire(&self.path)
+    }
+
+    /// Upgrade all rows in one transaction; on failure the database is
+    /// left unchanged.
+    fn migrate(&self) -> Result<bool> {
+        let _lock = self.lock()?;
+        let version = self.schema_version()?;
+        if version > SCHEMA_VERSION {
+            return Err(UtilError::HighscoreError(format!(
+                "Database was written by a newer version (schema {}, supported up to {})",
+                version, SCHEMA_VERSION
+            )));
+        }
+        if version == SCHEMA_VERSION {
+            return Ok(false);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        for table in ["results", "highscores"] {
+            Self::migrate_table(&tx, table)?;
+        }
+        set_schema_version(&tx, SCHEMA_VERSION)?;
+        tx.commit()?;
+        Ok(true)
+    }
+}
+
+fn insert_set_progress(conn: &Connection, name: &str, progress: &SetProgress) -> Result<()> {
+    let mut stmt = conn.prepare(&format!(
+        "INSERT INTO learning_progress ({}) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        PROGRESS_COLUMNS
+    ))?;
+    for (key, card) in &progress.cards {
+        let state = &card.state;
+        stmt.execute(params![
+            name,
+            key,
+            state.box_index as i64,
+            card.fingerprint,
+            state.last_reviewed.as_ref().map(format_timestamp),
+            state.due.as_ref().map(format_timestamp),
+            state.repetitions,
+            state.lapses,
+            state.interval_days,
+            state.ease,
+            state.stability,
+            state.difficulty,
+        ])?;
+    }
+    Ok(())
+}
+
+fn insert_session(conn: &Connection, session: &LearningSession) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO learning_sessions ({}) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            SESSION_COLUMNS
+        ),
+        params![
+            session.set_name,
+            format_timestamp(&session.started),
+            session.duration_secs,
+            session.stats.total_reviewed as i64,
+            session.stats.correct as i64,
+            session.stats.incorrect as i64,
+            session.stats.user_overrides as i64,
+            session.quiz_total as i64,
+            session.quiz_correct as i64,
+            session.algorithm.map(|a| a.code()),
+            session.movement.moved_up as i64,
+            session.movement.moved_down as i64,
+            session.movement.unchanged as i64,
+            session.mastery,
+            serde_json::to_string(&session.hardest)?,
+        ],
+    )?;
+    Ok(())
+}
+
+fn set_schema_version(conn: &Connection, version: u32) -> Result<()> {
+    conn.execute_batch(&format!("PRAGMA user_version = {}", version))?;
+    Ok(())
+}
+
+/// Timestamps are stored with whole seconds and a `Z` suffix, so they sort
+/// chronologically as text.
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Read a text column with one of the [`schema`] parsers.
+fn parse_column<T>(row: &Row, idx: usize, parse: fn(&str) -> Result<T>) -> rusqlite::Result<T> {
+    let value: String = row.get(idx)?;
+    parse(&value).map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+fn insert(conn: &Connection, table: &str, score: &HighScore) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO {} ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            table, COLUMNS
+        ),
+        params![
+            score.id,
+            score.name,
+            score.wpm,
+            score.raw_wpm,
+            score.accuracy,
+            score.language.code(),
+            score.difficulty.code(),
+            format_timestamp(&score.timestamp),
+            score.duration_secs,
+            score.mode.code(),
+            score.time_limit.map(|secs| secs as i64),
+            serde_json::to_string(&score.wpm_series)?,
+            score.consistency,
+            score.burst_wpm,
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_score(row: &Row) -> rusqlite::Result<HighScore> {
+    let mode: String = row.get(9)?;
+    let time_limit: Option<i64> = row.get(10)?;
+    let wpm_series: String = row.get(11)?;
+
+    Ok(HighScore {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        wpm: row.get(2)?,
+        raw_wpm: row.get(3)?,
+        accuracy: row.get(4)?,
+        language: parse_column(row, 5, schema::parse_legacy_language)?,
+        difficulty: parse_column(row, 6, schema::parse_legacy_difficulty)?,
+        timestamp: parse_column(row, 7, schema::parse_legacy_timestamp)?,
+        duration_secs: row.get(8)?,
+        mode: mode.parse().unwrap_or(TestMode::Words),
+        time_limit: time_limit.map(|secs| secs as u64),
+        wpm_series: serde_json::from_str(&wpm_series).unwrap_or_default(),
+        consistency: row.get(12)?,
+        burst_wpm: row.get(13)?,
+    })
+}
+
+fn read_card_progress(row: &Row) -> rusqlite::Result<CardProgress> {
+    let box_index: i64 = row.get(2)?;
+    Ok(CardProgress {
+        fingerprint: row.get(3)?,
+        state: ItemState {
+            box_index: box_index.max(0) as usize,
+            last_reviewed: parse_optional_timestamp(row, 4)?,
+            due: parse_optional_timestamp(row, 5)?,
+            repetitions: row.get(6)?,
+            lapses: row.get(7)?,
+            interval_days: row.get(8)?,
+            ease: row.get(9)?,
+            stability: row.get(10)?,
+            difficulty: row.get(11)?,
+        },
+    })
+}
+
+fn read_session(row: &Row) -> rusqlite::Result<LearningSession> {
+    let count = |idx: usize| -> rusqlite::Result<usize> { Ok(row.get::<_, i64>(idx)?.max(0) as usize) };
+    let algorithm: Option<String> = row.get(9)?;
+    let hardest: String = row.get(14)?;
+    Ok(LearningSession {
+        set_name: row.get(0)?,
+        started: parse_column(row, 1, schema::parse_legacy_timestamp)?,
+        duration_secs: row.get(2)?,
+        stats: SessionStats {
+            total_reviewed: count(3)?,
+            correct: count(4)?,
+            incorrect: count(5)?,
+            user_overrides: count(6)?,
+        },
+        quiz_total: count(7)?,
+        quiz_correct: count(8)?,
+        algorithm: algorithm.and_then(|a| a.parse().ok()),
+        movement: Movement {
+            moved_up: count(10)?,
+            moved_down: count(11)?,
+            unchanged: count(12)?,
+        },
+        mastery: row.get(13)?,
+        hardest: serde_json::from_str(&hardest).unwrap_or_default(),
+    })
+}
+
+fn parse_optional_timestamp(row: &Row, idx: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    match row.get::<_, Option<String>>(idx)? {
+        Some(_) => parse_column(row, idx, schema::parse_legacy_timestamp).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Difficulty;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_migrate_first_schema() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("rut.sqlite3");
+
+        // A database written before the schema was versioned
+        {
+            let storage = SqliteStorage::open(&path).unwrap();
+            set_schema_version(&storage.conn, 0).unwrap();
+            storage
+                .conn
+                .execute(
+                    &format!(
+                        "INSERT INTO highscores ({}) VALUES ('x', 'Old', 42.0, 42.0, 97.0, 'de', \
+                         'Schwer/Hard', '2024-01-01 12:00:00', NULL, 'words', NULL, '[]', 0.0, 0.0)",
+                        COLUMNS
+                    ),
+                    [],
+                )
+                .unwrap();
+        }
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert!(storage.migrate().unwrap());
+        assert!(!storage.migrate().unwrap());
+        assert_eq!(storage.schema_version().unwrap(), SCHEMA_VERSION);
+
+        let scores = storage.load_highscores().unwrap();
+        assert_eq!(scores[0].difficulty, Difficulty::Hard);
+        assert_eq!(
+            scores[0].timestamp,
+            schema::parse_legacy_timestamp("2024-01-01 12:00:00").unwrap()
+        );
+        let stored: String = storage
+            .conn
+            .query_row("SELECT difficulty FROM highscores", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "hard");
+    }
+}
@@ -1,6 +1,14 @@
-use crate::core::{Config, Difficulty, Language};
-use crate::modules::learning::{LearningSet, MatchResult};
-use crate::modules::typing::{HighScoreManager, TestResult, WordLoader};
+use crate::core::{CodeLanguage, Config, Difficulty, Language, ProfileStore, TestMode};
+use crate::modules::learning::{
+    Card, HardItem, LearningSession, LearningSet, MatchResult, Movement, QuizQuestion, Scheduler,
+    SessionItem, SessionStats, SetProgress,
+};
+use crate::modules::typing::{
+    CustomSource, Dashboard, IndentStyle, KeystrokeAnalysis, KeystrokeLog, RunFeedback, StatsPeriod,
+    TestResult,
+};
+use crate::storage::Storage;
+use super::heatmap::HeatmapMetric;
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -8,11 +16,13 @@ pub enum CurrentScreen {
     Menu,
     TypingTest,
     TypingResults,
+    Replay,
     LearningSelect,
     LearningMode,
     LearningResults,
     Statistics,
     Settings,
+    ProfileSelect,
     Exiting,
 }
 
@@ -36,6 +46,35 @@ pub struct App {
 
     // Statistics State
     pub statistics_state: StatisticsState,
+
+    // Custom text source (loaded once, stdin cannot be read twice)
+    pub custom_source: Option<CustomSource>,
+
+    // Replay State
+    pub replay_state: Option<ReplayState>,
+
+    // Statistic the keyboard heatmaps are coloured by
+    pub heatmap_metric: HeatmapMetric,
+
+    // Player profiles
+    pub profiles: ProfileStore,
+    pub profile_state: ProfileState,
+}
+
+/// What the name typed on the profile screen is for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileEdit {
+    Create,
+    Rename,
+}
+
+#[derive(Default)]
+pub struct ProfileState {
+    pub cursor: usize,
+    /// Name being typed, if a profile is being created or renamed
+    pub edit: Option<ProfileEdit>,
+    pub input: String,
+    pub error: Option<String>,
 }
 
 pub struct FileExplorerState {
@@ -56,23 +95,43 @@ impl Default for FileExplorerState {
     }
 }
 
+/// Page of the statistics screen.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StatsView {
+    /// Charts of the filtered results
+    #[default]
+    Dashboard,
+    /// Key heatmap and highscore table
+    Details,
+}
+
+#[derive(Default)]
 pub struct StatisticsState {
+    pub view: StatsView,
+    /// Date range of the dashboard
+    pub period: StatsPeriod,
+    /// Only results of this mode (all modes if `None`)
+    pub mode: Option<TestMode>,
     pub highscores: Vec<crate::modules::typing::HighScore>,
+    /// Summary of the results in the selected period and mode
     pub stats_summary: Option<crate::modules::typing::HighScoreStatistics>,
-}
-
-impl Default for StatisticsState {
-    fn default() -> Self {
-        Self {
-            highscores: Vec::new(),
-            stats_summary: None,
-        }
-    }
+    pub dashboard: Dashboard,
+    /// Key statistics accumulated over all recorded tests
+    pub key_analysis: KeystrokeAnalysis,
 }
 
 pub struct TypingState {
     pub language: Language,
     pub difficulty: Difficulty,
+    pub mode: TestMode,
+    /// Time limit in seconds (only used in timed mode)
+    pub time_limit: u64,
+    /// Programming language (only used in code mode)
+    pub code_language: CodeLanguage,
+    /// Indentation inserted for Tab (only used in code mode)
+    pub indent: IndentStyle,
+    /// Insert the next line's indentation after Enter (only used in code mode)
+    pub auto_indent: bool,
     pub target_text: String,
     pub typed_text: String,
     pub start_time: Option<Instant>,
@@ -80,6 +139,16 @@ pub struct TypingState {
     pub error_count: usize,
     pub is_active: bool,
     pub result: Option<TestResult>,
+    /// Every keystroke of the current test
+    pub keystrokes: KeystrokeLog,
+    /// Result ID of the finished test (for replays)
+    pub result_id: Option<String>,
+    /// Key statistics of the finished test
+    pub analysis: Option<KeystrokeAnalysis>,
+    /// Why the finished test was not saved as a highscore
+    pub score_rejection: Option<String>,
+    /// How the finished test compares to the player's history
+    pub feedback: Option<RunFeedback>,
 }
 
 impl Default for TypingState {
@@ -87,6 +156,11 @@ impl Default for TypingState {
         Self {
             language: Language::English,
             difficulty: Difficulty::Medium,
+            mode: TestMode::Words,
+            time_limit: 30,
+            code_language: CodeLanguage::Rust,
+            indent: IndentStyle::Spaces(4),
+            auto_indent: true,
             target_text: String::new(),
             typed_text: String::new(),
             start_time: None,
@@ -94,36 +168,216 @@ impl Default for TypingState {
             error_count: 0,
             is_active: false,
             result: None,
+            keystrokes: KeystrokeLog::new(),
+            result_id: None,
+            analysis: None,
+            score_rejection: None,
+            feedback: None,
+        }
+    }
+}
+
+impl TypingState {
+    /// Time elapsed since the first keystroke.
+    pub fn elapsed(&self) -> Duration {
+        match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => end.duration_since(start),
+            (Some(start), None) => start.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Remaining time in a timed test, or `None` for word-count tests.
+    pub fn remaining_time(&self) -> Option<Duration> {
+        match self.mode {
+            TestMode::Words | TestMode::Code | TestMode::Adaptive => None,
+            TestMode::Time => {
+                Some(Duration::from_secs(self.time_limit).saturating_sub(self.elapsed()))
+            }
+        }
+    }
+}
+
+/// Playback of a recorded typing test.
+pub struct ReplayState {
+    pub log: KeystrokeLog,
+    /// Playback speed multiplier (1.0 = real time)
+    pub speed: f64,
+    /// Position in the recording
+    pub position: Duration,
+    /// Last time the position was advanced (`None` while paused)
+    pub last_tick: Option<Instant>,
+}
+
+impl ReplayState {
+    pub fn new(log: KeystrokeLog, speed: f64) -> Self {
+        Self {
+            log,
+            speed,
+            position: Duration::ZERO,
+            last_tick: Some(Instant::now()),
         }
     }
+
+    /// Advance the playback position by the real time passed since the last tick.
+    pub fn tick(&mut self) {
+        if let Some(last) = self.last_tick {
+            let now = Instant::now();
+            self.position += now.duration_since(last).mul_f64(self.speed);
+            self.last_tick = Some(now);
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.last_tick = match self.last_tick {
+            Some(_) => None,
+            None => Some(Instant::now()),
+        };
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.log.duration()
+    }
 }
 
+#[derive(Default)]
 pub struct LearningState {
     pub set: Option<LearningSet>,
+    /// Cards and questions in the order they are asked
+    pub order: Vec<SessionItem>,
+    /// Position in `order`
     pub current_card_index: usize,
     pub user_input: String,
     pub show_back: bool,
     pub match_result: Option<MatchResult>,
-    pub correct_count: usize,
-    pub total_count: usize,
+    /// Whether the match of the current answer was decided by the user
+    pub overridden: bool,
+    pub stats: SessionStats,
+    pub started: Option<Instant>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Items answered wrong or graded hard, in session order
+    pub hard_items: Vec<HardItem>,
+
+    // Multiple-choice questions
+    /// Shuffled options of the current question
+    pub options: Vec<String>,
+    /// Highlighted option
+    pub selected_option: usize,
+    /// Option the answer was given with
+    pub chosen_option: Option<usize>,
+    pub quiz_correct: usize,
+    pub quiz_total: usize,
+
+    // Spaced repetition (None if disabled)
+    pub scheduler: Option<Box<dyn Scheduler>>,
+    pub storage: Option<Box<dyn Storage>>,
+    pub progress: SetProgress,
+    pub card_keys: Vec<String>,
+    pub movement: Movement,
+    /// Cards per Leitner box and mastery when the session started
+    pub box_counts_before: Vec<usize>,
+    pub mastery_before: Option<f64>,
+    pub error: Option<String>,
+
+    // Results
+    /// The finished session
+    pub session: Option<LearningSession>,
+    /// Sessions of the set, oldest first, including the finished one
+    pub set_history: Vec<LearningSession>,
 }
 
-impl Default for LearningState {
-    fn default() -> Self {
-        Self {
-            set: None,
-            current_card_index: 0,
-            user_input: String::new(),
-            show_back: false,
-            match_result: None,
-            correct_count: 0,
-            total_count: 0,
+impl LearningState {
+    /// The card or question being asked.
+    pub fn current_item(&self) -> Option<SessionItem> {
+        self.order.get(self.current_card_index).copied()
+    }
+
+    /// The card being asked and its index in the set.
+    pub fn current_card(&self) -> Option<(usize, &Card)> {
+        let SessionItem::Card(index) = self.current_item()? else {
+            return None;
+        };
+        let card = self.set.as_ref()?.cards.get(index)?;
+        Some((index, card))
+    }
+
+    /// The question being asked and its index in the set.
+    pub fn current_question(&self) -> Option<(usize, &QuizQuestion)> {
+        let SessionItem::Question(index) = self.current_item()? else {
+            return None;
+        };
+        let question = self.set.as_ref()?.questions.get(index)?;
+        Some((index, question))
+    }
+
+    /// Whether the current item is answered by choosing an option.
+    pub fn is_multiple_choice(&self) -> bool {
+        self.current_question()
+            .is_some_and(|(_, question)| question.is_multiple_choice())
+    }
+
+    /// Prompt, correct answer and explanation of the current item.
+    pub fn current_prompt(&self) -> Option<(&str, &str, Option<&str>)> {
+        if let Some((_, card)) = self.current_card() {
+            return Some((&card.front, &card.back, card.explanation.as_deref()));
+        }
+        let (_, question) = self.current_question()?;
+        Some((
+            &question.question,
+            &question.correct_answer,
+            question.explanation.as_deref(),
+        ))
+    }
+
+    /// Move on to the next item and shuffle its options if it is a
+    /// multiple-choice question.
+    pub fn advance(&mut self) {
+        self.current_card_index += 1;
+        self.prepare_item();
+    }
+
+    /// Reset the answer for the current item.
+    pub fn prepare_item(&mut self) {
+        self.user_input.clear();
+        self.show_back = false;
+        self.match_result = None;
+        self.overridden = false;
+        self.selected_option = 0;
+        self.chosen_option = None;
+        self.options = match self.current_question() {
+            Some((_, question)) if question.is_multiple_choice() => question.get_shuffled_options(),
+            _ => Vec::new(),
+        };
+    }
+
+    /// Similarity of the typed answer to the correct one.
+    pub fn match_score(&self) -> Option<f64> {
+        match self.match_result {
+            Some(MatchResult::AutoCorrect { score })
+            | Some(MatchResult::AutoIncorrect { score })
+            | Some(MatchResult::NeedsUserDecision { score, .. }) => Some(score),
+            None => None,
+        }
+    }
+
+    /// Whether the answer to the current item was correct; `None` while it
+    /// is not answered or its match needs a decision.
+    pub fn answer_is_correct(&self) -> Option<bool> {
+        if self.is_multiple_choice() {
+            let (_, question) = self.current_question()?;
+            let option = self.options.get(self.chosen_option?)?;
+            return Some(question.is_correct_option(option));
+        }
+        match self.match_result {
+            Some(MatchResult::AutoCorrect { .. }) => Some(true),
+            Some(MatchResult::AutoIncorrect { .. }) => Some(false),
+            _ => None,
         }
     }
 }
 
 impl App {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config, profiles: ProfileStore) -> Self {
         Self {
             current_screen: CurrentScreen::Menu,
             config,
@@ -131,8 +385,10 @@ impl App {
             menu_cursor: 0,
             menu_items: vec![
                 "Typing Test",
+                "Practice Weaknesses",
                 "Learning Mode",
                 "Statistics",
+                "Profiles",
                 "Settings",
                 "Quit",
             ],
@@ -140,6 +396,11 @@ impl App {
             learning_state: LearningState::default(),
             file_explorer_state: FileExplorerState::default(),
             statistics_state: StatisticsState::default(),
+            custom_source: None,
+            replay_state: None,
+            heatmap_metric: HeatmapMetric::default(),
+            profiles,
+            profile_state: ProfileState::default(),
         }
     }
 
@@ -148,5 +409,10 @@ impl App {
         // Preserve config defaults if needed, but for now reset to defaults
         self.typing_state.language = self.config.defaults.language.parse().unwrap_or(Language::English);
         self.typing_state.difficulty = self.config.defaults.difficulty.parse().unwrap_or(Difficulty::Medium);
+        self.typing_state.mode = self.config.defaults.mode.parse().unwrap_or(TestMode::Words);
+        self.typing_state.time_limit = self.config.defaults.time_limit;
+        self.typing_state.code_language = self.config.code.language.parse().unwrap_or(CodeLanguage::Rust);
+        self.typing_state.indent = IndentStyle::from_config(&self.config.code.indent_style, self.config.code.indent_width);
+        self.typing_state.auto_indent = self.config.code.auto_indent;
     }
 }
@@ -0,0 +1,182 @@
+//! Keyboard heatmap widget.
+//!
+//! Draws the letter and number rows of a keyboard with every key coloured by
+//! its error rate or average latency from a [`KeystrokeAnalysis`].
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::core::Language;
+use crate::modules::typing::{KeyStat, KeystrokeAnalysis};
+
+/// QWERTY rows for English tests.
+const QWERTY: [&str; 4] = ["1234567890-", "qwertyuiop[", "asdfghjkl;'", "zxcvbnm,./"];
+
+/// QWERTZ rows for German tests.
+const QWERTZ: [&str; 4] = ["1234567890ß", "qwertzuiopü", "asdfghjklöä", "yxcvbnm,.-"];
+
+/// Width of a key including the gap to the next one.
+const KEY_WIDTH: u16 = 4;
+
+/// Which statistic the heatmap colours keys by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeatmapMetric {
+    #[default]
+    Errors,
+    Latency,
+}
+
+impl HeatmapMetric {
+    /// Switch to the other metric.
+    pub fn toggle(self) -> Self {
+        match self {
+            HeatmapMetric::Errors => HeatmapMetric::Latency,
+            HeatmapMetric::Latency => HeatmapMetric::Errors,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HeatmapMetric::Errors => "errors",
+            HeatmapMetric::Latency => "latency",
+        }
+    }
+}
+
+/// Keyboard heatmap of a keystroke analysis.
+pub struct KeyboardHeatmap<'a> {
+    analysis: &'a KeystrokeAnalysis,
+    rows: [&'static str; 4],
+    metric: HeatmapMetric,
+}
+
+impl<'a> KeyboardHeatmap<'a> {
+    pub fn new(analysis: &'a KeystrokeAnalysis, language: Language, metric: HeatmapMetric) -> Self {
+        let rows = match language {
+            Language::German => QWERTZ,
+            Language::English => QWERTY,
+        };
+        Self {
+            analysis,
+            rows,
+            metric,
+        }
+    }
+
+    /// Width needed to draw the whole keyboard.
+    pub fn width() -> u16 {
+        // Longest row plus the stagger of the last row
+        11 * KEY_WIDTH + 3
+    }
+
+    fn key_color(&self, stat: &KeyStat, mean_latency: f64) -> Color {
+        if stat.attempts == 0 {
+            return Color::DarkGray;
+        }
+
+        match self.metric {
+            HeatmapMetric::Errors => match stat.error_rate() {
+                _ if stat.errors == 0 => Color::Green,
+                r if r < 0.05 => Color::LightGreen,
+                r if r < 0.10 => Color::Yellow,
+                r if r < 0.20 => Color::LightRed,
+                _ => Color::Red,
+            },
+            HeatmapMetric::Latency => {
+                let Some(latency) = stat.avg_latency_ms() else {
+                    return Color::DarkGray;
+                };
+                match latency / mean_latency.max(1.0) {
+                    r if r < 0.8 => Color::Green,
+                    r if r < 1.0 => Color::LightGreen,
+                    r if r < 1.25 => Color::Yellow,
+                    r if r < 1.6 => Color::LightRed,
+                    _ => Color::Red,
+                }
+            }
+        }
+    }
+}
+
+impl Widget for KeyboardHeatmap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (total, samples) = self
+            .analysis
+            .keys
+            .values()
+            .fold((0u64, 0usize), |(t, n), s| (t + s.total_latency_ms, n + s.latency_samples));
+        let mean_latency = if samples > 0 {
+            total as f64 / samples as f64
+        } else {
+            0.0
+        };
+
+        for (row_index, row) in self.rows.iter().enumerate() {
+            let y = area.y + row_index as u16;
+            if y >= area.bottom() {
+                break;
+            }
+
+            for (key_index, key) in row.chars().enumerate() {
+                let x = area.x + row_index as u16 + key_index as u16 * KEY_WIDTH;
+                if x + KEY_WIDTH - 1 > area.right() {
+                    break;
+                }
+
+                let stat = self.analysis.key_stat(key);
+                let color = self.key_color(&stat, mean_latency);
+                // Keep single-char labels (ß uppercases to "SS")
+                let mut upper = key.to_uppercase();
+                let label = match (upper.next(), upper.next()) {
+                    (Some(c), None) => c,
+                    _ => key,
+                };
+                buf.set_string(
+                    x,
+                    y,
+                    format!(" {} ", label),
+                    Style::default().fg(Color::Black).bg(color),
+                );
+            }
+        }
+    }
+}
+
+/// Text lines listing the weakest keys and the slowest bigrams and trigrams.
+pub fn summary_lines(analysis: &KeystrokeAnalysis, min_samples: usize) -> Vec<Line<'static>> {
+    let label = Style::default().fg(Color::Yellow);
+
+    let keys = analysis
+        .weakest_keys(5)
+        .into_iter()
+        .map(|(c, s)| format!("{} {:.0}%", c, s.accuracy()))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let ngrams = |list: Vec<(String, KeyStat)>| {
+        list.into_iter()
+            .map(|(ngram, s)| format!("{} {:.0}ms", ngram, s.avg_latency_ms().unwrap_or(0.0)))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    vec![
+        Line::from(vec![Span::styled("Weakest keys: ", label), Span::raw(keys)]),
+        Line::from(vec![
+            Span::styled("Slowest bigrams: ", label),
+            Span::raw(ngrams(analysis.slowest_bigrams(5, min_samples))),
+        ]),
+        Line::from(vec![
+            Span::styled("Slowest trigrams: ", label),
+            Span::raw(ngrams(analysis.slowest_trigrams(5, min_samples))),
+        ]),
+        Line::from(vec![Span::styled(
+            "Green = good, red = weak, gray = no data",
+            Style::default().fg(Color::DarkGray),
+        )]),
+    ]
+}
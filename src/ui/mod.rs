@@ -1,4 +1,5 @@
 pub mod app;
+pub mod heatmap;
 pub mod render;
 pub mod tui;
 
@@ -6,20 +7,81 @@ use anyhow::Result;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use std::time::Duration;
 
-use crate::core::Config;
-use crate::modules::typing::{WordLoader, TestResult, scorer};
+use crate::core::{Config, ProfileStore, TestMode};
+use crate::modules::learning::{
+    card_keys, end_of_day, hardest_items, session_order, Grade, HardItem, LearningSession,
+    LearningSet, MatchResult, SessionItem,
+};
+use crate::modules::typing::{
+    dashboard, snippets, unicode, AdaptiveGenerator, CustomSource, Dashboard, Goal, HighScoreStatistics, KeystrokeAnalysis,
+    KeystrokeLog, SnippetLoader, SourceOrder, TestResult, WeaknessProfile, WordFilter,
+    WordLoader,
+};
+use crate::storage::{self, ResultFilter};
 use app::{App, CurrentScreen};
 use std::time::Instant;
 
+/// Timed tests append more words once fewer than this many characters are left.
+const STREAM_THRESHOLD: usize = 60;
+
+/// Number of hardest cards and questions listed after a learning session.
+const HARDEST_ITEMS: usize = 5;
+
 /// Run the TUI application
-pub fn run(config: Config, initial_screen: CurrentScreen) -> Result<()> {
-    // Initialize terminal
-    let mut terminal = tui::init()?;
-    
+///
+/// # Arguments
+///
+/// * `config` - Application configuration with the current profile applied
+/// * `profiles` - The player profiles
+/// * `initial_screen` - Screen to start on
+pub fn run(config: Config, profiles: ProfileStore, initial_screen: CurrentScreen) -> Result<()> {
     // Create app state
-    let mut app = App::new(config);
+    let mut app = App::new(config, profiles);
     app.current_screen = initial_screen;
 
+    // Load the text before touching the terminal so errors are printed normally
+    match initial_screen {
+        CurrentScreen::TypingTest => start_typing_test(&mut app)?,
+        CurrentScreen::Statistics => load_statistics(&mut app),
+        CurrentScreen::ProfileSelect => open_profiles(&mut app),
+        _ => {}
+    }
+
+    run_app(app)
+}
+
+/// Learn a set in the TUI.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration with the current profile applied
+/// * `profiles` - The player profiles
+/// * `set` - The learning set
+/// * `spaced` - Use spaced repetition and the stored progress
+pub fn learn(config: Config, profiles: ProfileStore, set: LearningSet, spaced: bool) -> Result<()> {
+    let mut app = App::new(config, profiles);
+    open_learning_set(&mut app, set, spaced);
+    run_app(app)
+}
+
+/// Replay a recorded typing test in the TUI.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `profiles` - The player profiles
+/// * `log` - The recorded keystrokes
+/// * `speed` - Playback speed multiplier (1.0 = real time)
+pub fn replay(config: Config, profiles: ProfileStore, log: KeystrokeLog, speed: f64) -> Result<()> {
+    let mut app = App::new(config, profiles);
+    start_replay(&mut app, log, speed);
+    run_app(app)
+}
+
+fn run_app(mut app: App) -> Result<()> {
+    // Initialize terminal
+    let mut terminal = tui::init()?;
+
     // Main loop
     loop {
         // Render
@@ -28,17 +90,21 @@ pub fn run(config: Config, initial_screen: CurrentScreen) -> Result<()> {
         })?;
 
         // Handle events
-        if let Some(event) = tui::read_event(Duration::from_millis(16))? {
-            match event {
-                Event::Key(key) => {
-                    if key.kind == KeyEventKind::Press {
-                        handle_key_event(&mut app, key)?;
-                    }
-                }
-                _ => {}
+        if let Some(Event::Key(key)) = tui::read_event(Duration::from_millis(16))? {
+            if key.kind == KeyEventKind::Press {
+                handle_key_event(&mut app, key)?;
             }
         }
 
+        // Timed tests end without a keystroke
+        if app.current_screen == CurrentScreen::TypingTest {
+            check_typing_completion(&mut app);
+        }
+
+        if app.current_screen == CurrentScreen::Replay {
+            advance_replay(&mut app);
+        }
+
         // Check exit condition
         if app.exit {
             break;
@@ -58,16 +124,22 @@ fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
         CurrentScreen::LearningSelect => handle_learning_select_input(app, key),
         CurrentScreen::LearningMode => handle_learning_mode_input(app, key),
         CurrentScreen::Settings => handle_settings_input(app, key),
-        CurrentScreen::TypingResults => {
-            if key.code == KeyCode::Enter || key.code == KeyCode::Esc {
-                app.current_screen = CurrentScreen::Menu;
+        CurrentScreen::ProfileSelect => handle_profile_input(app, key),
+        CurrentScreen::TypingResults => match key.code {
+            KeyCode::Enter | KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
+            KeyCode::Char('r') => {
+                let log = app.typing_state.keystrokes.clone();
+                start_replay(app, log, 1.0);
             }
-        }
-        CurrentScreen::Statistics => {
-             if key.code == KeyCode::Esc {
-                app.current_screen = CurrentScreen::Menu;
-            }
-        }
+            KeyCode::Char('h') => app.heatmap_metric = app.heatmap_metric.toggle(),
+            _ => {}
+        },
+        CurrentScreen::LearningResults => match key.code {
+            KeyCode::Enter | KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
+            _ => {}
+        },
+        CurrentScreen::Replay => handle_replay_input(app, key),
+        CurrentScreen::Statistics => handle_statistics_input(app, key),
         _ => {
             if key.code == KeyCode::Esc {
                 app.current_screen = CurrentScreen::Menu;
@@ -80,15 +152,14 @@ fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
 fn handle_learning_select_input(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
-        KeyCode::Up => {
-            if app.file_explorer_state.selected_index > 0 {
-                app.file_explorer_state.selected_index -= 1;
-            }
+        KeyCode::Up if app.file_explorer_state.selected_index > 0 => {
+            app.file_explorer_state.selected_index -= 1;
         }
-        KeyCode::Down => {
-            if app.file_explorer_state.selected_index < app.file_explorer_state.files.len().saturating_sub(1) {
-                app.file_explorer_state.selected_index += 1;
-            }
+        KeyCode::Down
+            if app.file_explorer_state.selected_index
+                < app.file_explorer_state.files.len().saturating_sub(1) =>
+        {
+            app.file_explorer_state.selected_index += 1;
         }
         KeyCode::Enter => {
             if let Some(path) = app.file_explorer_state.files.get(app.file_explorer_state.selected_index) {
@@ -100,9 +171,8 @@ fn handle_learning_select_input(app: &mut App, key: KeyEvent) {
                 } else {
                     // Load file
                     if let Ok(set) = crate::modules::learning::load_auto(path) {
-                        app.learning_state = app::LearningState::default();
-                        app.learning_state.set = Some(set);
-                        app.current_screen = CurrentScreen::LearningMode;
+                        let spaced = app.config.learning.spaced_repetition;
+                        open_learning_set(app, set, spaced);
                     }
                 }
             }
@@ -111,38 +181,110 @@ fn handle_learning_select_input(app: &mut App, key: KeyEvent) {
     }
 }
 
+/// Start a session with a learning set.
+///
+/// With spaced repetition the stored progress is reconciled with the cards
+/// and the cards that are due today are asked in the order of the set's
+/// scheduling algorithm; otherwise all cards in file order. Depending on
+/// `learning.session_content` the quiz questions are asked between the cards
+/// or instead of them. The finished session is saved to the session history.
+fn open_learning_set(app: &mut App, set: LearningSet, spaced: bool) {
+    let mut state = app::LearningState::default();
+    let mut cards: Vec<usize> = (0..set.cards.len()).collect();
+
+    match storage::open(&app.config) {
+        Ok(storage) => {
+            if spaced {
+                match storage.load_progress() {
+                    Ok(stored) => {
+                        let (progress, changes) = stored.for_set(&set, app.config.learning.leitner_boxes);
+                        if changes.is_changed() {
+                            if let Err(e) = storage.save_set_progress(&set.name, &progress) {
+                                state.error = Some(format!("Progress not saved: {}", e));
+                            }
+                        }
+                        let algorithm = set.scheduler_algorithm(
+                            app.config.learning.algorithm.parse().unwrap_or_default(),
+                        );
+                        let scheduler = progress.scheduler(&set, algorithm, &app.config.learning);
+                        cards = scheduler.due_items(end_of_day(chrono::Local::now().date_naive()));
+                        let summary = scheduler.summary();
+                        state.box_counts_before = summary.box_counts.clone();
+                        state.mastery_before = Some(summary.mastery_percentage());
+                        state.scheduler = Some(scheduler);
+                        state.progress = progress;
+                        state.card_keys = card_keys(&set);
+                    }
+                    Err(e) => state.error = Some(format!("Progress not loaded: {}", e)),
+                }
+            }
+            state.storage = Some(storage);
+        }
+        Err(e) => state.error = Some(format!("Progress not loaded: {}", e)),
+    }
+
+    let content = app.config.learning.session_content.parse().unwrap_or_default();
+    state.order = session_order(cards, set.questions.len(), content);
+    state.set = Some(set);
+    state.prepare_item();
+    state.started = Some(Instant::now());
+    state.started_at = Some(chrono::Utc::now());
+    app.learning_state = state;
+    app.current_screen = CurrentScreen::LearningMode;
+}
+
 fn handle_learning_mode_input(app: &mut App, key: KeyEvent) {
+    if app.learning_state.is_multiple_choice() {
+        handle_quiz_input(app, key);
+        return;
+    }
+
+    let awaiting_decision = app.learning_state.show_back
+        && matches!(app.learning_state.match_result, Some(MatchResult::NeedsUserDecision { .. }));
+    let on_card = app.learning_state.current_card().is_some();
+
     match key.code {
         KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
-        KeyCode::Char(c) => {
-            if !app.learning_state.show_back {
-                app.learning_state.user_input.push(c);
+        KeyCode::Char(c @ ('y' | 'j' | 'n')) if awaiting_decision => {
+            app.learning_state.overridden = true;
+            if let Some(MatchResult::NeedsUserDecision { score, .. }) = app.learning_state.match_result {
+                app.learning_state.match_result = Some(if c != 'n' {
+                    MatchResult::AutoCorrect { score }
+                } else {
+                    MatchResult::AutoIncorrect { score }
+                });
             }
         }
-        KeyCode::Backspace => {
-            if !app.learning_state.show_back {
-                app.learning_state.user_input.pop();
+        KeyCode::Char(c @ '1'..='4') if app.learning_state.show_back && on_card => {
+            if let Some(grade) = c.to_digit(10).and_then(Grade::from_number) {
+                grade_item(app, grade);
             }
         }
+        KeyCode::Char(c) if !app.learning_state.show_back => {
+            app.learning_state.user_input.push(c);
+        }
+        KeyCode::Backspace if !app.learning_state.show_back => {
+            app.learning_state.user_input.pop();
+        }
+        KeyCode::Enter if awaiting_decision => {}
         KeyCode::Enter => {
             if app.learning_state.show_back {
-                // Next card
-                app.learning_state.current_card_index += 1;
-                app.learning_state.user_input.clear();
-                app.learning_state.show_back = false;
-                app.learning_state.match_result = None;
+                // Next item, graded by the answer
+                let grade = match app.learning_state.answer_is_correct() {
+                    Some(true) => Grade::Good,
+                    _ => Grade::Again,
+                };
+                grade_item(app, grade);
             } else {
                 // Submit answer
-                if let Some(set) = &app.learning_state.set {
-                    if let Some(card) = set.cards.get(app.learning_state.current_card_index) {
-                        let matcher = crate::modules::learning::FuzzyMatcher::new(
-                            app.config.learning.fuzzy_threshold,
-                            0.10
-                        );
-                        let result = matcher.check_answer(&app.learning_state.user_input, &card.back);
-                        app.learning_state.match_result = Some(result);
-                        app.learning_state.show_back = true;
-                    }
+                if let Some((_, answer, _)) = app.learning_state.current_prompt() {
+                    let matcher = crate::modules::learning::FuzzyMatcher::new(
+                        app.config.learning.fuzzy_threshold,
+                        0.10
+                    );
+                    let result = matcher.check_answer(&app.learning_state.user_input, answer);
+                    app.learning_state.match_result = Some(result);
+                    app.learning_state.show_back = true;
                 }
             }
         }
@@ -150,6 +292,142 @@ fn handle_learning_mode_input(app: &mut App, key: KeyEvent) {
     }
 }
 
+/// Answer a multiple-choice question by number (1-9) or with the arrow keys
+/// and Enter.
+fn handle_quiz_input(app: &mut App, key: KeyEvent) {
+    let state = &mut app.learning_state;
+    let count = state.options.len();
+
+    match key.code {
+        KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
+        KeyCode::Enter if state.show_back => {
+            let grade = if state.answer_is_correct() == Some(true) {
+                Grade::Good
+            } else {
+                Grade::Again
+            };
+            grade_item(app, grade);
+        }
+        _ if state.show_back => {}
+        KeyCode::Up | KeyCode::Char('k') => {
+            state.selected_option = (state.selected_option + count - 1) % count.max(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            state.selected_option = (state.selected_option + 1) % count.max(1);
+        }
+        KeyCode::Enter => {
+            state.chosen_option = Some(state.selected_option);
+            state.show_back = true;
+        }
+        KeyCode::Char(c) => {
+            if let Some(number) = c.to_digit(10).filter(|&n| n >= 1 && n as usize <= count) {
+                state.selected_option = number as usize - 1;
+                state.chosen_option = Some(state.selected_option);
+                state.show_back = true;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Count the answer to the current card or question and move on to the next
+/// one. With spaced repetition a card is also rescheduled and the progress
+/// saved. After the last item the results are shown.
+fn grade_item(app: &mut App, grade: Grade) {
+    let state = &mut app.learning_state;
+    let Some(item) = state.current_item() else {
+        return;
+    };
+    let correct = grade.is_correct();
+    if state.overridden {
+        state.stats.record_override(correct);
+    } else if correct {
+        state.stats.record_correct();
+    } else {
+        state.stats.record_incorrect();
+    }
+    if let SessionItem::Question(_) = item {
+        state.quiz_total += 1;
+        if correct {
+            state.quiz_correct += 1;
+        }
+    }
+    if grade <= Grade::Hard {
+        if let Some((prompt, answer, _)) = state.current_prompt() {
+            let hard = HardItem {
+                prompt: prompt.to_string(),
+                answer: answer.to_string(),
+                grade,
+                score: state.match_score(),
+            };
+            state.hard_items.push(hard);
+        }
+    }
+    state.advance();
+
+    if let (SessionItem::Card(index), Some(set), Some(scheduler)) =
+        (item, &state.set, &mut state.scheduler)
+    {
+        let before = scheduler.state(index).cloned().unwrap_or_default();
+        scheduler.review(index, grade, chrono::Utc::now());
+
+        if let Some(after) = scheduler.state(index) {
+            state.movement.record(scheduler.algorithm(), &before, after);
+            state.progress.record(&state.card_keys[index], &set.cards[index], after.clone());
+        }
+        if let Some(storage) = &state.storage {
+            if let Err(e) = storage.save_set_progress(&set.name, &state.progress) {
+                state.error = Some(format!("Progress not saved: {}", e));
+            }
+        }
+    }
+
+    if state.current_item().is_none() {
+        finish_learning_session(app);
+    }
+}
+
+/// Save the finished session to the history and show the results.
+fn finish_learning_session(app: &mut App) {
+    let state = &mut app.learning_state;
+    let Some(set) = &state.set else {
+        return;
+    };
+    let summary = state.scheduler.as_ref().map(|s| s.summary());
+    let session = LearningSession {
+        set_name: set.name.clone(),
+        started: state.started_at.unwrap_or_else(chrono::Utc::now),
+        duration_secs: state.started.map_or(0.0, |s| s.elapsed().as_secs_f64()),
+        stats: state.stats.clone(),
+        quiz_total: state.quiz_total,
+        quiz_correct: state.quiz_correct,
+        algorithm: state.scheduler.as_ref().map(|s| s.algorithm()),
+        movement: state.movement,
+        mastery: summary.map(|s| s.mastery_percentage()),
+        hardest: hardest_items(&state.hard_items, HARDEST_ITEMS)
+            .iter()
+            .map(|item| item.prompt.clone())
+            .collect(),
+    };
+
+    if let Some(storage) = &state.storage {
+        if let Err(e) = storage.append_session(&session) {
+            state.error = Some(format!("Session not saved: {}", e));
+        }
+        match storage.load_sessions() {
+            Ok(sessions) => {
+                state.set_history = sessions
+                    .into_iter()
+                    .filter(|s| s.set_name == session.set_name)
+                    .collect();
+            }
+            Err(e) => state.error = Some(format!("Session history not loaded: {}", e)),
+        }
+    }
+    state.session = Some(session);
+    app.current_screen = CurrentScreen::LearningResults;
+}
+
 fn handle_settings_input(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
@@ -166,8 +444,40 @@ fn handle_settings_input(app: &mut App, key: KeyEvent) {
                 _ => "Easy".to_string(),
             };
         }
+        KeyCode::Char('m') => {
+            // Cycle: words -> time 15s -> 30s -> 60s -> 120s -> code -> adaptive -> words
+            let mode = app.config.defaults.mode.parse().unwrap_or(TestMode::Words);
+            let limits = crate::core::types::TIME_LIMITS;
+            let (mode, time_limit) = match mode {
+                TestMode::Words => (TestMode::Time, limits[0]),
+                TestMode::Time => match limits.iter().position(|&l| l == app.config.defaults.time_limit) {
+                    Some(i) if i + 1 < limits.len() => (TestMode::Time, limits[i + 1]),
+                    _ => (TestMode::Code, app.config.defaults.time_limit),
+                },
+                TestMode::Code => (TestMode::Adaptive, app.config.defaults.time_limit),
+                TestMode::Adaptive => (TestMode::Words, app.config.defaults.time_limit),
+            };
+            app.config.defaults.mode = mode.code().to_string();
+            app.config.defaults.time_limit = time_limit;
+        }
+        KeyCode::Char('c') => {
+            app.config.code.language = match app.config.code.language.as_str() {
+                "rust" => "python".to_string(),
+                "python" => "javascript".to_string(),
+                _ => "rust".to_string(),
+            };
+        }
+        KeyCode::Char('i') => {
+            app.config.code.indent_style = match app.config.code.indent_style.as_str() {
+                "spaces" => "tab".to_string(),
+                _ => "spaces".to_string(),
+            };
+        }
+        KeyCode::Char('a') => {
+            app.config.code.auto_indent = !app.config.code.auto_indent;
+        }
         KeyCode::Char('s') => {
-            if let Err(e) = app.config.save_to_file("config/default.toml") {
+            if let Err(e) = save_settings(app) {
                 // TODO: Show error in UI
                 eprintln!("Failed to save config: {}", e);
             }
@@ -176,6 +486,122 @@ fn handle_settings_input(app: &mut App, key: KeyEvent) {
     }
 }
 
+/// Save the settings: language, difficulty and theme go to the current
+/// profile (or the configuration if there is none), the rest to the
+/// configuration file.
+fn save_settings(app: &mut App) -> crate::core::Result<()> {
+    let path = std::path::Path::new("config/default.toml");
+    let mut config = if path.exists() {
+        Config::load_from_file(path)?
+    } else {
+        Config::default()
+    };
+    config.defaults.mode = app.config.defaults.mode.clone();
+    config.defaults.time_limit = app.config.defaults.time_limit;
+    config.code = app.config.code.clone();
+
+    match app.profiles.current().cloned() {
+        Some(mut profile) => {
+            profile.update_settings(&app.config);
+            app.profiles.update(&profile)?;
+        }
+        None => {
+            config.defaults.language = app.config.defaults.language.clone();
+            config.defaults.difficulty = app.config.defaults.difficulty.clone();
+        }
+    }
+    config.save_to_file(path)
+}
+
+/// Show the profile screen with the current profile selected.
+fn open_profiles(app: &mut App) {
+    let current = app.profiles.current().map(|p| p.name.clone());
+    app.profile_state = app::ProfileState {
+        cursor: app
+            .profiles
+            .profiles()
+            .iter()
+            .position(|p| Some(&p.name) == current.as_ref())
+            .unwrap_or(0),
+        ..app::ProfileState::default()
+    };
+    app.current_screen = CurrentScreen::ProfileSelect;
+}
+
+fn handle_profile_input(app: &mut App, key: KeyEvent) {
+    let state = &mut app.profile_state;
+    if let Some(edit) = state.edit {
+        match key.code {
+            KeyCode::Esc => {
+                state.edit = None;
+                state.error = None;
+            }
+            KeyCode::Char(c) => state.input.push(c),
+            KeyCode::Backspace => {
+                state.input.pop();
+            }
+            KeyCode::Enter => {
+                let input = std::mem::take(&mut state.input);
+                let result = match edit {
+                    app::ProfileEdit::Create => app.profiles.create(&input, &app.config),
+                    app::ProfileEdit::Rename => {
+                        let name = app.profiles.profiles()[app.profile_state.cursor].name.clone();
+                        app.profiles.rename(&name, &input)
+                    }
+                };
+                match result {
+                    Ok(profile) => {
+                        if edit == app::ProfileEdit::Create {
+                            switch_profile(app, &profile);
+                        }
+                        open_profiles(app);
+                    }
+                    Err(e) => {
+                        app.profile_state.input = input;
+                        app.profile_state.error = Some(e.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    let count = app.profiles.profiles().len();
+    match key.code {
+        KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
+        KeyCode::Up if state.cursor > 0 => state.cursor -= 1,
+        KeyCode::Down if state.cursor + 1 < count => state.cursor += 1,
+        KeyCode::Char('n') => {
+            state.edit = Some(app::ProfileEdit::Create);
+            state.input.clear();
+            state.error = None;
+        }
+        KeyCode::Char('r') if state.cursor < count => {
+            state.edit = Some(app::ProfileEdit::Rename);
+            state.input = app.profiles.profiles()[state.cursor].name.clone();
+            state.error = None;
+        }
+        KeyCode::Enter if state.cursor < count => {
+            let name = app.profiles.profiles()[state.cursor].name.clone();
+            match app.profiles.select(&name) {
+                Ok(profile) => {
+                    switch_profile(app, &profile);
+                    app.current_screen = CurrentScreen::Menu;
+                }
+                Err(e) => state.error = Some(e.to_string()),
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Use the data and settings of another profile.
+fn switch_profile(app: &mut App, profile: &crate::core::Profile) {
+    app.config = app.profiles.apply(profile, &app.config);
+    app.statistics_state = app::StatisticsState::default();
+}
+
 fn refresh_file_list(app: &mut App) {
     app.file_explorer_state.files.clear();
     // Add parent directory if not at root
@@ -222,34 +648,29 @@ fn handle_menu_input(app: &mut App, key: KeyEvent) {
         KeyCode::Enter => {
             match app.menu_cursor {
                 0 => { // Typing Test
+                    let _ = start_typing_test(app); // Stay in the menu if no words could be loaded
+                }
+                1 => { // Practice Weaknesses
                     app.reset_typing();
-                    // Load words (simplified for now)
-                    let loader = WordLoader::new(&app.config.paths.data_dir);
-                    if let Ok(text) = loader.generate_text(app.typing_state.language.clone(), app.typing_state.difficulty.clone()) {
-                        app.typing_state.target_text = text;
-                        app.current_screen = CurrentScreen::TypingTest;
-                    }
+                    app.typing_state.mode = TestMode::Adaptive;
+                    let _ = load_typing_text(app);
                 }
-                1 => { // Learning Mode
+                2 => { // Learning Mode
                     app.current_screen = CurrentScreen::LearningSelect;
                     app.file_explorer_state.current_dir = std::env::current_dir().unwrap_or_default();
                     refresh_file_list(app);
                 }
-                2 => { // Statistics
+                3 => { // Statistics
                     app.current_screen = CurrentScreen::Statistics;
-                    // Load statistics
-                    let manager = crate::modules::typing::HighScoreManager::new(
-                        &app.config.paths.highscore_file,
-                        app.config.defaults.max_highscores
-                    );
-                    if let Ok(scores) = manager.load() {
-                        app.statistics_state.highscores = scores;
-                    }
+                    load_statistics(app);
+                }
+                4 => { // Profiles
+                    open_profiles(app);
                 }
-                3 => { // Settings
+                5 => { // Settings
                     app.current_screen = CurrentScreen::Settings;
                 }
-                4 => { // Quit
+                6 => { // Quit
                     app.exit = true;
                 }
                 _ => {}
@@ -259,6 +680,125 @@ fn handle_menu_input(app: &mut App, key: KeyEvent) {
     }
 }
 
+fn handle_statistics_input(app: &mut App, key: KeyEvent) {
+    let state = &mut app.statistics_state;
+    match key.code {
+        KeyCode::Esc => app.current_screen = CurrentScreen::Menu,
+        KeyCode::Tab => {
+            state.view = match state.view {
+                app::StatsView::Dashboard => app::StatsView::Details,
+                app::StatsView::Details => app::StatsView::Dashboard,
+            };
+        }
+        KeyCode::Char('p') => {
+            state.period = state.period.next();
+            load_statistics(app);
+        }
+        KeyCode::Char('m') => {
+            state.mode = dashboard::next_mode_filter(state.mode);
+            load_statistics(app);
+        }
+        KeyCode::Char('h') => app.heatmap_metric = app.heatmap_metric.toggle(),
+        _ => {}
+    }
+}
+
+/// Load the highscores, the dashboard of the results in the selected period
+/// and mode, and the accumulated key statistics.
+fn load_statistics(app: &mut App) {
+    if let Ok(manager) = crate::modules::typing::HighScoreManager::from_config(&app.config) {
+        if let Ok(scores) = manager.load() {
+            app.statistics_state.highscores = scores;
+        }
+
+        let state = &mut app.statistics_state;
+        let today = chrono::Local::now().date_naive();
+        let filter = ResultFilter::default()
+            .between(state.period.start(today), None)
+            .with_mode(state.mode);
+        let history = manager.get_history(&filter);
+        state.stats_summary = history
+            .as_ref()
+            .ok()
+            .map(|scores| HighScoreStatistics::from_history(scores));
+        state.dashboard = history
+            .map(|scores| Dashboard::from_history(&scores, today))
+            .unwrap_or_default();
+
+        if let Ok(logs) = manager.storage().load_keystroke_logs() {
+            app.statistics_state.key_analysis = KeystrokeAnalysis::from_logs(&logs);
+        }
+    }
+}
+
+/// Reset the typing state from the config defaults and load a fresh text.
+fn start_typing_test(app: &mut App) -> crate::core::Result<()> {
+    app.reset_typing();
+    load_typing_text(app)
+}
+
+/// Load the text for the current typing state and show the typing screen.
+fn load_typing_text(app: &mut App) -> crate::core::Result<()> {
+    let state = &app.typing_state;
+    let target_text = match state.mode {
+        TestMode::Code => {
+            let loader = match &app.config.code.snippet_path {
+                Some(path) => SnippetLoader::from_path(path),
+                None => SnippetLoader::bundled(),
+            };
+            loader.generate_snippet(state.code_language, state.difficulty, state.indent)?
+        }
+        TestMode::Adaptive => generate_practice_words(app)?,
+        TestMode::Words | TestMode::Time => {
+            if app.custom_source.is_none() {
+                if let Some(path) = &app.config.defaults.source {
+                    app.custom_source = Some(CustomSource::load(path)?);
+                }
+            }
+            generate_words(app)?
+        }
+    };
+    app.typing_state.target_text = unicode::normalize(&target_text);
+    app.current_screen = CurrentScreen::TypingTest;
+    Ok(())
+}
+
+/// Generate a word text from the custom source, or the word files if none is set.
+fn generate_words(app: &App) -> crate::core::Result<String> {
+    let state = &app.typing_state;
+    match &app.custom_source {
+        Some(source) => {
+            let order = app.config.defaults.source_order.parse().unwrap_or(SourceOrder::Auto);
+            source.generate_text(order, state.difficulty)
+        }
+        None => {
+            let loader = WordLoader::new(&app.config.paths.data_dir);
+            loader.generate_text_filtered(state.language, state.difficulty, &word_filter(app))
+        }
+    }
+}
+
+/// Generate words that practise the weakest keys of the recorded tests.
+fn generate_practice_words(app: &App) -> crate::core::Result<String> {
+    let state = &app.typing_state;
+    let logs = storage::open(&app.config)?.load_keystroke_logs()?;
+    let analysis = KeystrokeAnalysis::from_logs(
+        logs.iter()
+            .filter(|log| log.language.is_none() || log.language == Some(state.language)),
+    );
+
+    let loader = WordLoader::new(&app.config.paths.data_dir);
+    let generator = AdaptiveGenerator::new(&loader, WeaknessProfile::from_analysis(&analysis));
+    generator.generate_text(state.language, state.difficulty, &word_filter(app))
+}
+
+/// Word filter from the difficulty and the configured word criteria.
+fn word_filter(app: &App) -> WordFilter {
+    WordFilter::for_difficulty(app.typing_state.difficulty)
+        .with_parts_of_speech(&app.config.defaults.parts_of_speech)
+        .with_frequency(app.config.defaults.frequency.as_deref().and_then(|f| f.parse().ok()))
+}
+
 fn handle_typing_input(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => {
@@ -266,63 +806,215 @@ fn handle_typing_input(app: &mut App, key: KeyEvent) {
             app.typing_state.is_active = false;
         }
         KeyCode::Char(c) => {
-            if !app.typing_state.is_active {
-                app.typing_state.is_active = true;
-                app.typing_state.start_time = Some(Instant::now());
-            }
-            
-            app.typing_state.typed_text.push(c);
-            
-            // Check for error (simplified: just checking if char matches target at that position)
-            let idx = app.typing_state.typed_text.len() - 1;
-            if let Some(target_char) = app.typing_state.target_text.chars().nth(idx) {
-                if c != target_char {
-                    app.typing_state.error_count += 1;
+            type_char(app, c, false);
+            extend_target_text(app);
+            check_typing_completion(app);
+        }
+        KeyCode::Enter if app.typing_state.mode == TestMode::Code => {
+            type_char(app, '\n', false);
+            if app.typing_state.auto_indent {
+                let position = unicode::grapheme_count(&app.typing_state.typed_text);
+                for c in snippets::indentation_at(&app.typing_state.target_text, position).chars() {
+                    type_char(app, c, true);
                 }
-            } else {
-                // Typed beyond target
-                app.typing_state.error_count += 1;
             }
-
+            check_typing_completion(app);
+        }
+        KeyCode::Tab if app.typing_state.mode == TestMode::Code => {
+            for (i, c) in app.typing_state.indent.unit().chars().enumerate() {
+                type_char(app, c, i > 0);
+            }
             check_typing_completion(app);
         }
         KeyCode::Backspace => {
-            app.typing_state.typed_text.pop();
+            let state = &mut app.typing_state;
+            if unicode::pop_grapheme(&mut state.typed_text).is_some() {
+                let position = unicode::grapheme_count(&state.typed_text);
+                let expected = expected_char(&state.target_text, position);
+                state.keystrokes.record_backspace(state.elapsed(), expected, position + 1);
+            }
         }
         _ => {}
     }
 }
 
+/// Append a typed character, record the keystroke and count it as an error
+/// if it does not match the target.
+///
+/// `synthetic` marks characters inserted automatically (auto-indent, the
+/// extra spaces of a Tab) rather than typed. Characters are compared as
+/// grapheme clusters, so a combining mark completes the letter before it.
+fn type_char(app: &mut App, c: char, synthetic: bool) {
+    if !app.typing_state.is_active {
+        app.typing_state.is_active = true;
+        app.typing_state.start_time = Some(Instant::now());
+    }
+
+    let state = &mut app.typing_state;
+    let before = state.typed_text.clone();
+    let position = unicode::grapheme_count(&before);
+    let expected = expected_char(&state.target_text, position);
+    state.keystrokes.record_char(state.elapsed(), c, expected, position, synthetic);
+
+    unicode::push_char(&mut state.typed_text, c);
+    state.error_count += unicode::keystroke_errors(&state.target_text, &before, &state.typed_text);
+}
+
+/// First character of the target grapheme at `position` (for the keystroke log).
+fn expected_char(target: &str, position: usize) -> Option<char> {
+    unicode::grapheme_at(target, position).and_then(|g| g.chars().next())
+}
+
+/// Keep the text of a timed test from running out by streaming in more words.
+fn extend_target_text(app: &mut App) {
+    let state = &app.typing_state;
+    if state.mode != TestMode::Time {
+        return;
+    }
+
+    let remaining = unicode::grapheme_count(&state.target_text)
+        .saturating_sub(unicode::grapheme_count(&state.typed_text));
+    if remaining >= STREAM_THRESHOLD {
+        return;
+    }
+
+    if let Ok(more) = generate_words(app) {
+        app.typing_state.target_text.push(' ');
+        app.typing_state.target_text.push_str(&unicode::normalize(&more));
+    }
+}
+
 fn check_typing_completion(app: &mut App) {
-    if app.typing_state.typed_text.len() >= app.typing_state.target_text.len() {
-        app.typing_state.end_time = Some(Instant::now());
-        app.typing_state.is_active = false;
-        
-        // Calculate results
-        let duration = app.typing_state.end_time.unwrap().duration_since(app.typing_state.start_time.unwrap());
-        let result = TestResult::calculate(
-            &app.typing_state.target_text,
+    let state = &app.typing_state;
+    let finished = match state.mode {
+        TestMode::Words | TestMode::Code | TestMode::Adaptive => {
+            unicode::is_complete(&state.target_text, &state.typed_text)
+        }
+        TestMode::Time => state.is_active && state.remaining_time() == Some(Duration::ZERO),
+    };
+
+    if finished {
+        finish_typing_test(app);
+    }
+}
+
+fn finish_typing_test(app: &mut App) {
+    let start_time = match app.typing_state.start_time {
+        Some(start) => start,
+        None => return,
+    };
+
+    let (duration, time_limit) = match app.typing_state.mode {
+        TestMode::Words | TestMode::Code | TestMode::Adaptive => {
+            (Instant::now().duration_since(start_time), None)
+        }
+        TestMode::Time => {
+            let limit = app.typing_state.time_limit;
+            (Duration::from_secs(limit), Some(limit))
+        }
+    };
+    app.typing_state.end_time = Some(start_time + duration);
+    app.typing_state.is_active = false;
+
+    // In timed mode only the part of the text that was reached counts
+    let typed_graphemes = unicode::grapheme_count(&app.typing_state.typed_text);
+    let target = unicode::prefix(&app.typing_state.target_text, typed_graphemes).to_string();
+
+    // Calculate results
+    let result = match app.typing_state.mode {
+        TestMode::Code => TestResult::calculate_code(
+            &target,
+            &app.typing_state.typed_text,
+            duration,
+            app.typing_state.error_count
+        ),
+        mode => TestResult::calculate(
+            &target,
             &app.typing_state.typed_text,
             duration,
             app.typing_state.error_count
-        );
-        
-        // Save score
-        let manager = crate::modules::typing::HighScoreManager::new(
-            &app.config.paths.highscore_file,
-            app.config.defaults.max_highscores
-        );
-        
-        let score = crate::modules::typing::HighScore::from_result(
-            "Player".to_string(), // TODO: Get name
-            &result,
-            app.typing_state.language.clone(),
-            app.typing_state.difficulty.clone()
-        );
-        
-        let _ = manager.add_score(score); // Ignore error for now
-        
-        app.typing_state.result = Some(result);
-        app.current_screen = CurrentScreen::TypingResults;
+        )
+        .with_mode(mode, time_limit),
+    }
+    .with_keystrokes(&app.typing_state.keystrokes);
+
+    // Save score
+    let manager = crate::modules::typing::HighScoreManager::from_config(&app.config);
+
+    let score = crate::modules::typing::HighScore::from_result(
+        app.profiles.player_name(),
+        &result,
+        app.typing_state.language,
+        app.typing_state.difficulty
+    );
+
+    // The keystroke log is saved together with the result
+    let log = &mut app.typing_state.keystrokes;
+    log.id = score.id.clone().unwrap_or_default();
+    log.language = Some(app.typing_state.language);
+    log.difficulty = Some(app.typing_state.difficulty);
+    log.mode = result.mode;
+    log.time_limit = result.time_limit;
+    log.target_text = app.typing_state.target_text.clone();
+    log.timestamp = Some(score.timestamp);
+    app.typing_state.analysis = Some(KeystrokeAnalysis::from_log(log));
+
+    // Compare with the earlier results before this one is added
+    let goals: Vec<Goal> = app.config.goals.iter().filter_map(|g| Goal::from_config(g).ok()).collect();
+    let feedback = manager.as_ref().ok().and_then(|m| m.run_feedback(&score, &goals).ok());
+
+    app.typing_state.result_id = score.id.clone();
+    let log = &app.typing_state.keystrokes;
+    app.typing_state.score_rejection = match manager.and_then(|m| m.add_score_with_log(score, log)) {
+        Ok(()) => None,
+        Err(e) => Some(e.to_string()),
+    };
+    // A rejected result is not compared like a valid run
+    app.typing_state.feedback = feedback.filter(|_| app.typing_state.score_rejection.is_none());
+
+    app.typing_state.result = Some(result);
+    app.current_screen = CurrentScreen::TypingResults;
+}
+
+/// Show a recorded test on the replay screen.
+fn start_replay(app: &mut App, log: KeystrokeLog, speed: f64) {
+    app.typing_state.target_text = log.target_text.clone();
+    app.typing_state.typed_text.clear();
+    app.typing_state.mode = log.mode;
+    if let Some(language) = log.language {
+        app.typing_state.language = language;
+    }
+    if let Some(difficulty) = log.difficulty {
+        app.typing_state.difficulty = difficulty;
+    }
+    app.replay_state = Some(app::ReplayState::new(log, speed));
+    app.current_screen = CurrentScreen::Replay;
+}
+
+/// Advance the replay and rebuild the typed text up to the current position.
+fn advance_replay(app: &mut App) {
+    if let Some(replay) = &mut app.replay_state {
+        replay.tick();
+        let count = replay.log.keystrokes_until(replay.position);
+        app.typing_state.typed_text = replay.log.typed_text_after(count);
+    }
+}
+
+fn handle_replay_input(app: &mut App, key: KeyEvent) {
+    let Some(replay) = &mut app.replay_state else {
+        app.current_screen = CurrentScreen::Menu;
+        return;
+    };
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.replay_state = None;
+            app.current_screen = CurrentScreen::Menu;
+        }
+        KeyCode::Char(' ') => replay.toggle_pause(),
+        KeyCode::Char('+') | KeyCode::Right => replay.speed = (replay.speed * 2.0).min(16.0),
+        KeyCode::Char('-') | KeyCode::Left => replay.speed = (replay.speed / 2.0).max(0.25),
+        KeyCode::Char('r') => replay.position = Duration::ZERO,
+        _ => {}
     }
 }
@@ -1,12 +1,24 @@
+use chrono::Datelike;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols,
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Axis, BarChart, Block, Borders, Chart, Dataset, GraphType, List, ListItem, Paragraph, Sparkline, Wrap,
+    },
     Frame,
 };
 
-use super::app::{App, CurrentScreen};
+use super::app::{App, CurrentScreen, LearningState, ProfileEdit, StatsView};
+use super::heatmap::{self, KeyboardHeatmap};
+use crate::core::{Language, TestMode};
+use crate::modules::learning::{format_duration, hardest_items, set_trends, SessionItem};
+use crate::modules::typing::highscore::RECENT_TESTS;
+use crate::modules::typing::unicode::{self, GraphemeMatch};
+use crate::modules::typing::{
+    dashboard, Dashboard, HighScoreStatistics, KeystrokeAnalysis, RunFeedback, TestResult,
+};
 
 /// Render the application state
 pub fn render(app: &mut App, frame: &mut Frame) {
@@ -29,20 +41,27 @@ fn render_header(app: &App, frame: &mut Frame, area: Rect) {
         CurrentScreen::Menu => " Main Menu ",
         CurrentScreen::TypingTest => " Typing Test ",
         CurrentScreen::TypingResults => " Test Results ",
+        CurrentScreen::Replay => " Replay ",
         CurrentScreen::LearningSelect => " Select Learning Set ",
         CurrentScreen::LearningMode => " Learning Mode ",
         CurrentScreen::LearningResults => " Learning Results ",
         CurrentScreen::Statistics => " Statistics ",
         CurrentScreen::Settings => " Settings ",
+        CurrentScreen::ProfileSelect => " Profiles ",
         CurrentScreen::Exiting => " Exiting ",
     };
+    let profile = app
+        .profiles
+        .current()
+        .map(|p| format!("[{}] ", p.name))
+        .unwrap_or_default();
 
     let block = Block::default()
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::Cyan));
 
     let paragraph = Paragraph::new(Span::styled(
-        format!(" Rust Util Tools - {} ", title),
+        format!(" Rust Util Tools - {} {}", title, profile),
         Style::default().add_modifier(Modifier::BOLD),
     ))
     .block(block)
@@ -54,13 +73,49 @@ fn render_header(app: &App, frame: &mut Frame, area: Rect) {
 fn render_footer(app: &App, frame: &mut Frame, area: Rect) {
     let help_text = match app.current_screen {
         CurrentScreen::Menu => "Use ↑/↓ to navigate, Enter to select, q to quit",
+        CurrentScreen::TypingTest if app.typing_state.mode == TestMode::Code => {
+            "Type the code! Enter for new line, Tab to indent, Esc to cancel"
+        }
         CurrentScreen::TypingTest => "Type the text! Esc to cancel",
-        CurrentScreen::TypingResults => "Press Enter to continue",
+        CurrentScreen::TypingResults => "Press Enter to continue, r to replay, h: Heatmap errors/latency",
+        CurrentScreen::Replay => "Space: Pause, +/-: Speed, r: Restart, Esc: Back",
         CurrentScreen::LearningSelect => "Enter path to file, Esc to back",
+        CurrentScreen::LearningMode
+            if matches!(
+                app.learning_state.match_result,
+                Some(crate::modules::learning::MatchResult::NeedsUserDecision { .. })
+            ) =>
+        {
+            "y: Count as correct, n: Count as wrong, 1-4: Again/Hard/Good/Easy, Esc to back"
+        }
+        CurrentScreen::LearningMode
+            if app.learning_state.set.is_some() && app.learning_state.current_item().is_none() =>
+        {
+            "Esc to back"
+        }
+        CurrentScreen::LearningMode if app.learning_state.is_multiple_choice() => {
+            if app.learning_state.show_back {
+                "Enter: Next, Esc to back"
+            } else {
+                "1-9: Choose, ↑/↓ + Enter: Choose highlighted, Esc to back"
+            }
+        }
+        CurrentScreen::LearningMode
+            if app.learning_state.show_back && app.learning_state.current_question().is_some() =>
+        {
+            "Enter: Next, Esc to back"
+        }
+        CurrentScreen::LearningMode if app.learning_state.show_back => {
+            "Enter: Next, 1-4: Grade Again/Hard/Good/Easy, Esc to back"
+        }
         CurrentScreen::LearningMode => "Type answer + Enter, Esc to back",
         CurrentScreen::LearningResults => "Press Enter to continue",
-        CurrentScreen::Statistics => "Press Esc to back",
-        CurrentScreen::Settings => "l: Lang, d: Diff, s: Save, Esc: Back",
+        CurrentScreen::Statistics => "Tab: Dashboard/Details, p: Period, m: Mode, h: Heatmap errors/latency, Esc: Back",
+        CurrentScreen::Settings => "l: Lang, d: Diff, m: Mode, c/i/a: Code, s: Save, Esc: Back",
+        CurrentScreen::ProfileSelect if app.profile_state.edit.is_some() => {
+            "Type a name, Enter to confirm, Esc to cancel"
+        }
+        CurrentScreen::ProfileSelect => "↑/↓: Navigate, Enter: Select, n: New, r: Rename, Esc: Back",
         _ => "",
     };
 
@@ -78,12 +133,14 @@ fn render_footer(app: &App, frame: &mut Frame, area: Rect) {
 fn render_content(app: &mut App, frame: &mut Frame, area: Rect) {
     match app.current_screen {
         CurrentScreen::Menu => render_menu(app, frame, area),
-        CurrentScreen::TypingTest => render_typing_test(app, frame, area),
+        CurrentScreen::TypingTest | CurrentScreen::Replay => render_typing_test(app, frame, area),
         CurrentScreen::TypingResults => render_typing_results(app, frame, area),
         CurrentScreen::Statistics => render_statistics(app, frame, area),
         CurrentScreen::Settings => render_settings(app, frame, area),
+        CurrentScreen::ProfileSelect => render_profiles(app, frame, area),
         CurrentScreen::LearningSelect => render_learning_select(app, frame, area),
         CurrentScreen::LearningMode => render_learning_mode(app, frame, area),
+        CurrentScreen::LearningResults => render_learning_results(app, frame, area),
         _ => render_placeholder(app, frame, area),
     }
 }
@@ -155,6 +212,7 @@ fn render_menu(app: &App, frame: &mut Frame, area: Rect) {
 }
 
 fn render_typing_test(app: &App, frame: &mut Frame, area: Rect) {
+    let state = &app.typing_state;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -164,14 +222,31 @@ fn render_typing_test(app: &App, frame: &mut Frame, area: Rect) {
         .split(area);
 
     // Target Text
+    let title = match (state.remaining_time(), state.mode) {
+        _ if app.current_screen == CurrentScreen::Replay => replay_title(app),
+        (Some(remaining), _) => format!(" Target Text - {}s left ", remaining.as_secs_f64().ceil()),
+        (None, TestMode::Code) => format!(" {} Snippet ", state.code_language),
+        (None, TestMode::Adaptive) => " Practice Weaknesses ".to_string(),
+        (None, _) => " Target Text ".to_string(),
+    };
     let target_block = Block::default()
         .borders(Borders::ALL)
-        .title(" Target Text ");
-    
-    let target_text = Paragraph::new(app.typing_state.target_text.as_str())
+        .title(title);
+
+    // Keep the cursor visible once the text is longer than the box
+    let inner_height = chunks[0].height.saturating_sub(2) as usize;
+    let inner_width = chunks[0].width.saturating_sub(2).max(1) as usize;
+    let typed_graphemes = unicode::grapheme_count(&state.typed_text);
+    let cursor_row = match state.mode {
+        TestMode::Code => unicode::prefix(&state.target_text, typed_graphemes).matches('\n').count(),
+        _ => typed_graphemes / inner_width,
+    };
+    let scroll = cursor_row.saturating_sub(inner_height / 2) as u16;
+
+    let target_text = Paragraph::new(styled_target_lines(app))
         .block(target_block)
-        .wrap(Wrap { trim: true })
-        .style(Style::default().fg(Color::Gray));
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
     
     frame.render_widget(target_text, chunks[0]);
 
@@ -179,26 +254,92 @@ fn render_typing_test(app: &App, frame: &mut Frame, area: Rect) {
     let typed_block = Block::default()
         .borders(Borders::ALL)
         .title(" Your Input ");
-    
-    // Colorize typed text (green for correct, red for wrong)
-    // This is a simplified view; for a real typing test we'd want character-by-character coloring
-    // relative to the target.
-    let typed_text = Paragraph::new(app.typing_state.typed_text.as_str())
+
+    let typed_text = Paragraph::new(state.typed_text.replace('\t', &" ".repeat(app.config.code.indent_width)))
         .block(typed_block)
-        .wrap(Wrap { trim: true })
+        .wrap(Wrap { trim: false })
         .style(Style::default().fg(Color::White));
 
     frame.render_widget(typed_text, chunks[1]);
 }
 
+fn replay_title(app: &App) -> String {
+    match &app.replay_state {
+        Some(replay) => format!(
+            " Replay {} - {:.1}s / {:.1}s - {}x{} ",
+            replay.log.id,
+            replay.position.min(replay.log.duration()).as_secs_f64(),
+            replay.log.duration().as_secs_f64(),
+            replay.speed,
+            if replay.last_tick.is_none() {
+                " (paused)"
+            } else if replay.is_finished() {
+                " (finished)"
+            } else {
+                ""
+            }
+        ),
+        None => " Replay ".to_string(),
+    }
+}
+
+/// Build the target text as styled lines: typed characters are coloured by
+/// correctness, the cursor is highlighted and newlines start a new line.
+fn styled_target_lines(app: &App) -> Vec<Line<'static>> {
+    let theme = &app.config.theme;
+    let correct = Style::default().fg(theme.correct_color.parse().unwrap_or(Color::Green));
+    let error = Style::default()
+        .fg(theme.error_color.parse().unwrap_or(Color::Red))
+        .add_modifier(Modifier::UNDERLINED);
+    let cursor = Style::default()
+        .fg(theme.current_color.parse().unwrap_or(Color::Cyan))
+        .add_modifier(Modifier::REVERSED);
+    let upcoming = Style::default().fg(theme.upcoming_color.parse().unwrap_or(Color::Gray));
+    let tab = " ".repeat(app.config.code.indent_width);
+
+    let typed = unicode::graphemes(&app.typing_state.typed_text);
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+
+    for (i, target) in unicode::graphemes(&app.typing_state.target_text).into_iter().enumerate() {
+        let style = match typed.get(i).map(|g| unicode::compare(g, Some(target))) {
+            Some(GraphemeMatch::Correct) => correct,
+            // Still waiting for a combining mark
+            Some(GraphemeMatch::Partial) if i + 1 == typed.len() => cursor,
+            Some(_) => error,
+            None if i == typed.len() => cursor,
+            None => upcoming,
+        };
+
+        match target {
+            "\n" | "\r\n" => {
+                // Show the Enter key where a newline is expected or mistyped
+                if style == cursor || style == error {
+                    spans.push(Span::styled("⏎", style));
+                }
+                lines.push(Line::from(std::mem::take(&mut spans)));
+            }
+            "\t" => spans.push(Span::styled(tab.clone(), style)),
+            g => spans.push(Span::styled(g.to_string(), style)),
+        }
+    }
+    lines.push(Line::from(spans));
+
+    lines
+}
+
 fn render_typing_results(app: &App, frame: &mut Frame, area: Rect) {
     if let Some(result) = &app.typing_state.result {
-        let text = vec![
+        let mut text = vec![
             Line::from(vec![Span::raw("")]),
             Line::from(vec![Span::styled(
                 format!("WPM: {:.1}", result.wpm),
                 Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
             )]),
+            Line::from(vec![Span::raw(format!(
+                "Raw: {:.1} | Burst: {:.1} | Consistency (CV): {:.1}%",
+                result.raw_wpm, result.burst_wpm, result.consistency
+            ))]),
             Line::from(vec![Span::raw("")]),
             Line::from(vec![Span::styled(
                 format!("Accuracy: {:.1}%", result.accuracy),
@@ -206,22 +347,402 @@ fn render_typing_results(app: &App, frame: &mut Frame, area: Rect) {
             )]),
             Line::from(vec![Span::raw("")]),
             Line::from(vec![Span::raw(format!("Time: {:.2}s", result.duration.as_secs_f64()))]),
+            Line::from(vec![Span::raw(match result.time_limit {
+                Some(secs) => format!("Mode: {} ({}s)", result.mode, secs),
+                None => format!("Mode: {}", result.mode),
+            })]),
             Line::from(vec![Span::raw("")]),
-            Line::from(vec![Span::styled(
+        ];
+        match &app.typing_state.feedback {
+            Some(feedback) => text.extend(feedback_lines(app, result, feedback)),
+            None => text.push(Line::from(vec![Span::styled(
                 result.rating(),
                 Style::default().fg(Color::Yellow),
-            )]),
-        ];
+            )])),
+        }
+        text.push(Line::from(vec![Span::raw("")]));
+        text.push(Line::from(vec![Span::styled(
+            format!("Replay ID: {}", app.typing_state.result_id.as_deref().unwrap_or("-")),
+            Style::default().fg(Color::DarkGray),
+        )]));
+        if let Some(reason) = &app.typing_state.score_rejection {
+            text.push(Line::from(vec![Span::styled(
+                reason.clone(),
+                Style::default().fg(Color::Red),
+            )]));
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(1),    // Results
+                Constraint::Length(5), // WPM per second
+                Constraint::Length(6), // Key analysis
+            ])
+            .split(area);
 
         let paragraph = Paragraph::new(text)
             .block(Block::default().borders(Borders::ALL).title(" Results "))
             .alignment(Alignment::Center);
 
-        frame.render_widget(paragraph, area);
+        frame.render_widget(paragraph, chunks[0]);
+
+        let series: Vec<u64> = result.wpm_series.iter().map(|wpm| wpm.round() as u64).collect();
+        let sparkline = Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " WPM per second (peak {:.0}) ",
+                result.burst_wpm
+            )))
+            .data(&series)
+            .style(Style::default().fg(Color::Green));
+        frame.render_widget(sparkline, chunks[1]);
+
+        if let Some(analysis) = &app.typing_state.analysis {
+            render_key_analysis(app, frame, chunks[2], analysis, app.typing_state.language, 1);
+        }
+    }
+}
+
+/// Personal best, rank and goal progress of a finished test.
+fn feedback_lines(app: &App, result: &TestResult, feedback: &RunFeedback) -> Vec<Line<'static>> {
+    let style = if feedback.is_personal_best() {
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Yellow)
+    };
+    let mode = match result.time_limit {
+        Some(secs) => format!("{} {}s", result.mode.code(), secs),
+        None => result.mode.code().to_string(),
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(feedback.summary(), style)),
+        Line::from(format!(
+            "Rank {} of {} ({}, {}, {})",
+            feedback.rank,
+            feedback.total,
+            app.typing_state.language.name(),
+            app.typing_state.difficulty.code(),
+            mode
+        )),
+    ];
+
+    for progress in &feedback.goals {
+        let filled = (progress.wpm_progress * 10.0).round() as usize;
+        let accuracy = if progress.accuracy_met { "✓" } else { "✗" };
+        lines.push(Line::from(format!(
+            "Goal {}: {}{} {:.0}% · accuracy {}",
+            progress.goal,
+            "█".repeat(filled),
+            "░".repeat(10 - filled),
+            progress.wpm_progress * 100.0,
+            accuracy
+        )));
+        if progress.reached {
+            let message = if progress.reached_before {
+                "Goal reached again!"
+            } else {
+                "Goal reached for the first time!"
+            };
+            lines.push(Line::from(Span::styled(
+                message,
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            )));
+        }
     }
+    lines
+}
+
+/// Render a keyboard heatmap next to the weakest keys and slowest n-grams.
+///
+/// # Arguments
+///
+/// * `analysis` - Key statistics to show
+/// * `language` - Selects the keyboard layout (QWERTY/QWERTZ)
+/// * `min_samples` - Minimum measurements for an n-gram to be listed
+fn render_key_analysis(
+    app: &App,
+    frame: &mut Frame,
+    area: Rect,
+    analysis: &KeystrokeAnalysis,
+    language: Language,
+    min_samples: usize,
+) {
+    let title = if analysis.tests > 1 {
+        format!(" Key Heatmap ({}, {} tests) ", app.heatmap_metric.label(), analysis.tests)
+    } else {
+        format!(" Key Heatmap ({}) ", app.heatmap_metric.label())
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(KeyboardHeatmap::width() + 2), // Keyboard
+            Constraint::Min(1),                               // N-grams
+        ])
+        .split(inner);
+
+    frame.render_widget(
+        KeyboardHeatmap::new(analysis, language, app.heatmap_metric),
+        chunks[0],
+    );
+    frame.render_widget(
+        Paragraph::new(heatmap::summary_lines(analysis, min_samples)),
+        chunks[1],
+    );
 }
 
 fn render_statistics(app: &App, frame: &mut Frame, area: Rect) {
+    match app.statistics_state.view {
+        StatsView::Dashboard => render_dashboard(app, frame, area),
+        StatsView::Details => render_statistics_details(app, frame, area),
+    }
+}
+
+/// Colour of a day in the practice calendar.
+fn calendar_color(tests: u64) -> Color {
+    match tests {
+        0 => Color::DarkGray,
+        1 => Color::Green,
+        2..=3 => Color::LightGreen,
+        _ => Color::Yellow,
+    }
+}
+
+fn render_dashboard(app: &App, frame: &mut Frame, area: Rect) {
+    let state = &app.statistics_state;
+    let dashboard = &state.dashboard;
+    let mode = state.mode.map(|m| m.to_string()).unwrap_or_else(|| "all modes".to_string());
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),  // Summary
+            Constraint::Min(8),     // Charts
+            Constraint::Length(11), // Breakdowns and practice
+        ])
+        .split(area);
+
+    let summary_text = match &state.stats_summary {
+        Some(stats) if stats.total_tests > 0 => vec![
+            Line::from(format!(
+                "Tests: {} | Avg WPM: {:.1} | Avg Accuracy: {:.1}% | Best WPM: {:.1} | Trend: {:+.2} WPM per week",
+                stats.total_tests, stats.avg_wpm, stats.avg_accuracy, stats.best_wpm, stats.wpm_per_week
+            )),
+            distribution_line(stats),
+            Line::from(format!(
+                "Streak: {} day(s) | Longest: {} day(s) | Time typed: {:.0} min",
+                dashboard.current_streak,
+                dashboard.longest_streak,
+                stats.total_time_secs / 60.0
+            )),
+        ],
+        _ => vec![Line::from("No results in this period.")],
+    };
+    let summary = Paragraph::new(summary_text)
+        .block(Block::default().borders(Borders::ALL).title(format!(" {}, {} ", state.period, mode)))
+        .alignment(Alignment::Center);
+    frame.render_widget(summary, chunks[0]);
+
+    let charts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[1]);
+    render_progress_chart(dashboard, frame, charts[0]);
+    render_wpm_histogram(dashboard, frame, charts[1]);
+
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(34),
+            Constraint::Percentage(33),
+            Constraint::Percentage(33),
+        ])
+        .split(chunks[2]);
+
+    let mut breakdown = Vec::new();
+    for category in dashboard.by_language.iter().chain(&dashboard.by_difficulty) {
+        breakdown.push(Line::from(vec![
+            Span::styled(format!("{:<8}", category.label), Style::default().fg(Color::Yellow)),
+            Span::raw(format!(
+                "{:>4} tests {:>5.1} avg {:>5.1} best {:>5.1}%",
+                category.tests, category.avg_wpm, category.best_wpm, category.avg_accuracy
+            )),
+        ]));
+    }
+    let breakdown = Paragraph::new(breakdown)
+        .block(Block::default().borders(Borders::ALL).title(" Languages & Difficulties "));
+    frame.render_widget(breakdown, panels[0]);
+
+    let bests: Vec<Line> = dashboard
+        .personal_bests
+        .iter()
+        .map(|score| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>6.1} WPM ", score.wpm),
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    "{} {} {} - {}",
+                    score.language.code(),
+                    score.difficulty.code(),
+                    score.mode_label(),
+                    score.local_time()
+                )),
+            ])
+        })
+        .collect();
+    let bests = Paragraph::new(bests)
+        .block(Block::default().borders(Borders::ALL).title(" Personal Bests "));
+    frame.render_widget(bests, panels[1]);
+
+    render_practice_calendar(dashboard, frame, panels[2]);
+}
+
+/// WPM and accuracy of every result in the period as a line chart.
+fn render_progress_chart(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" WPM & Accuracy over time ");
+    if dashboard.tests == 0 {
+        frame.render_widget(block, area);
+        return;
+    }
+
+    let max_wpm = dashboard.wpm_points.iter().map(|&(_, wpm)| wpm).fold(100.0, f64::max);
+    let y_max = (max_wpm / 10.0).ceil() * 10.0;
+    let x_max = (dashboard.tests as f64).max(2.0);
+    let day_label = |day: Option<chrono::NaiveDate>| {
+        Span::raw(day.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default())
+    };
+
+    let datasets = vec![
+        Dataset::default()
+            .name("WPM")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&dashboard.wpm_points),
+        Dataset::default()
+            .name(format!("{}-day avg", dashboard::ROLLING_DAYS))
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&dashboard.rolling_points),
+        Dataset::default()
+            .name("Accuracy %")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&dashboard.accuracy_points),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([1.0, x_max])
+                .labels(vec![day_label(dashboard.first_day), day_label(dashboard.last_day)]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, y_max])
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format!("{:.0}", y_max / 2.0)),
+                    Span::raw(format!("{:.0}", y_max)),
+                ]),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Number of results per WPM bucket.
+fn render_wpm_histogram(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let labels: Vec<String> = dashboard
+        .wpm_histogram
+        .iter()
+        .map(|(wpm, _)| wpm.to_string())
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(&dashboard.wpm_histogram)
+        .map(|(label, &(_, count))| (label.as_str(), count))
+        .collect();
+
+    // Make the bars as wide as the area allows
+    let buckets = data.len().max(1) as u16;
+    let bar_width = (area.width.saturating_sub(2) / buckets).saturating_sub(1).clamp(1, 6);
+
+    let chart = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title(" WPM distribution "))
+        .data(&data)
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    frame.render_widget(chart, area);
+}
+
+/// Streak, tests per day of the last weeks and a calendar of practice days.
+fn render_practice_calendar(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" Daily Practice ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)])
+        .split(inner);
+
+    let today = chrono::Local::now().date_naive();
+    let counts = dashboard.daily_counts(today, rows[0].width as i64);
+    frame.render_widget(
+        Sparkline::default()
+            .data(&counts)
+            .style(Style::default().fg(Color::Green)),
+        rows[0],
+    );
+
+    // One column per week (two cells wide), one row per weekday
+    let weeks = (rows[1].width / 2).max(1) as i64;
+    let this_monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+    let first_monday = this_monday - chrono::Duration::weeks(weeks - 1);
+    let calendar: Vec<Line> = (0..7)
+        .map(|weekday| {
+            let cells: Vec<Span> = (0..weeks)
+                .map(|week| {
+                    let day = first_monday + chrono::Duration::days(week * 7 + weekday);
+                    if day > today {
+                        return Span::raw("  ");
+                    }
+                    let tests = dashboard.daily_tests.get(&day).copied().unwrap_or(0);
+                    Span::styled("■ ", Style::default().fg(calendar_color(tests)))
+                })
+                .collect();
+            Line::from(cells)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(calendar), rows[1]);
+}
+
+/// Spread and recent level of the WPM.
+fn distribution_line(stats: &HighScoreStatistics) -> Line<'static> {
+    Line::from(format!(
+        "Median: {:.1} (p10 {:.1}, p90 {:.1}) | Std dev: {:.1} | 7 days: {:.1} | 30 days: {:.1} | Acc-weighted: {:.1}",
+        stats.median_wpm,
+        stats.p10_wpm,
+        stats.p90_wpm,
+        stats.wpm_std_dev,
+        stats.rolling_7d_wpm,
+        stats.rolling_30d_wpm,
+        stats.weighted_wpm
+    ))
+}
+
+fn render_statistics_details(app: &App, frame: &mut Frame, area: Rect) {
     use ratatui::widgets::{Table, Row};
 
     if app.statistics_state.highscores.is_empty() {
@@ -237,53 +758,98 @@ fn render_statistics(app: &App, frame: &mut Frame, area: Rect) {
             score.name.clone(),
             format!("{:.1}", score.wpm),
             format!("{:.1}%", score.accuracy),
-            score.difficulty.clone(),
-            score.language.clone(),
-            score.timestamp.clone(),
+            score.difficulty.to_string(),
+            score.language.code().to_string(),
+            score.mode_label(),
+            score.local_time(),
         ])
     }).collect();
 
+    let summary_text = match &app.statistics_state.stats_summary {
+        Some(stats) => {
+            let mut lines = vec![
+                Line::from(format!(
+                    "Total Tests: {} | Avg WPM: {:.1} | Avg Accuracy: {:.1}% | Best WPM: {:.1}",
+                    stats.total_tests, stats.avg_wpm, stats.avg_accuracy, stats.best_wpm
+                )),
+                Line::from(format!(
+                    "Last {}: {:.1} WPM | Trend: {:+.2} WPM per test, {:+.2} per week | Time typed: {:.0} min",
+                    RECENT_TESTS.min(stats.total_tests),
+                    stats.recent_avg_wpm,
+                    stats.wpm_trend,
+                    stats.wpm_per_week,
+                    stats.total_time_secs / 60.0
+                )),
+                distribution_line(stats),
+            ];
+            let categories = stats
+                .by_language
+                .iter()
+                .map(|(language, stats)| (language.name(), stats))
+                .chain(stats.by_difficulty.iter().map(|(difficulty, stats)| (difficulty.code(), stats)));
+            for (label, stats) in categories {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:<8}", label), Style::default().fg(Color::Yellow)),
+                    Span::raw(format!(
+                        "{:>4} tests | median {:>5.1} | p10-p90 {:>5.1}-{:<5.1} | std dev {:>4.1} | {:+.2} WPM per week",
+                        stats.total_tests,
+                        stats.median_wpm,
+                        stats.p10_wpm,
+                        stats.p90_wpm,
+                        stats.wpm_std_dev,
+                        stats.wpm_per_week
+                    )),
+                ]));
+            }
+            lines
+        }
+        None => vec![Line::from("No result history yet.")],
+    };
+    let summary_height = summary_text.len() as u16 + 2;
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(5), // Summary
-            Constraint::Min(1),    // Table
+            Constraint::Length(summary_height), // Summary
+            Constraint::Length(6),              // Key analysis
+            Constraint::Min(1),                 // Table
         ])
         .split(area);
 
-    // Calculate summary
-    let total_tests = app.statistics_state.highscores.len();
-    let avg_wpm = app.statistics_state.highscores.iter().map(|s| s.wpm).sum::<f64>() / total_tests as f64;
-    let avg_acc = app.statistics_state.highscores.iter().map(|s| s.accuracy).sum::<f64>() / total_tests as f64;
-    let best_wpm = app.statistics_state.highscores.iter().map(|s| s.wpm).fold(0.0f64, f64::max);
-
-    let summary_text = format!(
-        "Total Tests: {} | Avg WPM: {:.1} | Avg Accuracy: {:.1}% | Best WPM: {:.1}",
-        total_tests, avg_wpm, avg_acc, best_wpm
-    );
-
     let summary = Paragraph::new(summary_text)
         .block(Block::default().borders(Borders::ALL).title(" Summary "))
         .alignment(Alignment::Center);
     
     frame.render_widget(summary, chunks[0]);
 
+    let analysis = &app.statistics_state.key_analysis;
+    if analysis.tests > 0 {
+        let language = app.config.defaults.language.parse().unwrap_or(Language::English);
+        render_key_analysis(app, frame, chunks[1], analysis, language, 3);
+    } else {
+        let paragraph = Paragraph::new("No recorded keystrokes yet.")
+            .block(Block::default().borders(Borders::ALL).title(" Key Heatmap "))
+            .alignment(Alignment::Center);
+        frame.render_widget(paragraph, chunks[1]);
+    }
+
     let table = Table::new(
         rows,
         [
-            Constraint::Percentage(20),
+            Constraint::Percentage(15),
             Constraint::Percentage(10),
             Constraint::Percentage(10),
             Constraint::Percentage(15),
-            Constraint::Percentage(10),
-            Constraint::Percentage(35),
+            Constraint::Percentage(8),
+            Constraint::Percentage(12),
+            Constraint::Percentage(30),
         ]
     )
-    .header(Row::new(vec!["Name", "WPM", "Acc", "Diff", "Lang", "Date"])
+    .header(Row::new(vec!["Name", "WPM", "Acc", "Diff", "Lang", "Mode", "Date"])
         .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
     .block(Block::default().borders(Borders::ALL).title(" Highscores "));
 
-    frame.render_widget(table, chunks[1]);
+    frame.render_widget(table, chunks[2]);
 }
 
 fn render_settings(app: &App, frame: &mut Frame, area: Rect) {
@@ -307,6 +873,33 @@ fn render_settings(app: &App, frame: &mut Frame, area: Rect) {
             Span::raw(" (Press 'd' to toggle)"),
         ]),
         Line::from(vec![Span::raw("")]),
+        Line::from(vec![
+            Span::raw("Mode: "),
+            Span::styled(
+                if app.config.defaults.mode == TestMode::Time.code() {
+                    format!("{} ({}s)", app.config.defaults.mode, app.config.defaults.time_limit)
+                } else {
+                    app.config.defaults.mode.clone()
+                },
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" (Press 'm' to toggle)"),
+        ]),
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![
+            Span::raw("Code: "),
+            Span::styled(
+                format!(
+                    "{}, indent: {}, auto-indent: {}",
+                    app.config.code.language,
+                    app.config.code.indent_style,
+                    if app.config.code.auto_indent { "on" } else { "off" }
+                ),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" (Press 'c', 'i', 'a' to toggle)"),
+        ]),
+        Line::from(vec![Span::raw("")]),
         Line::from(vec![Span::raw("Press 's' to save configuration")]),
     ];
 
@@ -317,18 +910,92 @@ fn render_settings(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(paragraph, area);
 }
 
+fn render_profiles(app: &App, frame: &mut Frame, area: Rect) {
+    let state = &app.profile_state;
+    let current = app.profiles.current().map(|p| p.name.as_str());
+
+    let mut lines: Vec<Line> = app
+        .profiles
+        .profiles()
+        .iter()
+        .enumerate()
+        .map(|(i, profile)| {
+            let style = if i == state.cursor {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let marker = if Some(profile.name.as_str()) == current { "* " } else { "  " };
+            Line::from(vec![
+                Span::styled(format!("{}{}", marker, profile.name), style),
+                Span::styled(
+                    format!("  ({}, {})", profile.language, profile.difficulty),
+                    Style::default().fg(Color::Gray),
+                ),
+            ])
+        })
+        .collect();
+
+    if lines.is_empty() {
+        lines.push(Line::from("No profiles yet. Press 'n' to create one."));
+    }
+
+    if let Some(edit) = state.edit {
+        let label = match edit {
+            ProfileEdit::Create => "New profile: ",
+            ProfileEdit::Rename => "New name: ",
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::raw(label),
+            Span::styled(
+                format!("{}_", state.input),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+
+    if let Some(error) = &state.error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(" Who is typing? "));
+
+    frame.render_widget(paragraph, area);
+}
+
 fn render_learning_mode(app: &App, frame: &mut Frame, area: Rect) {
-    if let Some(set) = &app.learning_state.set {
-        if app.learning_state.current_card_index >= set.cards.len() {
-             let paragraph = Paragraph::new("Learning Session Complete!")
+    let state = &app.learning_state;
+    if state.set.is_some() {
+        let Some(item) = state.current_item() else {
+            let mut lines = vec![Line::from("No cards are due today.")];
+            if let Some(scheduler) = &state.scheduler {
+                let summary = scheduler.summary();
+                lines.push(Line::from(mastery_line(
+                    &scheduler.algorithm().to_string(),
+                    summary.mastery_percentage(),
+                    &summary.box_counts,
+                    summary.new_items,
+                )));
+                if let Some(next) = scheduler.next_due_after(chrono::Utc::now()) {
+                    lines.push(Line::from(format!(
+                        "Next review: {}",
+                        next.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+                    )));
+                }
+            }
+            if let Some(error) = &state.error {
+                lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+            }
+            let paragraph = Paragraph::new(lines)
                 .block(Block::default().borders(Borders::ALL))
                 .alignment(Alignment::Center);
             frame.render_widget(paragraph, area);
             return;
-        }
+        };
 
-        let card = &set.cards[app.learning_state.current_card_index];
-        
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -339,11 +1006,32 @@ fn render_learning_mode(app: &App, frame: &mut Frame, area: Rect) {
             .split(area);
 
         // Question
+        let mut title = match item {
+            SessionItem::Card(_) => " Card",
+            SessionItem::Question(_) => " Question",
+        }
+        .to_string();
+        title.push_str(&format!(" {}/{} ", state.current_card_index + 1, state.order.len()));
+        if let (SessionItem::Card(index), Some(scheduler)) = (item, &state.scheduler) {
+            title.push_str(&format!("- {} ", scheduler.describe(index)));
+        }
+        if state.is_multiple_choice() {
+            render_quiz_question(state, title, frame, area);
+            return;
+        }
+        let Some((prompt, answer, explanation)) = state.current_prompt() else {
+            return;
+        };
         let question_block = Block::default()
             .borders(Borders::ALL)
-            .title(format!(" Card {}/{} ", app.learning_state.current_card_index + 1, set.cards.len()));
-        
-        let question_text = Paragraph::new(card.front.as_str())
+            .title(title);
+
+        let mut question_lines = vec![Line::from(prompt)];
+        if let Some(error) = &state.error {
+            question_lines.push(Line::from(""));
+            question_lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+        }
+        let question_text = Paragraph::new(question_lines)
             .block(question_block)
             .wrap(Wrap { trim: true })
             .alignment(Alignment::Center);
@@ -375,14 +1063,17 @@ fn render_learning_mode(app: &App, frame: &mut Frame, area: Rect) {
                 };
                 
                 let result_text = crate::modules::learning::fuzzy::format_match_result(match_result);
-                let full_text = format!("{}\n\nCorrect Answer: {}", result_text, card.back);
+                let mut full_text = format!("{}\n\nCorrect Answer: {}", result_text, answer);
+                if let Some(explanation) = explanation {
+                    full_text.push_str(&format!("\n\n{}", explanation));
+                }
                 
                 Paragraph::new(full_text)
                     .block(feedback_block)
                     .wrap(Wrap { trim: true })
                     .style(Style::default().fg(color))
             } else {
-                 Paragraph::new(card.back.as_str())
+                 Paragraph::new(answer)
                     .block(feedback_block)
                     .wrap(Wrap { trim: true })
             };
@@ -397,9 +1088,238 @@ fn render_learning_mode(app: &App, frame: &mut Frame, area: Rect) {
     }
 }
 
+/// One line with the mastery of a set, e.g. "leitner: 40% mastered, boxes: [3, 2, 0]".
+fn mastery_line(algorithm: &str, mastery: f64, box_counts: &[usize], new_items: usize) -> String {
+    let mut line = format!("{}: {:.0}% mastered", algorithm, mastery);
+    if box_counts.is_empty() {
+        line.push_str(&format!(", {} new", new_items));
+    } else {
+        line.push_str(&format!(", boxes: {:?}", box_counts));
+    }
+    line
+}
+
+/// Results of a finished learning session: accuracy, time, the hardest
+/// cards, how the cards moved between the boxes and the earlier sessions
+/// with the same set.
+fn render_learning_results(app: &App, frame: &mut Frame, area: Rect) {
+    let state = &app.learning_state;
+    let Some(session) = &state.session else {
+        return;
+    };
+    let stats = &session.stats;
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            session.set_name.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "Accuracy: {:.0}% ({}/{} correct)",
+                session.accuracy(),
+                stats.correct,
+                stats.total_reviewed
+            ),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )),
+    ];
+    if session.quiz_total > 0 {
+        lines.push(Line::from(format!(
+            "Quiz: {}/{} correct",
+            session.quiz_correct, session.quiz_total
+        )));
+    }
+    lines.push(Line::from(format!("Overrides: {}", stats.user_overrides)));
+    lines.push(Line::from(format!(
+        "Time: {} ({:.1}s per answer)",
+        format_duration(session.duration_secs),
+        session.secs_per_item()
+    )));
+
+    if let Some(scheduler) = &state.scheduler {
+        let summary = scheduler.summary();
+        lines.push(Line::from(""));
+        let mut mastery = format!("{}: ", scheduler.algorithm());
+        if let Some(before) = state.mastery_before {
+            mastery.push_str(&format!("{:.0}% → ", before));
+        }
+        mastery.push_str(&format!("{:.0}% mastered", summary.mastery_percentage()));
+        lines.push(Line::from(mastery));
+        let movement = &session.movement;
+        lines.push(Line::from(format!(
+            "Moved up: {} · down: {} · unchanged: {}",
+            movement.moved_up, movement.moved_down, movement.unchanged
+        )));
+        if !summary.box_counts.is_empty() {
+            lines.push(Line::from(format!(
+                "Boxes: {:?} → {:?}",
+                state.box_counts_before, summary.box_counts
+            )));
+        }
+        if let Some(next) = scheduler.next_due_after(chrono::Utc::now()) {
+            lines.push(Line::from(format!(
+                "Next review: {}",
+                next.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            )));
+        }
+    }
+    if let Some(error) = &state.error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(" Session "))
+        .alignment(Alignment::Center);
+    frame.render_widget(paragraph, chunks[0]);
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+
+    let hardest = hardest_items(&state.hard_items, super::HARDEST_ITEMS);
+    let mut hard_lines: Vec<Line> = hardest
+        .iter()
+        .map(|item| {
+            Line::from(vec![
+                Span::styled(format!("{} ", item.grade), Style::default().fg(Color::Red)),
+                Span::raw(item.prompt.clone()),
+                Span::styled(format!(" → {}", item.answer), Style::default().fg(Color::Gray)),
+            ])
+        })
+        .collect();
+    if hard_lines.is_empty() {
+        hard_lines.push(Line::from("Every answer was right the first time."));
+    }
+    let paragraph = Paragraph::new(hard_lines)
+        .block(Block::default().borders(Borders::ALL).title(" Hardest "))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, right[0]);
+
+    let mut history_lines: Vec<Line> = state
+        .set_history
+        .iter()
+        .rev()
+        .take(right[1].height.saturating_sub(3) as usize)
+        .map(|session| {
+            let mut line = format!(
+                "{}  {:>3} answers  {:>3.0}%  {:>7}",
+                session.local_time(),
+                session.stats.total_reviewed,
+                session.accuracy(),
+                format_duration(session.duration_secs)
+            );
+            if let Some(mastery) = session.mastery {
+                line.push_str(&format!("  {:.0}% mastered", mastery));
+            }
+            Line::from(line)
+        })
+        .collect();
+    if let Some(trend) = set_trends(&state.set_history).first() {
+        history_lines.insert(
+            0,
+            Line::from(Span::styled(
+                format!(
+                    "{} sessions, accuracy {:.0}% → {:.0}% ({:+.0}), {} total",
+                    trend.sessions,
+                    trend.first_accuracy,
+                    trend.last_accuracy,
+                    trend.accuracy_change(),
+                    format_duration(trend.duration_secs)
+                ),
+                Style::default().fg(Color::Yellow),
+            )),
+        );
+    }
+    let paragraph = Paragraph::new(history_lines)
+        .block(Block::default().borders(Borders::ALL).title(" History "));
+    frame.render_widget(paragraph, right[1]);
+}
+
+/// A multiple-choice question: the numbered options, and after answering the
+/// correct option and the explanation.
+fn render_quiz_question(state: &LearningState, title: String, frame: &mut Frame, area: Rect) {
+    let Some((_, question)) = state.current_question() else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(30), // Question
+            Constraint::Percentage(40), // Options
+            Constraint::Percentage(30), // Result/Explanation
+        ])
+        .split(area);
+
+    let mut question_lines = vec![Line::from(question.question.as_str())];
+    if let Some(error) = &state.error {
+        question_lines.push(Line::from(""));
+        question_lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+    let question_text = Paragraph::new(question_lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: true })
+        .alignment(Alignment::Center);
+    frame.render_widget(question_text, chunks[0]);
+
+    let options: Vec<Line> = state
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let style = if state.show_back {
+                if question.is_correct_option(option) {
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+                } else if state.chosen_option == Some(i) {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                }
+            } else if i == state.selected_option {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let marker = if !state.show_back && i == state.selected_option { ">" } else { " " };
+            Line::from(Span::styled(format!("{} {}. {}", marker, i + 1, option), style))
+        })
+        .collect();
+    let options = Paragraph::new(options)
+        .block(Block::default().borders(Borders::ALL).title(" Options "))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(options, chunks[1]);
+
+    if state.show_back {
+        let correct = state.answer_is_correct() == Some(true);
+        let (verdict, color) = if correct {
+            ("✓ Correct!".to_string(), Color::Green)
+        } else {
+            (format!("✗ Wrong - Correct Answer: {}", question.correct_answer), Color::Red)
+        };
+        let mut lines = vec![Line::from(Span::styled(verdict, Style::default().fg(color)))];
+        if let Some(explanation) = &question.explanation {
+            lines.push(Line::from(""));
+            lines.push(Line::from(explanation.as_str()));
+        }
+        let result = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(" Result "))
+            .wrap(Wrap { trim: true });
+        frame.render_widget(result, chunks[2]);
+    }
+}
+
 fn render_placeholder(_app: &App, frame: &mut Frame, area: Rect) {
     let paragraph = Paragraph::new("Not implemented")
         .block(Block::default().borders(Borders::ALL))
         .alignment(Alignment::Center);
     frame.render_widget(paragraph, area);
 }
+
@@ -0,0 +1,404 @@
+//! Portable bundles of user data.
+//!
+//! `rut export` writes the highscores, result history, learning progress and
+//! settings of a profile into one versioned JSON file; `rut import` reads it
+//! on another machine. Importing either merges the results into the existing
+//! history, skipping results that are already there, or replaces the history,
+//! the learning progress and the settings. Paths in the settings are never imported, they are specific to
+//! the machine.
+
+use crate::core::{Config, Result, UtilError};
+use crate::modules::learning::progress::LearningProgress;
+use crate::modules::typing::highscore::{HighScore, HighScoreManager};
+use crate::utils::fs as safe_fs;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Current bundle format version.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Everything `rut export` writes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    /// Format version of the bundle
+    pub version: u32,
+    /// When the bundle was created
+    pub created: DateTime<Utc>,
+    /// Name of the exported profile
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Settings of the exported profile
+    pub config: Config,
+    /// The highscore table
+    #[serde(default)]
+    pub highscores: Vec<HighScore>,
+    /// Every recorded result, oldest first
+    #[serde(default)]
+    pub history: Vec<HighScore>,
+    /// Spaced-repetition progress of the learning sets
+    #[serde(default)]
+    pub learning: LearningProgress,
+}
+
+impl Bundle {
+    /// Collect the data of a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `manager` - Highscore manager of the profile
+    /// * `config` - Configuration with the profile applied
+    /// * `profile` - Name of the profile, if there is one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the highscores, the history or the learning
+    /// progress cannot be read.
+    pub fn export(manager: &HighScoreManager, config: &Config, profile: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            created: Utc::now(),
+            profile: profile.map(str::to_string),
+            config: config.clone(),
+            highscores: manager.load()?,
+            history: manager.load_history()?,
+            learning: manager.storage().load_progress()?,
+        })
+    }
+
+    /// Read a bundle file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or was written
+    /// by a newer version.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)?;
+        let value: serde_json::Value = serde_json::from_slice(&contents).map_err(|e| {
+            UtilError::ImportError(format!("{} is not a bundle: {}", path.display(), e))
+        })?;
+
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version > BUNDLE_VERSION as u64 {
+            return Err(UtilError::ImportError(format!(
+                "{} was written by a newer version (bundle format {}, supported up to {})",
+                path.display(),
+                version,
+                BUNDLE_VERSION
+            )));
+        }
+        serde_json::from_value(value).map_err(|e| {
+            UtilError::ImportError(format!("Invalid bundle {}: {}", path.display(), e))
+        })
+    }
+
+    /// Write the bundle to a file, replacing it atomically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        safe_fs::write_atomic(path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Work out what importing the bundle would change.
+    ///
+    /// # Arguments
+    ///
+    /// * `existing` - The current history, oldest first
+    /// * `progress` - The current learning progress
+    /// * `config` - The current configuration (its paths are kept)
+    /// * `mode` - Merge with or replace the current data
+    pub fn plan(
+        &self,
+        existing: Vec<HighScore>,
+        progress: LearningProgress,
+        config: &Config,
+        mode: ImportMode,
+    ) -> ImportPlan {
+        // Highscores are results as well; older data may only have the table
+        let mut incoming: Vec<HighScore> = Vec::new();
+        let mut seen = ResultSet::default();
+        for score in self.history.iter().chain(&self.highscores) {
+            if !seen.contains(score) {
+                seen.insert(score);
+                incoming.push(score.clone());
+            }
+        }
+
+        let mut known = ResultSet::default();
+        for score in &existing {
+            known.insert(score);
+        }
+        let (new, present): (Vec<HighScore>, Vec<HighScore>) =
+            incoming.iter().cloned().partition(|s| !known.contains(s));
+
+        let (mut results, removed) = match mode {
+            ImportMode::Merge => {
+                let mut results = existing;
+                results.extend(new.iter().cloned());
+                (results, 0)
+            }
+            ImportMode::Replace => {
+                let removed = existing.iter().filter(|s| !seen.contains(s)).count();
+                (incoming, removed)
+            }
+        };
+        results.sort_by_key(|s| s.timestamp);
+
+        let learning = match mode {
+            ImportMode::Merge => {
+                let mut merged = progress.clone();
+                merged.merge(&self.learning);
+                merged
+            }
+            ImportMode::Replace => self.learning.clone(),
+        };
+        let learning = (learning != progress).then_some(learning);
+
+        let settings = (mode == ImportMode::Replace).then(|| Config {
+            paths: config.paths.clone(),
+            ..self.config.clone()
+        });
+
+        ImportPlan {
+            mode,
+            results,
+            added: new.len(),
+            present: present.len(),
+            removed,
+            learning,
+            settings,
+        }
+    }
+}
+
+/// IDs and timestamps of results, to recognise results that were already
+/// imported.
+#[derive(Debug, Default)]
+struct ResultSet {
+    ids: HashSet<String>,
+    timestamps: HashSet<DateTime<Utc>>,
+}
+
+impl ResultSet {
+    fn insert(&mut self, score: &HighScore) {
+        if let Some(id) = &score.id {
+            self.ids.insert(id.clone());
+        }
+        self.timestamps.insert(score.timestamp);
+    }
+
+    /// A result is known if its ID or its timestamp is.
+    fn contains(&self, score: &HighScore) -> bool {
+        score.id.as_ref().is_some_and(|id| self.ids.contains(id))
+            || self.timestamps.contains(&score.timestamp)
+    }
+}
+
+/// How imported data is combined with the existing data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    /// Add results that are not there yet, keep the settings
+    #[default]
+    Merge,
+    /// Replace the history and the settings with those of the bundle
+    Replace,
+}
+
+impl fmt::Display for ImportMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ImportMode::Merge => "merge",
+            ImportMode::Replace => "replace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ImportMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "merge" => Ok(ImportMode::Merge),
+            "replace" => Ok(ImportMode::Replace),
+            _ => Err(format!("Unknown import mode: {} (expected merge or replace)", s)),
+        }
+    }
+}
+
+/// The changes an import makes.
+#[derive(Debug, Clone)]
+pub struct ImportPlan {
+    pub mode: ImportMode,
+    /// The history after the import, oldest first
+    pub results: Vec<HighScore>,
+    /// Number of results that are new
+    pub added: usize,
+    /// Number of results of the bundle that are already there
+    pub present: usize,
+    /// Number of existing results that are dropped
+    pub removed: usize,
+    /// The learning progress after the import, `None` if it is unchanged
+    pub learning: Option<LearningProgress>,
+    /// The settings after the import, `None` if they are kept
+    pub settings: Option<Config>,
+}
+
+impl ImportPlan {
+    /// Whether the import changes any results.
+    pub fn changes_results(&self) -> bool {
+        self.added > 0 || self.removed > 0
+    }
+
+    /// Write the new history and learning progress and rebuild the
+    /// highscore table.
+    ///
+    /// Settings are not written; the caller decides whether they go to the
+    /// profile or the configuration file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history, the table or the progress cannot be
+    /// written.
+    pub fn apply(&self, manager: &HighScoreManager) -> Result<()> {
+        if self.changes_results() {
+            manager.replace_history(&self.results)?;
+        }
+        if let Some(learning) = &self.learning {
+            manager.storage().replace_progress(learning)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ImportPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Results: {} new, {} already present", self.added, self.present)?;
+        if self.mode == ImportMode::Replace {
+            write!(f, ", {} removed", self.removed)?;
+        }
+        writeln!(f, " ({} after import)", self.results.len())?;
+        match &self.learning {
+            Some(learning) => writeln!(
+                f,
+                "Learning progress: {} card(s) in {} set(s) after import",
+                learning.card_count(),
+                learning.sets.len()
+            )?,
+            None => writeln!(f, "Learning progress: unchanged")?,
+        }
+        match self.settings {
+            Some(_) => write!(f, "Settings: replaced with those of the bundle"),
+            None => write!(f, "Settings: kept"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn score(id: Option<&str>, minute: u32, wpm: f64) -> HighScore {
+        HighScore {
+            id: id.map(str::to_string),
+            timestamp: format!("2024-01-01T12:{:02}:00Z", minute).parse().unwrap(),
+            ..HighScore::sample("Player", wpm)
+        }
+    }
+
+    fn bundle(history: Vec<HighScore>) -> Bundle {
+        let mut config = Config::default();
+        config.defaults.time_limit = 60;
+        config.paths.highscore_file = "elsewhere/highscores.json".into();
+        Bundle {
+            version: BUNDLE_VERSION,
+            created: Utc::now(),
+            profile: Some("Player".to_string()),
+            config,
+            highscores: history.clone(),
+            history,
+            learning: LearningProgress::default(),
+        }
+    }
+
+    #[test]
+    fn test_plan() {
+        let existing = vec![score(Some("a"), 0, 40.0), score(None, 1, 45.0)];
+        let bundle = bundle(vec![
+            score(Some("a"), 0, 40.0),
+            score(Some("b"), 1, 45.0),
+            score(Some("c"), 2, 50.0),
+        ]);
+        let config = Config::default();
+
+        let merge = bundle.plan(existing.clone(), LearningProgress::default(), &config, ImportMode::Merge);
+        assert_eq!((merge.added, merge.present, merge.removed), (1, 2, 0));
+        assert_eq!(merge.results.len(), 3);
+        assert_eq!(merge.results[2].id.as_deref(), Some("c"));
+        assert!(merge.settings.is_none());
+
+        let replace = bundle.plan(
+            vec![score(Some("x"), 5, 30.0)],
+            LearningProgress::default(),
+            &config,
+            ImportMode::Replace,
+        );
+        assert_eq!((replace.added, replace.present, replace.removed), (3, 0, 1));
+        let settings = replace.settings.unwrap();
+        assert_eq!(settings.defaults.time_limit, 60);
+        assert_eq!(settings.paths.highscore_file, config.paths.highscore_file);
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let dir = TempDir::new().unwrap();
+        let source = HighScoreManager::new(dir.path().join("a/highscores.json"), 10);
+        source.add_score(score(Some("a"), 0, 40.0)).unwrap();
+        source.add_score(score(Some("b"), 1, 50.0)).unwrap();
+        let mut learning = LearningProgress::default();
+        learning.sets.insert("Capitals".to_string(), Default::default());
+        source.storage().replace_progress(&learning).unwrap();
+
+        let path = dir.path().join("bundle.json");
+        let config = Config::default();
+        Bundle::export(&source, &config, Some("Player"))
+            .unwrap()
+            .write(&path)
+            .unwrap();
+        let bundle = Bundle::read(&path).unwrap();
+        assert_eq!(bundle.history.len(), 2);
+
+        let target = HighScoreManager::new(dir.path().join("b/highscores.json"), 10);
+        target.add_score(score(Some("b"), 1, 50.0)).unwrap();
+        let plan = bundle.plan(
+            target.load_history().unwrap(),
+            target.storage().load_progress().unwrap(),
+            &config,
+            ImportMode::Merge,
+        );
+        plan.apply(&target).unwrap();
+        assert_eq!(target.load_history().unwrap().len(), 2);
+        assert_eq!(target.load().unwrap()[0].wpm, 50.0);
+        assert_eq!(target.storage().load_progress().unwrap(), learning);
+
+        // Importing again changes nothing
+        let again = bundle.plan(
+            target.load_history().unwrap(),
+            target.storage().load_progress().unwrap(),
+            &config,
+            ImportMode::Merge,
+        );
+        assert!(!again.changes_results());
+        assert!(again.learning.is_none());
+
+        std::fs::write(&path, r#"{"version": 99}"#).unwrap();
+        assert!(matches!(Bundle::read(&path), Err(UtilError::ImportError(_))));
+    }
+}
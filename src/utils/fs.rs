@@ -0,0 +1,151 @@
+//! Safe file writing for data files.
+//!
+//! Data files are never written in place: new contents go to a temporary
+//! file in the same directory, which is flushed to disk and then renamed
+//! over the original, so a crash leaves either the old or the new file but
+//! never a truncated one. The previous version is kept as `<file>.bak`.
+//! Read-modify-write cycles are serialised across processes with an
+//! advisory lock on `<file>.lock`.
+
+use crate::core::Result;
+use fs2::FileExt;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `path` with `suffix` appended to the file name (`a.json` → `a.json.bak`).
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Path of the backup kept by [`write_atomic_with_backup`].
+pub fn backup_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    with_suffix(path.as_ref(), ".bak")
+}
+
+/// Replace a file atomically.
+///
+/// The contents are written to a temporary file next to `path`, synced and
+/// renamed over `path`. Missing parent directories are created.
+///
+/// # Errors
+///
+/// Returns an error if the temporary file cannot be written or renamed.
+pub fn write_atomic<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Hidden, per-process name so concurrent writers never share a temp file
+    let mut temp_name = OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+/// Replace a file atomically, keeping the previous version as `<file>.bak`.
+///
+/// # Errors
+///
+/// Returns an error if the backup or the new file cannot be written.
+pub fn write_atomic_with_backup<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        std::fs::copy(path, backup_path(path))?;
+    }
+    write_atomic(path, contents)
+}
+
+/// Exclusive advisory lock for a data file, released when dropped.
+///
+/// The lock is taken on a separate `<file>.lock` file, so the data file
+/// itself can be replaced while the lock is held. Only processes that also
+/// use `FileLock` are kept out.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Lock a data file, waiting until other processes release it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be created or locked.
+    pub fn acquire<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(with_suffix(path, ".lock"))?;
+        file.lock_exclusive()?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_with_backup() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data/scores.json");
+
+        write_atomic_with_backup(&path, b"first").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+        assert!(!backup_path(&path).exists());
+
+        write_atomic_with_backup(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read(backup_path(&path)).unwrap(), b"first");
+
+        // No temporary files are left behind
+        let names: Vec<_> = std::fs::read_dir(dir.path().join("data"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names.len(), 2);
+    }
+
+    #[test]
+    fn test_lock_excludes_other_holders() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("scores.json");
+
+        let lock = FileLock::acquire(&path).unwrap();
+        let other = File::open(with_suffix(&path, ".lock")).unwrap();
+        assert!(other.try_lock_exclusive().is_err());
+
+        drop(lock);
+        assert!(other.try_lock_exclusive().is_ok());
+    }
+}
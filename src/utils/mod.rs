@@ -1,6 +1,10 @@
 //! Utility functions and helpers.
 
+pub mod bundle;
+pub mod fs;
+
+// Re-export commonly used items
+pub use bundle::{Bundle, ImportMode, ImportPlan};
+
 // TODO: Implement utility functions
-// - File I/O helpers
 // - Statistics calculations
-// - Data export/import